@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{env, ffi::OsString, path::Path, process};
 
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -15,8 +15,23 @@ use crate::{
 use super::prepare_awto_dir;
 
 /// Compiles database package from app schema
-#[derive(Parser)]
+#[derive(Default, Parser)]
 pub struct Database {
+    /// Applies destructive changes, such as dropping columns or lossy type casts
+    #[clap(long)]
+    pub allow_destructive: bool,
+    /// Prints the changes which would be made to the database without applying them
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Writes schema changes to versioned migration files in './migrations' instead of applying them
+    #[clap(long)]
+    pub migrations: bool,
+    /// Applies the migration files in './migrations' to the database, including any file written for new changes
+    #[clap(long, requires = "migrations")]
+    pub apply: bool,
+    /// What to do with tables which were removed from the schema
+    #[clap(long, possible_values = &["keep", "drop", "archive"])]
+    pub removed_tables: Option<String>,
     /// Prints more information
     #[clap(short, long)]
     pub verbose: bool,
@@ -25,6 +40,8 @@ pub struct Database {
 #[async_trait]
 impl Runnable for Database {
     async fn run(&mut self) -> Result<()> {
+        dotenv::dotenv().ok();
+
         let cargo_file = CargoFile::load("./schema/Cargo.toml")
             .await
             .context("could not load schema Cargo.toml file from './schema/Cargo.toml'")?;
@@ -45,11 +62,44 @@ impl Runnable for Database {
             }
         }
 
+        let mut envs = Vec::new();
+        let mut existing_migrations = Vec::new();
+        if self.migrations {
+            let migrations_dir = env::current_dir()?.join(Self::MIGRATIONS_DIR);
+            existing_migrations = Self::migration_files(&migrations_dir).await?;
+            envs.push((Self::MIGRATIONS_DIR_ENV, migrations_dir.into_os_string()));
+        }
+
+        if self.apply {
+            envs.push((Self::APPLY_MIGRATIONS_ENV, OsString::from("1")));
+        }
+
+        if self.allow_destructive {
+            envs.push((Self::ALLOW_DESTRUCTIVE_ENV, OsString::from("1")));
+        }
+
+        if let Some(removed_tables) = &self.removed_tables {
+            envs.push((Self::REMOVED_TABLES_ENV, OsString::from(removed_tables)));
+        }
+
+        if self.dry_run {
+            return Self::dry_run(envs).await;
+        }
+
         prepare_awto_dir().await?;
 
         Self::prepare_database_dir().await?;
         add_package_to_workspace("awto/database").await?;
-        build_awto_pkg("database").await?;
+
+        build_awto_pkg("database", &envs).await?;
+
+        if self.migrations {
+            for migration in Self::migration_files(Self::MIGRATIONS_DIR).await? {
+                if !existing_migrations.contains(&migration) {
+                    info!("wrote migration '{}'", migration);
+                }
+            }
+        }
 
         info!("compiled package 'database'");
 
@@ -65,12 +115,67 @@ impl Database {
     const DATABASE_DIR: &'static str = "./awto/database";
     const DATABASE_SRC_DIR: &'static str = "./awto/database/src";
     const DATABASE_CARGO_PATH: &'static str = "./awto/database/Cargo.toml";
-    const DATABASE_CARGO_TOML_BYTES: &'static [u8] =
-        include_bytes!("../templates/database/Cargo.toml.template");
+    const DATABASE_CARGO_TOML: &'static str =
+        include_str!("../templates/database/Cargo.toml.template");
     const DATABASE_BUILD_PATH: &'static str = "./awto/database/build.rs";
     const DATABASE_BUILD_BYTES: &'static [u8] =
         include_bytes!("../templates/database/build.rs.template");
     const DATABASE_LIB_PATH: &'static str = "./awto/database/src/lib.rs";
+    const MIGRATIONS_DIR: &'static str = "./migrations";
+    const MIGRATIONS_DIR_ENV: &'static str = "AWTO_MIGRATIONS_DIR";
+    const APPLY_MIGRATIONS_ENV: &'static str = "AWTO_APPLY_MIGRATIONS";
+    const DRY_RUN_ENV: &'static str = "AWTO_DRY_RUN";
+    const ALLOW_DESTRUCTIVE_ENV: &'static str = "AWTO_ALLOW_DESTRUCTIVE";
+    const REMOVED_TABLES_ENV: &'static str = "AWTO_REMOVED_TABLES";
+
+    /// Builds the existing database package with a dry run, printing the planned changes.
+    async fn dry_run(mut envs: Vec<(&str, OsString)>) -> Result<()> {
+        if !Path::new(Self::DATABASE_DIR).is_dir() {
+            return Err(anyhow!(
+                "package 'database' has not been compiled yet, run 'awto compile database' first"
+            ));
+        }
+
+        let plan_path = env::temp_dir().join(format!("awto-dry-run-{}.txt", process::id()));
+        envs.push((Self::DRY_RUN_ENV, plan_path.clone().into_os_string()));
+
+        build_awto_pkg("database", &envs).await?;
+
+        let plan = fs::read_to_string(&plan_path).await.context(
+            "package 'database' did not write a plan, recompile it with 'awto compile database'",
+        )?;
+        fs::remove_file(&plan_path).await.ok();
+
+        print!("{}", plan);
+
+        Ok(())
+    }
+
+    async fn migration_files(dir: impl AsRef<Path>) -> Result<Vec<String>> {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        if !dir.is_dir() {
+            return Ok(files);
+        }
+
+        let mut entries = fs::read_dir(dir)
+            .await
+            .with_context(|| format!("could not read directory '{}'", dir.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            files.push(entry.file_name().to_string_lossy().into_owned());
+        }
+
+        Ok(files)
+    }
+
+    /// Returns the features of the database package needed to connect to the database in `DATABASE_URL`.
+    fn database_features() -> Vec<&'static str> {
+        match env::var("DATABASE_URL") {
+            Ok(uri) if uri.starts_with("mysql:") || uri.starts_with("mariadb:") => vec!["mysql"],
+            Ok(uri) if uri.starts_with("sqlite:") => vec!["sqlite"],
+            _ => Vec::new(),
+        }
+    }
 
     async fn prepare_database_dir() -> Result<()> {
         if Path::new(Self::DATABASE_DIR).is_dir() {
@@ -87,7 +192,16 @@ impl Database {
             .await
             .with_context(|| format!("could not create directory '{}'", Self::DATABASE_SRC_DIR))?;
 
-        fs::write(Self::DATABASE_CARGO_PATH, Self::DATABASE_CARGO_TOML_BYTES)
+        // SQLite and MySQL support is optional, so each is only enabled for its own databases
+        let features: Vec<_> = Self::database_features()
+            .iter()
+            .map(|feature| format!("{:?}", feature))
+            .collect();
+        let cargo_toml = Self::DATABASE_CARGO_TOML.replace(
+            "default = []",
+            &format!("default = [{}]", features.join(", ")),
+        );
+        fs::write(Self::DATABASE_CARGO_PATH, cargo_toml)
             .await
             .with_context(|| format!("could not write file '{}'", Self::DATABASE_CARGO_PATH))?;
 
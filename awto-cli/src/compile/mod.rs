@@ -1,3 +1,4 @@
+use std::ffi::OsString;
 use std::path::Path;
 use std::process::Stdio;
 
@@ -42,6 +43,7 @@ impl Runnable for Compile {
 
         let mut database = Database {
             verbose: self.verbose,
+            ..Default::default()
         };
         database.run().await?;
 
@@ -69,12 +71,13 @@ async fn prepare_awto_dir() -> Result<()> {
     Ok(())
 }
 
-async fn build_awto_pkg(name: &str) -> Result<()> {
+async fn build_awto_pkg(name: &str, envs: &[(&str, OsString)]) -> Result<()> {
     let status = tokio::process::Command::new("cargo")
         .current_dir("./awto")
         .arg("build")
         .arg("-p")
         .arg(name)
+        .envs(envs.iter().cloned())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
@@ -49,7 +49,7 @@ impl Runnable for Protobuf {
 
         Self::prepare_protobuf_dir().await?;
         add_package_to_workspace("awto/protobuf").await?;
-        build_awto_pkg("protobuf").await?;
+        build_awto_pkg("protobuf", &[]).await?;
 
         info!("compiled package 'protobuf'");
 
@@ -21,9 +21,11 @@ use clap::Parser;
 use colored::Colorize;
 use compile::Compile;
 use log::{error, Level, LevelFilter};
+use migrate::Migrate;
 
 mod compile;
 mod macros;
+mod migrate;
 mod util;
 
 /// Awto cli
@@ -36,6 +38,7 @@ struct Opts {
 #[derive(Parser)]
 enum SubCommand {
     Compile(Compile),
+    Migrate(Migrate),
 }
 
 #[tokio::main]
@@ -48,15 +51,20 @@ async fn main() {
             Some(compile::SubCommand::Protobuf(protobuf)) => runnable_cmd!(protobuf),
             None => runnable_cmd!(compile),
         },
+        SubCommand::Migrate(migrate) => match migrate.subcmd {
+            Some(migrate::SubCommand::Rollback(rollback)) => runnable_cmd!(rollback),
+            None => runnable_cmd!(migrate),
+        },
     };
 
-    let log_level = if cmd.is_verbose() {
-        LevelFilter::Debug
+    let (log_level, sqlx_log_level) = if cmd.is_verbose() {
+        (LevelFilter::Debug, LevelFilter::Info)
     } else {
-        LevelFilter::Info
+        (LevelFilter::Info, LevelFilter::Warn)
     };
     env_logger::Builder::new()
         .filter_level(log_level)
+        .filter_module("sqlx", sqlx_log_level)
         .format(|buf, record| {
             let prefix = match record.level() {
                 Level::Error => "error".red(),
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use clap::{IntoApp, Parser};
+
+use crate::Runnable;
+
+pub use self::rollback::Rollback;
+
+mod rollback;
+
+/// Manages migrations applied to the database
+#[derive(Parser)]
+pub struct Migrate {
+    #[clap(subcommand)]
+    pub subcmd: Option<SubCommand>,
+}
+
+#[derive(Parser)]
+pub enum SubCommand {
+    Rollback(Rollback),
+}
+
+#[async_trait]
+impl Runnable for Migrate {
+    async fn run(&mut self) -> Result<()> {
+        Ok(Migrate::into_app().print_help()?)
+    }
+}
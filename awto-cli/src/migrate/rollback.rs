@@ -0,0 +1,75 @@
+use std::env;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use awto_compile::database::{rollback_database, RollbackOptions, DEFAULT_SCHEMA};
+use clap::Parser;
+use log::{info, warn};
+
+use crate::Runnable;
+
+/// Rolls back the latest migrations applied to the database
+#[derive(Parser)]
+pub struct Rollback {
+    /// Number of migrations to roll back
+    #[clap(long, default_value = "1")]
+    pub steps: usize,
+    /// Rolls back migrations which can't be undone without losing data, such as dropped columns
+    #[clap(long)]
+    pub allow_irreversible: bool,
+    /// Writes the migrations reverting the rolled back migrations to versioned migration files in './migrations'
+    #[clap(long)]
+    pub migrations: bool,
+    /// Prints more information
+    #[clap(short, long)]
+    pub verbose: bool,
+}
+
+#[async_trait]
+impl Runnable for Rollback {
+    async fn run(&mut self) -> Result<()> {
+        dotenv::dotenv().ok();
+        let uri = env::var("DATABASE_URL").context("missing env DATABASE_URL")?;
+        let schema = env::var("DATABASE_SCHEMA").unwrap_or_else(|_| DEFAULT_SCHEMA.to_string());
+        let migrations_dir = if self.migrations {
+            Some(env::current_dir()?.join(Self::MIGRATIONS_DIR))
+        } else {
+            None
+        };
+
+        let rolled_back = rollback_database(
+            &uri,
+            RollbackOptions {
+                steps: self.steps,
+                allow_irreversible: self.allow_irreversible,
+                schema,
+                migrations_dir,
+            },
+        )
+        .await
+        .map_err(|err| anyhow!("{}", err))?;
+
+        if rolled_back.is_empty() {
+            info!("no migrations to roll back");
+        }
+        for migration in &rolled_back {
+            for change in migration.irreversible_changes() {
+                warn!("could not restore data lost by {}", change);
+            }
+            info!(
+                "rolled back migration {} '{}'",
+                migration.version, migration.name
+            );
+        }
+
+        Ok(())
+    }
+
+    fn is_verbose(&self) -> bool {
+        self.verbose
+    }
+}
+
+impl Rollback {
+    const MIGRATIONS_DIR: &'static str = "./migrations";
+}
@@ -11,7 +11,6 @@ use toml_edit::{Document, Value};
 #[derive(Deserialize, Clone, Debug)]
 pub struct CargoFile {
     pub package: Option<CargoPackage>,
-    pub workspace: Option<CargoWorkspace>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -19,15 +18,10 @@ pub struct CargoPackage {
     pub name: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
-pub struct CargoWorkspace {
-    pub members: Vec<String>,
-}
-
 impl CargoFile {
     pub async fn load(path: impl AsRef<Path>) -> Result<CargoFile> {
         let bytes = fs::read(path).await.context("file not found")?;
-        Ok(toml::from_slice(&bytes).context("Cargo.toml file corrupt")?)
+        toml::from_slice(&bytes).context("Cargo.toml file corrupt")
     }
 }
 
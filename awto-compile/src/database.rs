@@ -1,108 +1,689 @@
-use std::{borrow::Cow, env, fmt::Write, io};
+use std::{
+    borrow::Cow,
+    env,
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+};
 
 use awto::{
-    database::{DatabaseColumn, DatabaseDefault, DatabaseTable, DatabaseType},
-    schema::{Model, Role},
+    database::{
+        ColumnChange, ConstraintChange, ConstraintKind, DatabaseColumn, DatabaseConstraint,
+        DatabaseDefault, DatabaseEnum, DatabaseIndex, DatabaseTable, DatabaseType, EnumChange,
+        IndexChange, IndexMethod, SchemaDiff, TableChange,
+    },
+    schema::{Model, Role, RustField},
 };
-use proc_macro2::Literal;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
-use sqlx::{Executor, PgPool};
-use tokio_stream::StreamExt;
+use sqlx::{any::AnyQueryResult, AnyConnection, AnyPool, Connection, Executor};
 
 use crate::{
     error::Error,
     util::{is_ty_option, is_ty_vec, strip_ty_option},
 };
 
-const COMPILED_RUST_FILE: &str = "app.rs";
+use self::{
+    backend::{quote_identifier, quote_literal, Backend},
+    diff::{
+        altered_table, destructive_changes, diff_enum, diff_table, irreversible_changes,
+        DestructiveChange,
+    },
+    migration::{
+        add_enum_values, apply_migration, ensure_history_table, enum_value_statements,
+        fetch_applied_migrations, history_table_exists, next_version, read_migrations,
+        revert_migration, verify_migrations, write_migration, AppliedMigration, Migration,
+        IRREVERSIBLE_PREFIX,
+    },
+    plan::DatabasePlan,
+    registry::{
+        archived_table_name, ensure_registry_table, fetch_registered_tables,
+        update_registered_tables, RemovedTables,
+    },
+    sqlite::REBUILT_TABLE_PREFIX,
+};
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub mod backend;
+pub mod diff;
+pub mod migration;
+pub mod mysql;
+pub mod plan;
+pub mod registry;
+pub mod sqlite;
+
+const COMPILED_RUST_FILE: &str = "app.rs";
+const MIGRATION_NAME: &str = "sync";
+/// Key of the advisory lock held while syncing a database schema, "awto" in ascii.
+const ADVISORY_LOCK_KEY: i32 = 0x6177_746f;
+/// Postgres trigger function setting the column named by its argument to the current time.
+const ON_UPDATE_FUNCTION: &str = "awto_set_updated_at";
+/// Postgres error code of using an enum value within the transaction which added it.
+const UNSAFE_NEW_ENUM_VALUE_USAGE: &str = "55P04";
+/// Schema synced when no other schema is given.
+pub const DEFAULT_SCHEMA: &str = "public";
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct CompileDatabaseResult {
     pub queries_executed: usize,
     pub rows_affected: u64,
+    /// Path of the migration file written, when compiling with a migrations directory.
+    pub migration_file: Option<PathBuf>,
+    /// Planned changes, when compiling as a dry run.
+    pub plan: Option<DatabasePlan>,
+    /// Awto managed tables which no longer exist in the schema.
+    pub removed_tables: Vec<String>,
 }
 
-#[cfg(feature = "async")]
+impl CompileDatabaseResult {
+    fn add_query_results(&mut self, results: &[AnyQueryResult]) {
+        self.queries_executed += results.len();
+        self.rows_affected += results
+            .iter()
+            .fold(0, |acc, result| result.rows_affected() + acc);
+    }
+}
+
+/// Options for [`compile_database_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompileDatabaseOptions {
+    /// Writes schema changes as versioned migration files into this directory.
+    ///
+    /// The database is compared with the schema as it would be after the migration files in the directory,
+    /// and new changes are written to a new file without being applied, unless [`CompileDatabaseOptions::apply_migrations`] is set.
+    pub migrations_dir: Option<PathBuf>,
+    /// Applies the migration files which have not yet been applied to the database,
+    /// along with the file written for new changes, when compiling with a migrations directory.
+    pub apply_migrations: bool,
+    /// Plans the changes without applying them to the database, writing migration files
+    /// or generating code, writing a report of the plan to this file.
+    ///
+    /// Pending migration files are applied in a transaction which is rolled back once the
+    /// changes after them are planned. This fails on MySQL, which can't roll back schema changes,
+    /// and on Postgres when a pending migration uses enum values added by another.
+    pub dry_run: Option<PathBuf>,
+    /// Applies destructive changes, such as dropping columns or lossy type casts.
+    ///
+    /// Without this, compiling fails if the schema requires a destructive change.
+    pub allow_destructive: bool,
+    /// What to do with awto managed tables which no longer exist in the schema.
+    pub removed_tables: RemovedTables,
+    /// Database schema to sync, created if it does not exist.
+    ///
+    /// Defaults to [`DEFAULT_SCHEMA`].
+    pub schema: Option<String>,
+}
+
+impl CompileDatabaseOptions {
+    /// Environment variable read by [`CompileDatabaseOptions::from_env`] for [`CompileDatabaseOptions::migrations_dir`].
+    pub const MIGRATIONS_DIR_ENV: &'static str = "AWTO_MIGRATIONS_DIR";
+    /// Environment variable read by [`CompileDatabaseOptions::from_env`] for [`CompileDatabaseOptions::apply_migrations`].
+    pub const APPLY_MIGRATIONS_ENV: &'static str = "AWTO_APPLY_MIGRATIONS";
+    /// Environment variable read by [`CompileDatabaseOptions::from_env`] for [`CompileDatabaseOptions::dry_run`].
+    pub const DRY_RUN_ENV: &'static str = "AWTO_DRY_RUN";
+    /// Environment variable read by [`CompileDatabaseOptions::from_env`] for [`CompileDatabaseOptions::allow_destructive`].
+    pub const ALLOW_DESTRUCTIVE_ENV: &'static str = "AWTO_ALLOW_DESTRUCTIVE";
+    /// Environment variable read by [`CompileDatabaseOptions::from_env`] for [`CompileDatabaseOptions::removed_tables`].
+    pub const REMOVED_TABLES_ENV: &'static str = "AWTO_REMOVED_TABLES";
+    /// Environment variable read by [`CompileDatabaseOptions::from_env`] for [`CompileDatabaseOptions::schema`].
+    pub const SCHEMA_ENV: &'static str = "DATABASE_SCHEMA";
+
+    /// Loads options from environment variables, as set by the awto cli.
+    pub fn from_env() -> Self {
+        CompileDatabaseOptions {
+            migrations_dir: env::var_os(Self::MIGRATIONS_DIR_ENV).map(PathBuf::from),
+            apply_migrations: env::var_os(Self::APPLY_MIGRATIONS_ENV).is_some(),
+            dry_run: env::var_os(Self::DRY_RUN_ENV).map(PathBuf::from),
+            allow_destructive: env::var_os(Self::ALLOW_DESTRUCTIVE_ENV).is_some(),
+            removed_tables: env::var(Self::REMOVED_TABLES_ENV)
+                .ok()
+                .and_then(|removed_tables| removed_tables.parse().ok())
+                .unwrap_or_default(),
+            schema: env::var(Self::SCHEMA_ENV).ok(),
+        }
+    }
+}
+
+/// Syncs the database with the schema and generates Rust code for the database package.
 pub async fn compile_database(
     uri: &str,
     models: Vec<Model>,
+) -> Result<CompileDatabaseResult, Box<dyn std::error::Error>> {
+    compile_database_with_options(uri, models, CompileDatabaseOptions::default()).await
+}
+
+#[cfg(feature = "async")]
+pub async fn compile_database_with_options(
+    uri: &str,
+    models: Vec<Model>,
+    options: CompileDatabaseOptions,
 ) -> Result<CompileDatabaseResult, Box<dyn std::error::Error>> {
     use tokio::fs;
 
     let out_dir = env::var("OUT_DIR").unwrap();
-    let pool = PgPool::connect(uri).await?;
-    let compiler = DatabaseCompiler::from_pool(&pool, models);
+    let pool = AnyPool::connect(uri).await?;
+    let compiler = DatabaseCompiler::from_pool(&pool, models)
+        .with_removed_tables(options.removed_tables)
+        .with_schema(options.schema.as_deref().unwrap_or(DEFAULT_SCHEMA));
+
+    // Dry runs leave the generated code as it is
+    if options.dry_run.is_none() {
+        let generated_code = compiler.compile_generated_code();
+        if !generated_code.is_empty() {
+            let rs_path = format!("{}/{}", out_dir, COMPILED_RUST_FILE);
+            fs::write(rs_path, generated_code).await?;
+        }
 
-    let generated_code = compiler.compile_generated_code();
-    if !generated_code.is_empty() {
-        let rs_path = format!("{}/{}", out_dir, COMPILED_RUST_FILE);
-        fs::write(rs_path, generated_code).await?;
+        compiler.append_sea_orm_models().await?;
     }
 
-    compiler.append_sea_orm_models().await?;
-
-    let sql = compiler.compile().await?;
-    if !sql.is_empty() {
-        let results = pool
-            .execute_many(sql.as_str())
-            .collect::<Result<Vec<_>, _>>()
-            .await?;
-        let queries_executed = results.len();
-        let rows_affected = results
-            .iter()
-            .fold(0, |acc, result| result.rows_affected() + acc);
-
-        Ok(CompileDatabaseResult {
-            queries_executed,
-            rows_affected,
-        })
-    } else {
-        Ok(CompileDatabaseResult::default())
+    let result = sync_database(&compiler, &options).await?;
+    if let (Some(plan_path), Some(plan)) = (&options.dry_run, &result.plan) {
+        fs::write(plan_path, plan.to_string()).await?;
     }
+
+    Ok(result)
 }
 
 #[cfg(not(feature = "async"))]
-pub async fn compile_database(
+pub async fn compile_database_with_options(
     uri: &str,
     models: Vec<Model>,
+    options: CompileDatabaseOptions,
 ) -> Result<CompileDatabaseResult, Box<dyn std::error::Error>> {
     use std::fs;
 
     let out_dir = env::var("OUT_DIR").unwrap();
-    let pool = PgPool::connect(uri).await?;
-    let compiler = DatabaseCompiler::from_pool(&pool, models);
+    let pool = AnyPool::connect(uri).await?;
+    let compiler = DatabaseCompiler::from_pool(&pool, models)
+        .with_removed_tables(options.removed_tables)
+        .with_schema(options.schema.as_deref().unwrap_or(DEFAULT_SCHEMA));
+
+    // Dry runs leave the generated code as it is
+    if options.dry_run.is_none() {
+        let generated_code = compiler.compile_generated_code();
+        if !generated_code.is_empty() {
+            let rs_path = format!("{}/{}", out_dir, COMPILED_RUST_FILE);
+            fs::write(rs_path, generated_code)?;
+        }
 
-    let generated_code = compiler.compile_generated_code();
-    if !generated_code.is_empty() {
-        let rs_path = format!("{}/{}", out_dir, COMPILED_RUST_FILE);
-        fs::write(rs_path, generated_code)?;
+        compiler.append_sea_orm_models()?;
     }
 
-    compiler.append_sea_orm_models()?;
+    let result = sync_database(&compiler, &options).await?;
+    if let (Some(plan_path), Some(plan)) = (&options.dry_run, &result.plan) {
+        fs::write(plan_path, plan.to_string())?;
+    }
 
-    let sql = compiler.compile().await?;
-    if !sql.is_empty() {
-        let results = pool
-            .execute_many(sql.as_str())
-            .collect::<Result<Vec<_>, _>>()
-            .await?;
-        let queries_executed = results.len();
-        let rows_affected = results
-            .iter()
-            .fold(0, |acc, result| result.rows_affected() + acc);
+    Ok(result)
+}
+
+/// Options for [`rollback_database`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollbackOptions {
+    /// Number of applied migrations to roll back, starting from the latest.
+    pub steps: usize,
+    /// Rolls back migrations with changes which can't be undone without losing data,
+    /// such as restoring a dropped column without its values.
+    pub allow_irreversible: bool,
+    /// Database schema the migrations were applied to.
+    pub schema: String,
+    /// Directory of migration files, into which the migrations reverting the rolled back migrations are written.
+    pub migrations_dir: Option<PathBuf>,
+}
+
+impl Default for RollbackOptions {
+    fn default() -> Self {
+        RollbackOptions {
+            steps: 1,
+            allow_irreversible: false,
+            schema: DEFAULT_SCHEMA.to_string(),
+            migrations_dir: None,
+        }
+    }
+}
+
+/// Rolls back the latest applied migrations by applying new migrations running their down migrations.
+///
+/// Returns the migrations rolled back, latest first.
+/// The migrations reverting them are recorded like any other migration, and written to
+/// [`RollbackOptions::migrations_dir`] once the rollback is committed,
+/// so the files of the rolled back migrations are kept and their versions aren't reused.
+pub async fn rollback_database(
+    uri: &str,
+    options: RollbackOptions,
+) -> Result<Vec<AppliedMigration>, Box<dyn std::error::Error>> {
+    let migrations = match &options.migrations_dir {
+        Some(migrations_dir) => read_migrations(migrations_dir)?,
+        None => Vec::new(),
+    };
+
+    let pool = AnyPool::connect(uri).await?;
+    let mut conn = pool.acquire().await.map_err(Error::Sqlx)?;
+
+    set_foreign_keys(&mut conn, false).await?;
+    let result = rollback_migrations(&mut conn, &options, &migrations).await;
+    unlock_database(&mut conn).await?;
+    set_foreign_keys(&mut conn, true).await?;
+
+    let (rolled_back, reverts) = result?;
+    if let Some(migrations_dir) = &options.migrations_dir {
+        for revert in &reverts {
+            write_migration(migrations_dir, revert)?;
+        }
+    }
 
-        Ok(CompileDatabaseResult {
-            queries_executed,
-            rows_affected,
+    Ok(rolled_back)
+}
+
+/// Reverts the latest applied migrations, returning them along with the migrations reverting them.
+///
+/// Migrations which revert another migration, or which have already been reverted, aren't rolled back.
+async fn rollback_migrations(
+    conn: &mut AnyConnection,
+    options: &RollbackOptions,
+    migrations: &[Migration],
+) -> Result<(Vec<AppliedMigration>, Vec<Migration>), Error> {
+    let mut tx = conn.begin().await.map_err(Error::Sqlx)?;
+    lock_database(&mut tx, &options.schema).await?;
+
+    if !history_table_exists(&mut tx, &options.schema).await? {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    ensure_history_table(&mut tx, &options.schema).await?;
+
+    let applied_migrations = fetch_applied_migrations(&mut tx, &options.schema).await?;
+    verify_migrations(migrations, &applied_migrations)?;
+    // Reverts are numbered after the migration files, so pending files would be applied before them
+    let pending_migrations: Vec<_> = migrations
+        .iter()
+        .filter(|migration| {
+            applied_migrations
+                .iter()
+                .all(|applied| applied.version != i64::from(migration.version))
         })
+        .map(Migration::file_name)
+        .collect();
+    if !pending_migrations.is_empty() {
+        return Err(Error::PendingMigrations(pending_migrations));
+    }
+
+    let reverted_versions: Vec<_> = applied_migrations
+        .iter()
+        .filter_map(AppliedMigration::reverted_version)
+        .collect();
+    let rolled_back: Vec<_> = applied_migrations
+        .iter()
+        .rev()
+        .filter(|applied| {
+            applied.reverted_version().is_none() && !reverted_versions.contains(&applied.version)
+        })
+        .take(options.steps)
+        .cloned()
+        .collect();
+
+    let mut reverts = Vec::new();
+    let version = next_version(migrations, &applied_migrations);
+    for (migration, version) in rolled_back.iter().zip(version..) {
+        let revert = revert_migration(version, migration, options.allow_irreversible)?;
+        apply_migration(&mut tx, &options.schema, &revert).await?;
+        reverts.push(revert);
+    }
+
+    check_foreign_keys(&mut tx).await?;
+    tx.commit().await.map_err(Error::Sqlx)?;
+
+    Ok((rolled_back, reverts))
+}
+
+/// Takes a transaction level advisory lock, preventing concurrent syncs and rollbacks of a schema.
+///
+/// MySQL locks are held by the session instead, until released by [`unlock_database`].
+/// SQLite only allows a single writer, so there is nothing to lock.
+async fn lock_database(conn: &mut AnyConnection, schema: &str) -> Result<(), Error> {
+    let locked = match Backend::of(conn) {
+        Backend::Postgres => {
+            sqlx::query_as::<_, (bool,)>("SELECT pg_try_advisory_xact_lock($1, hashtext($2))")
+                .bind(ADVISORY_LOCK_KEY)
+                .bind(schema)
+                .fetch_one(conn)
+                .await
+                .map_err(Error::Sqlx)?
+                .0
+        }
+        Backend::MySql => {
+            sqlx::query_as::<_, (bool,)>(
+                "SELECT COALESCE(GET_LOCK(CONCAT('awto:', DATABASE()), 0), 0) = 1",
+            )
+            .fetch_one(conn)
+            .await
+            .map_err(Error::Sqlx)?
+            .0
+        }
+        Backend::Sqlite => true,
+    };
+    if !locked {
+        return Err(Error::Locked);
+    }
+
+    Ok(())
+}
+
+/// Releases the lock of a MySQL session taken by [`lock_database`], if it holds it.
+async fn unlock_database(conn: &mut AnyConnection) -> Result<(), Error> {
+    if Backend::of(conn) != Backend::MySql {
+        return Ok(());
+    }
+
+    sqlx::query("SELECT RELEASE_LOCK(CONCAT('awto:', DATABASE()))")
+        .execute(conn)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Creates a schema if it does not yet exist, on backends with schemas.
+async fn ensure_schema(conn: &mut AnyConnection, schema: &str) -> Result<(), Error> {
+    if Backend::of(conn) != Backend::Postgres {
+        return Ok(());
+    }
+
+    sqlx::query(&format!(
+        "CREATE SCHEMA IF NOT EXISTS {}",
+        quote_identifier(schema)
+    ))
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Enables or disables enforcing foreign keys on a SQLite connection.
+///
+/// Rebuilt tables are briefly missing while their rows are copied,
+/// so foreign keys are disabled outside of the transaction and checked before committing instead.
+async fn set_foreign_keys(conn: &mut AnyConnection, enabled: bool) -> Result<(), Error> {
+    if Backend::of(conn) != Backend::Sqlite {
+        return Ok(());
+    }
+
+    sqlx::query(if enabled {
+        "PRAGMA foreign_keys = ON"
     } else {
-        Ok(CompileDatabaseResult::default())
+        "PRAGMA foreign_keys = OFF"
+    })
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Fails if rows of a SQLite database violate their foreign keys.
+async fn check_foreign_keys(conn: &mut AnyConnection) -> Result<(), Error> {
+    if Backend::of(conn) != Backend::Sqlite {
+        return Ok(());
+    }
+
+    let violation: Option<(String,)> =
+        sqlx::query_as("SELECT \"table\" FROM pragma_foreign_key_check LIMIT 1")
+            .fetch_optional(conn)
+            .await
+            .map_err(Error::Sqlx)?;
+    if let Some((table,)) = violation {
+        return Err(Error::ForeignKeyViolation(table));
+    }
+
+    Ok(())
+}
+
+async fn sync_database(
+    compiler: &DatabaseCompiler<'_>,
+    options: &CompileDatabaseOptions,
+) -> Result<CompileDatabaseResult, Box<dyn std::error::Error>> {
+    if options.dry_run.is_some() {
+        return Ok(CompileDatabaseResult {
+            plan: Some(plan_database(compiler, options).await?),
+            ..Default::default()
+        });
+    }
+
+    if let (Some(migrations_dir), false) = (&options.migrations_dir, options.apply_migrations) {
+        return write_migrations(compiler, options, migrations_dir).await;
+    }
+
+    let mut conn = compiler.pool.acquire().await.map_err(Error::Sqlx)?;
+
+    set_foreign_keys(&mut conn, false).await?;
+    let result = apply_changes(compiler, options, &mut conn).await;
+    unlock_database(&mut conn).await?;
+    set_foreign_keys(&mut conn, true).await?;
+
+    result
+}
+
+async fn apply_changes(
+    compiler: &DatabaseCompiler<'_>,
+    options: &CompileDatabaseOptions,
+    conn: &mut AnyConnection,
+) -> Result<CompileDatabaseResult, Box<dyn std::error::Error>> {
+    let mut result = CompileDatabaseResult::default();
+    // Migrations adding enum values which aren't written to the migrations directory
+    let mut enum_migrations = Vec::new();
+    let mut added_enum_values = Vec::new();
+
+    // Everything is applied in a single transaction, holding a lock to prevent concurrent syncs.
+    // Postgres can't add enum values within it, so it is committed before they are added and then begun again.
+    // MySQL implicitly commits every schema change, so there a failing change leaves the earlier ones applied.
+    'sync: loop {
+        let mut tx = conn.begin().await.map_err(Error::Sqlx)?;
+        lock_database(&mut tx, &compiler.schema).await?;
+
+        ensure_schema(&mut tx, &compiler.schema).await?;
+        ensure_history_table(&mut tx, &compiler.schema).await?;
+        ensure_registry_table(&mut tx, &compiler.schema).await?;
+        let applied_migrations = fetch_applied_migrations(&mut tx, &compiler.schema).await?;
+
+        // Apply migrations which haven't been applied yet before comparing the schema
+        let mut migrations = enum_migrations.clone();
+        if let Some(migrations_dir) = &options.migrations_dir {
+            migrations.extend(read_migrations(migrations_dir)?);
+            verify_migrations(&migrations, &applied_migrations)?;
+        }
+        let version = next_version(&migrations, &applied_migrations);
+
+        for migration in &migrations {
+            if applied_migrations
+                .iter()
+                .any(|applied| applied.version == i64::from(migration.version))
+            {
+                continue;
+            }
+
+            if !added_enum_values.contains(&migration.version)
+                && !enum_value_statements(&migration.sql).is_empty()
+            {
+                tx.commit().await.map_err(Error::Sqlx)?;
+                result.add_query_results(&add_enum_values(conn, migration).await?);
+                added_enum_values.push(migration.version);
+                continue 'sync;
+            }
+
+            result.add_query_results(&apply_migration(&mut tx, &compiler.schema, migration).await?);
+        }
+
+        let plan = compiler.plan_in(&mut tx).await?;
+        if !options.allow_destructive && !plan.destructive_changes.is_empty() {
+            return Err(Error::DestructiveChanges(plan.destructive_changes).into());
+        }
+
+        // Enum values are added by a migration of their own, before the changes using them
+        let enum_values_sql = compiler.write_enum_values_sql(&plan.diff);
+        if !enum_values_sql.is_empty() {
+            let migration = Migration {
+                down_sql: Some(String::new()),
+                ..Migration::new(version, MIGRATION_NAME, format!("{}\n", enum_values_sql))
+            };
+            match &options.migrations_dir {
+                Some(migrations_dir) => {
+                    result.migration_file = Some(write_migration(migrations_dir, &migration)?)
+                }
+                None => enum_migrations.push(migration),
+            }
+
+            tx.commit().await.map_err(Error::Sqlx)?;
+            continue;
+        }
+
+        if !plan.sql.is_empty() {
+            let migration = sync_migration(version, &plan);
+            result
+                .add_query_results(&apply_migration(&mut tx, &compiler.schema, &migration).await?);
+            if let Some(migrations_dir) = &options.migrations_dir {
+                result.migration_file = Some(write_migration(migrations_dir, &migration)?);
+            }
+        }
+
+        update_registered_tables(&mut tx, &compiler.schema, &compiler.database_table_names())
+            .await?;
+        result.removed_tables = plan.removed_tables;
+
+        check_foreign_keys(&mut tx).await?;
+        tx.commit().await.map_err(Error::Sqlx)?;
+
+        return Ok(result);
+    }
+}
+
+/// Writes the changes needed after the pending migration files into a new migration file,
+/// leaving the database untouched.
+async fn write_migrations(
+    compiler: &DatabaseCompiler<'_>,
+    options: &CompileDatabaseOptions,
+    migrations_dir: &Path,
+) -> Result<CompileDatabaseResult, Box<dyn std::error::Error>> {
+    let plan = plan_database(compiler, options).await?;
+    if !options.allow_destructive && !plan.destructive_changes.is_empty() {
+        return Err(Error::DestructiveChanges(plan.destructive_changes).into());
+    }
+
+    let mut result = CompileDatabaseResult::default();
+    if !plan.sql.is_empty() {
+        let mut conn = compiler.pool.acquire().await.map_err(Error::Sqlx)?;
+        let applied_migrations = if history_table_exists(&mut conn, &compiler.schema).await? {
+            fetch_applied_migrations(&mut conn, &compiler.schema).await?
+        } else {
+            Vec::new()
+        };
+        let version = next_version(&read_migrations(migrations_dir)?, &applied_migrations);
+
+        // Enum values are added before the rest of the migration when it is applied
+        let migration = sync_migration(version, &plan);
+        result.migration_file = Some(write_migration(migrations_dir, &migration)?);
+    }
+    result.removed_tables = plan.removed_tables;
+
+    Ok(result)
+}
+
+/// Returns the migration applying the changes of a plan, recording the sql undoing them.
+fn sync_migration(version: u32, plan: &DatabasePlan) -> Migration {
+    let mut down_sql = String::new();
+    for change in &plan.irreversible_changes {
+        writeln!(down_sql, "{}{}", IRREVERSIBLE_PREFIX, change).unwrap();
+    }
+    writeln!(down_sql, "{}", plan.down_sql).unwrap();
+
+    Migration {
+        down_sql: Some(down_sql),
+        ..Migration::new(version, MIGRATION_NAME, format!("{}\n", plan.sql))
+    }
+}
+
+async fn plan_database(
+    compiler: &DatabaseCompiler<'_>,
+    options: &CompileDatabaseOptions,
+) -> Result<DatabasePlan, Box<dyn std::error::Error>> {
+    let mut conn = compiler.pool.acquire().await.map_err(Error::Sqlx)?;
+
+    set_foreign_keys(&mut conn, false).await?;
+    let result = plan_changes(compiler, options, &mut conn).await;
+    set_foreign_keys(&mut conn, true).await?;
+
+    result
+}
+
+async fn plan_changes(
+    compiler: &DatabaseCompiler<'_>,
+    options: &CompileDatabaseOptions,
+    conn: &mut AnyConnection,
+) -> Result<DatabasePlan, Box<dyn std::error::Error>> {
+    // Pending migrations are applied in a transaction which is rolled back after planning,
+    // so the plan only contains the changes they don't already make
+    let mut tx = conn.begin().await.map_err(Error::Sqlx)?;
+
+    let mut pending_migrations = Vec::new();
+    if let Some(migrations_dir) = &options.migrations_dir {
+        let applied_migrations = if history_table_exists(&mut tx, &compiler.schema).await? {
+            fetch_applied_migrations(&mut tx, &compiler.schema).await?
+        } else {
+            Vec::new()
+        };
+
+        let migrations = read_migrations(migrations_dir)?;
+        verify_migrations(&migrations, &applied_migrations)?;
+
+        pending_migrations = migrations
+            .into_iter()
+            .filter(|migration| {
+                applied_migrations
+                    .iter()
+                    .all(|applied| applied.version != i64::from(migration.version))
+            })
+            .collect();
     }
+
+    if !pending_migrations.is_empty() {
+        // MySQL commits schema changes as they are made, so they can't be rolled back
+        if compiler.backend == Backend::MySql {
+            return Err(Error::DryRunPendingMigrations(compiler.backend).into());
+        }
+
+        lock_database(&mut tx, &compiler.schema).await?;
+        ensure_schema(&mut tx, &compiler.schema).await?;
+        ensure_history_table(&mut tx, &compiler.schema).await?;
+        // Postgres 12 and later can add enum values within a transaction, as long as they aren't used in it
+        for migration in &pending_migrations {
+            add_enum_values(&mut tx, migration).await?;
+            match apply_migration(&mut tx, &compiler.schema, migration).await {
+                Err(Error::Sqlx(sqlx::Error::Database(err)))
+                    if err.code().as_deref() == Some(UNSAFE_NEW_ENUM_VALUE_USAGE) =>
+                {
+                    return Err(Error::DryRunPendingMigrations(compiler.backend).into());
+                }
+                result => result?,
+            };
+        }
+    }
+
+    let mut plan = compiler.plan_in(&mut tx).await?;
+    plan.pending_migrations = pending_migrations
+        .iter()
+        .map(|migration| migration.file_name())
+        .collect();
+
+    tx.rollback().await.map_err(Error::Sqlx)?;
+
+    Ok(plan)
 }
 
 pub struct DatabaseCompiler<'pool> {
-    pool: Cow<'pool, PgPool>,
+    pool: Cow<'pool, AnyPool>,
+    backend: Backend,
     models: Vec<Model>,
+    removed_tables: RemovedTables,
+    schema: String,
 }
 
 impl<'pool> DatabaseCompiler<'pool> {
@@ -110,44 +691,403 @@ impl<'pool> DatabaseCompiler<'pool> {
         uri: &str,
         models: Vec<Model>,
     ) -> Result<DatabaseCompiler<'_>, sqlx::Error> {
-        let pool = sqlx::PgPool::connect(uri).await?;
+        let pool = AnyPool::connect(uri).await?;
 
         Ok(DatabaseCompiler {
+            backend: pool.any_kind().into(),
             pool: Cow::Owned(pool),
             models,
+            removed_tables: RemovedTables::default(),
+            schema: DEFAULT_SCHEMA.to_string(),
         })
     }
 
-    pub fn from_pool(pool: &'pool PgPool, models: Vec<Model>) -> DatabaseCompiler<'pool> {
+    pub fn from_pool(pool: &'pool AnyPool, models: Vec<Model>) -> DatabaseCompiler<'pool> {
         DatabaseCompiler {
+            backend: pool.any_kind().into(),
             pool: Cow::Borrowed(pool),
             models,
+            removed_tables: RemovedTables::default(),
+            schema: DEFAULT_SCHEMA.to_string(),
         }
     }
 
+    /// Sets what to do with awto managed tables which no longer exist in the schema.
+    pub fn with_removed_tables(mut self, removed_tables: RemovedTables) -> Self {
+        self.removed_tables = removed_tables;
+        self
+    }
+
+    /// Sets the database schema to sync, which defaults to [`DEFAULT_SCHEMA`].
+    ///
+    /// Generated sql qualifies every table, type and index with the schema, on backends with schemas.
+    pub fn with_schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = schema.into();
+        self
+    }
+
+    /// Compiles the sql needed to sync the database with the schema.
     pub async fn compile(&self) -> Result<String, Error> {
-        let mut sql = String::new();
+        Ok(self.plan().await?.sql)
+    }
 
-        for (_, table) in self.database_tables() {
-            let db_columns = self.fetch_table(table).await?;
+    /// Plans the changes needed to sync the database with the schema, along with their sql.
+    pub async fn plan(&self) -> Result<DatabasePlan, Error> {
+        let mut conn = self.pool.acquire().await.map_err(Error::Sqlx)?;
+        self.plan_in(&mut conn).await
+    }
 
-            match db_columns {
-                Some(db_columns) => {
-                    writeln!(sql, "{}", self.write_sync_sql(table, &db_columns).await).unwrap();
+    /// Plans the changes needed to sync the database with the schema,
+    /// querying the database through a connection or transaction.
+    pub async fn plan_in(&self, conn: &mut AnyConnection) -> Result<DatabasePlan, Error> {
+        let diff = self.diff_in(conn).await?;
+        // Rebuilding a table or redefining its columns needs its columns before the changes
+        let (db_tables, schema_tables) = if self.backend.alters_from_table() {
+            (
+                self.fetch_altered_tables(conn, &diff).await?,
+                self.database_tables()
+                    .into_iter()
+                    .map(|(_, table)| self.backend.table(table))
+                    .collect::<Result<_, _>>()?,
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let sql = self.write_diff_sql(&diff, &db_tables);
+        let down_sql = self.write_diff_sql(&diff.inverse(), &schema_tables);
+        let destructive_changes = self.destructive_changes(conn, &diff).await?;
+        let removed_tables = self.removed_tables(conn, &diff).await?;
+
+        Ok(DatabasePlan {
+            irreversible_changes: irreversible_changes(&diff),
+            diff,
+            destructive_changes,
+            removed_tables,
+            sql,
+            down_sql,
+            ..Default::default()
+        })
+    }
+
+    /// Compares the schema with the database, returning the changes needed to sync them.
+    pub async fn diff(&self) -> Result<SchemaDiff, Error> {
+        let mut conn = self.pool.acquire().await.map_err(Error::Sqlx)?;
+        self.diff_in(&mut conn).await
+    }
+
+    /// Compares the schema with the database, querying the database through a connection or transaction.
+    pub async fn diff_in(&self, conn: &mut AnyConnection) -> Result<SchemaDiff, Error> {
+        let mut diff = SchemaDiff::default();
+
+        // Backends without enum types store enums as text
+        if self.backend.has_enum_types() {
+            for (_, database_enum) in self.database_enums() {
+                let db_enum = self.fetch_enum(conn, &database_enum.name).await?;
+                if let Some(enum_change) = diff_enum(database_enum, db_enum.as_ref()) {
+                    diff.enums.push(enum_change);
                 }
-                None => {
-                    writeln!(sql, "{}", self.write_table_create_sql(table)).unwrap();
+            }
+        }
+
+        let database_tables = self.database_tables();
+        for (_, table) in &database_tables {
+            let table = self.backend.table(table)?;
+            let mut db_table = self.fetch_table(conn, &table.name).await?;
+            if db_table.is_none() {
+                // Previous names which are still used by a table in the schema are ignored
+                let previous_names = table.renamed_from.iter().filter(|previous_name| {
+                    database_tables
+                        .iter()
+                        .all(|(_, schema_table)| &schema_table.name != *previous_name)
+                });
+                for previous_name in previous_names {
+                    if let Some(mut renamed_table) = self.fetch_table(conn, previous_name).await? {
+                        if let Some(table_change) =
+                            self.drop_table_named_columns(&mut renamed_table, &table.name)
+                        {
+                            diff.tables.push(table_change);
+                        }
+                        diff.tables.push(TableChange::Rename {
+                            from: previous_name.clone(),
+                            to: table.name.clone(),
+                        });
+                        db_table = Some(renamed_table);
+                        break;
+                    }
                 }
             }
+
+            if let Some(table_change) = diff_table(&table, db_table.as_ref()) {
+                diff.tables.push(table_change);
+            }
         }
 
-        Ok(sql.trim().to_string())
+        for removed_table in self.removed_tables(conn, &diff).await? {
+            match self.removed_tables {
+                RemovedTables::Keep => {}
+                RemovedTables::Drop => {
+                    if let Some(db_table) = self.fetch_table(conn, &removed_table).await? {
+                        diff.tables.push(TableChange::Drop(db_table));
+                    }
+                }
+                RemovedTables::Archive => diff.tables.push(TableChange::Rename {
+                    to: archived_table_name(&removed_table),
+                    from: removed_table,
+                }),
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Drops the constraints and triggers on the columns of a table which are named after the table,
+    /// before it's renamed to `to`.
+    ///
+    /// They're removed from `db_table`, so diffing it with the schema adds them again under the new name.
+    /// SQLite finds column constraints by their columns rather than their names, so only its triggers are dropped.
+    fn drop_table_named_columns(
+        &self,
+        db_table: &mut DatabaseTable,
+        to: &str,
+    ) -> Option<TableChange> {
+        let mut columns = Vec::new();
+
+        for column in &mut db_table.columns {
+            if !self.backend.rebuilds_tables() {
+                if column.unique {
+                    column.unique = false;
+                    columns.push(ColumnChange::DropUnique {
+                        column: column.name.clone(),
+                    });
+                }
+                if let Some(references) = column.references.take() {
+                    columns.push(ColumnChange::DropForeignKey {
+                        column: column.name.clone(),
+                        references,
+                    });
+                }
+                if let Some(check) = column.constraint.take() {
+                    columns.push(ColumnChange::DropCheck {
+                        column: column.name.clone(),
+                        check,
+                    });
+                }
+            }
+            if column.on_update_now
+                && self.backend.has_on_update_triggers()
+                && self
+                    .backend
+                    .on_update_trigger_name(&db_table.name, &column.name)
+                    != self.backend.on_update_trigger_name(to, &column.name)
+            {
+                column.on_update_now = false;
+                columns.push(ColumnChange::DropOnUpdateNow {
+                    column: column.name.clone(),
+                });
+            }
+        }
+
+        if columns.is_empty() {
+            return None;
+        }
+
+        Some(TableChange::Alter {
+            table: db_table.name.clone(),
+            columns,
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+        })
+    }
+
+    /// Fetches the tables altered by a diff as they are before it, named as they are when altered.
+    async fn fetch_altered_tables(
+        &self,
+        conn: &mut AnyConnection,
+        diff: &SchemaDiff,
+    ) -> Result<Vec<DatabaseTable>, Error> {
+        let mut tables = Vec::new();
+
+        for table_change in &diff.tables {
+            if let TableChange::Alter { table, .. } = table_change {
+                // Tables are renamed before they are altered
+                let db_name = diff
+                    .tables
+                    .iter()
+                    .find_map(|table_change| match table_change {
+                        TableChange::Rename { from, to } if to == table => Some(from),
+                        _ => None,
+                    })
+                    .unwrap_or(table);
+                if let Some(db_table) = self.fetch_table(conn, db_name).await? {
+                    tables.push(DatabaseTable {
+                        name: table.clone(),
+                        ..db_table
+                    });
+                }
+            }
+        }
+
+        Ok(tables)
+    }
+
+    /// Returns awto managed tables in the database which no longer exist in the schema.
+    ///
+    /// Tables being renamed to a table in the schema by the diff are not considered removed.
+    pub async fn removed_tables(
+        &self,
+        conn: &mut AnyConnection,
+        diff: &SchemaDiff,
+    ) -> Result<Vec<String>, Error> {
+        let table_names = self.database_table_names();
+        let renamed_tables: Vec<_> = diff
+            .tables
+            .iter()
+            .filter_map(|table_change| match table_change {
+                TableChange::Rename { from, to } if table_names.contains(&to.as_str()) => {
+                    Some(from.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(fetch_registered_tables(conn, &self.schema)
+            .await?
+            .into_iter()
+            .filter(|table| {
+                !table_names.contains(&table.as_str()) && !renamed_tables.contains(&table.as_str())
+            })
+            .collect())
+    }
+
+    /// Returns the changes in a diff which can lose data.
+    pub async fn destructive_changes(
+        &self,
+        conn: &mut AnyConnection,
+        diff: &SchemaDiff,
+    ) -> Result<Vec<DestructiveChange>, Error> {
+        let mut populated_tables = Vec::new();
+
+        for table_change in &diff.tables {
+            if let TableChange::Alter { table, columns, .. } = table_change {
+                if columns
+                    .iter()
+                    .any(|change| matches!(change, ColumnChange::SetNotNull { .. }))
+                    && self.table_has_rows(conn, table).await?
+                {
+                    populated_tables.push(table.clone());
+                }
+            }
+        }
+
+        Ok(destructive_changes(diff, &populated_tables))
+    }
+
+    /// Renders the sql for a schema diff.
+    ///
+    /// Backends which rebuild altered tables or redefine altered columns read them from `tables`,
+    /// the altered tables as they are before the diff.
+    pub fn write_diff_sql(&self, diff: &SchemaDiff, tables: &[DatabaseTable]) -> String {
+        let mut sql = String::new();
+
+        // Enums are created before the tables using them, and dropped after
+        for enum_change in &diff.enums {
+            if !matches!(enum_change, EnumChange::Drop(_)) {
+                writeln!(sql, "{}", self.write_enum_change_sql(enum_change)).unwrap();
+            }
+        }
+        if self.backend == Backend::Postgres && Self::sets_on_update(diff) {
+            writeln!(sql, "{}", self.write_on_update_function_sql()).unwrap();
+        }
+        for table_change in &diff.tables {
+            writeln!(sql, "{}", self.write_table_change_sql(table_change, tables)).unwrap();
+        }
+        for enum_change in &diff.enums {
+            if let EnumChange::Drop(_) = enum_change {
+                writeln!(sql, "{}", self.write_enum_change_sql(enum_change)).unwrap();
+            }
+        }
+
+        sql.trim().to_string()
     }
 
     /// Compiles generated Rust code from schemas and services.
     pub fn compile_generated_code(&self) -> String {
         let mut code = String::new();
 
+        write!(
+            code,
+            r#"
+#[derive(Debug)]
+pub enum TryFromDatabaseError {{
+    InvalidJson(String),
+}}
+
+impl ::std::fmt::Display for TryFromDatabaseError {{
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> Result<(), ::std::fmt::Error> {{
+        match self {{
+            Self::InvalidJson(column) => write!(f, "invalid json in column '{{}}'", column),
+        }}
+    }}
+}}
+        "#
+        )
+        .unwrap();
+
+        let database_enums = self.database_enums();
+        let enum_names: Vec<_> = database_enums
+            .iter()
+            .map(|(model, _)| model.name.as_str())
+            .collect();
+
+        if !database_enums.is_empty() {
+            let active_enums = database_enums.iter().map(|(model, database_enum)| {
+                let ident = format_ident!("{}", model.name);
+                let enum_name = &database_enum.name;
+                let variant_idents: Vec<_> = model
+                    .variants
+                    .iter()
+                    .map(|variant| format_ident!("{}", variant.name))
+                    .collect();
+                let values = &database_enum.values;
+
+                quote!(
+                    #[derive(Clone, Debug, PartialEq, ::sea_orm::EnumIter, ::sea_orm::DeriveActiveEnum)]
+                    #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = #enum_name)]
+                    pub enum #ident {
+                        #(
+                            #[sea_orm(string_value = #values)]
+                            #variant_idents,
+                        )*
+                    }
+
+                    impl ::std::convert::From<#ident> for ::schema::#ident {
+                        fn from(val: #ident) -> Self {
+                            match val {
+                                #( #ident::#variant_idents => Self::#variant_idents, )*
+                            }
+                        }
+                    }
+
+                    impl ::std::convert::From<::schema::#ident> for #ident {
+                        fn from(val: ::schema::#ident) -> Self {
+                            match val {
+                                #( ::schema::#ident::#variant_idents => Self::#variant_idents, )*
+                            }
+                        }
+                    }
+                )
+            });
+
+            // Generated models refer to enums through `super::sea_orm_active_enums`
+            let expanded = quote!(
+                pub mod sea_orm_active_enums {
+                    #( #active_enums )*
+                }
+            );
+
+            write!(code, "{}", expanded).unwrap();
+        }
+
         for (model, table) in self.database_tables() {
             let ident = format_ident!("{}", model.name);
             let db_module_ident = format_ident!("{}", table.name);
@@ -160,7 +1100,28 @@ impl<'pool> DatabaseCompiler<'pool> {
 
                 let ty = strip_ty_option(&field.ty);
 
-                if is_ty_vec(ty) {
+                if let Some(column) = Self::json_serialized_column(table, field) {
+                    let (from_json, to_json) = Self::json_conversions(&table.name, column);
+                    if is_ty_option(&field.ty) {
+                        from_schema_fields.push(
+                            quote!(#field_ident: val.#field_ident.map(|v| #from_json).transpose()?),
+                        );
+                        from_db_fields
+                            .push(quote!(#field_ident: val.#field_ident.map(|v| #to_json)));
+                    } else {
+                        from_schema_fields
+                            .push(quote!(#field_ident: { let v = val.#field_ident; #from_json? }));
+                        from_db_fields
+                            .push(quote!(#field_ident: { let v = val.#field_ident; #to_json }));
+                    }
+                } else if enum_names.contains(&ty) && is_ty_option(&field.ty) {
+                    from_schema_fields.push(
+                        quote!(#field_ident: val.#field_ident.map(::std::convert::Into::into)),
+                    );
+                    from_db_fields.push(
+                        quote!(#field_ident: val.#field_ident.map(::std::convert::Into::into)),
+                    );
+                } else if is_ty_vec(ty) {
                     from_schema_fields.push(
                         quote!(#field_ident: val.#field_ident.into_iter().map(|v| v.into()).collect()),
                     );
@@ -174,12 +1135,14 @@ impl<'pool> DatabaseCompiler<'pool> {
             }
 
             let expanded = quote!(
-                impl ::std::convert::From<crate::#db_module_ident::Model> for ::schema::#ident {
+                impl ::std::convert::TryFrom<crate::#db_module_ident::Model> for ::schema::#ident {
+                    type Error = TryFromDatabaseError;
+
                     #[allow(unused_variables)]
-                    fn from(val: crate::#db_module_ident::Model) -> Self {
-                        Self {
+                    fn try_from(val: crate::#db_module_ident::Model) -> Result<Self, Self::Error> {
+                        Ok(Self {
                             #( #from_schema_fields, )*
-                        }
+                        })
                     }
                 }
 
@@ -193,7 +1156,7 @@ impl<'pool> DatabaseCompiler<'pool> {
                 }
             );
 
-            write!(code, "{}", expanded.to_string()).unwrap();
+            write!(code, "{}", expanded).unwrap();
         }
 
         for (model, table) in self.database_sub_tables() {
@@ -202,6 +1165,31 @@ impl<'pool> DatabaseCompiler<'pool> {
 
             let active_values = model.fields.iter().map(|field| {
                 let field_ident = format_ident!("{}", field.name);
+
+                if enum_names.contains(&strip_ty_option(&field.ty)) {
+                    let self_field = if is_ty_option(&field.ty) {
+                        quote!(self.#field_ident.map(::std::convert::Into::into))
+                    } else {
+                        quote!(::std::convert::Into::into(self.#field_ident))
+                    };
+
+                    return quote!(
+                        #field_ident: ::sea_orm::entity::ActiveValue::set(#self_field)
+                    );
+                }
+
+                if let Some(column) = Self::json_serialized_column(table, field) {
+                    let (_, to_json) = Self::json_conversions(&table.name, column);
+                    let self_field = if is_ty_option(&field.ty) {
+                        quote!(self.#field_ident.map(|v| #to_json))
+                    } else {
+                        quote!({ let v = self.#field_ident; #to_json })
+                    };
+
+                    return quote!(
+                        #field_ident: ::sea_orm::entity::ActiveValue::set(#self_field)
+                    );
+                }
                 
                 let self_field = if is_ty_option(&field.ty) {
                     let db_field = table.columns.iter().find(|column| column.name == field.name).unwrap();
@@ -238,7 +1226,7 @@ impl<'pool> DatabaseCompiler<'pool> {
                 }
             );
 
-            write!(code, "{}", expanded.to_string()).unwrap();
+            write!(code, "{}", expanded).unwrap();
         }
 
         code.trim().to_string()
@@ -282,6 +1270,37 @@ impl<'pool> DatabaseCompiler<'pool> {
         Ok(())
     }
 
+    /// Returns the column of a field which is serialized to json, rather than being a `serde_json::Value`.
+    fn json_serialized_column<'a>(
+        table: &'a DatabaseTable,
+        field: &RustField,
+    ) -> Option<&'a DatabaseColumn> {
+        if strip_ty_option(&field.ty) == "serde_json::Value" {
+            return None;
+        }
+
+        table.columns.iter().find(|column| {
+            column.name == field.name
+                && matches!(column.ty, DatabaseType::Json | DatabaseType::Jsonb)
+        })
+    }
+
+    /// Returns the expressions converting a json value `v` into the field's type, and the field's type into a json value.
+    ///
+    /// The conversion from the json value returns a `Result`, as the column can hold json which doesn't match the field's type.
+    fn json_conversions(table: &str, column: &DatabaseColumn) -> (TokenStream, TokenStream) {
+        let column_name = format!("{}.{}", table, column.name);
+        let to_json_message = format!("failed to serialize column '{}' to json", column_name);
+
+        (
+            quote!(
+                ::serde_json::from_value(v)
+                    .map_err(|_| TryFromDatabaseError::InvalidJson(#column_name.to_string()))
+            ),
+            quote!(::serde_json::to_value(v).expect(#to_json_message)),
+        )
+    }
+
     fn database_tables(&self) -> Vec<(&Model, &DatabaseTable)> {
         self.models.iter().fold(Vec::new(), |mut acc, model| {
             let roles = model
@@ -299,6 +1318,30 @@ impl<'pool> DatabaseCompiler<'pool> {
         })
     }
 
+    fn database_enums(&self) -> Vec<(&Model, &DatabaseEnum)> {
+        self.models.iter().fold(Vec::new(), |mut acc, model| {
+            let roles = model
+                .roles
+                .iter()
+                .filter_map(|role| match role {
+                    Role::DatabaseEnum(database_enum) => Some((model, database_enum)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            acc.extend(roles);
+
+            acc
+        })
+    }
+
+    fn database_table_names(&self) -> Vec<&str> {
+        self.database_tables()
+            .into_iter()
+            .map(|(_, table)| table.name.as_str())
+            .collect()
+    }
+
     fn database_sub_tables(&self) -> Vec<(&Model, &DatabaseTable)> {
         self.models.iter().fold(Vec::new(), |mut acc, model| {
             let roles = model
@@ -318,24 +1361,51 @@ impl<'pool> DatabaseCompiler<'pool> {
 
     async fn fetch_table(
         &self,
-        table: &DatabaseTable,
-    ) -> Result<Option<Vec<DatabaseColumn>>, Error> {
+        conn: &mut AnyConnection,
+        table_name: &str,
+    ) -> Result<Option<DatabaseTable>, Error> {
+        if let Backend::Sqlite = self.backend {
+            let enum_names: Vec<_> = self
+                .database_enums()
+                .into_iter()
+                .map(|(_, database_enum)| database_enum.name.as_str())
+                .collect();
+            return sqlite::fetch_table(conn, table_name, &enum_names).await;
+        }
+        if let Backend::MySql = self.backend {
+            let index_names: Vec<_> = self
+                .database_tables()
+                .into_iter()
+                .filter(|(_, table)| {
+                    table.name == table_name
+                        || table.renamed_from.iter().any(|name| name == table_name)
+                })
+                .flat_map(|(_, table)| table.indexes.iter().map(|index| index.name.as_str()))
+                .collect();
+            return mysql::fetch_table(conn, table_name, &index_names).await;
+        }
+
         #[derive(Debug, sqlx::FromRow)]
         struct ColumnsQuery {
             column_name: String,
             column_default: Option<String>,
             is_nullable: String,
             data_type: String,
+            udt_name: String,
+            is_enum: bool,
             character_maximum_length: Option<i32>,
+            numeric_precision: Option<i32>,
+            numeric_scale: Option<i32>,
             is_primary_key: bool,
+            is_identity: bool,
             is_unique: bool,
             reference: Option<String>,
         }
 
         let raw_columns: Vec<ColumnsQuery> = sqlx::query_as(FETCH_TABLE_QUERY)
-            .bind("public")
-            .bind(&table.name)
-            .fetch_all(&*self.pool)
+            .bind(&self.schema)
+            .bind(table_name)
+            .fetch_all(&mut *conn)
             .await
             .map_err(Error::Sqlx)?;
 
@@ -343,50 +1413,55 @@ impl<'pool> DatabaseCompiler<'pool> {
             return Ok(None);
         }
 
-        let columns: Vec<DatabaseColumn> = raw_columns
+        let mut columns: Vec<DatabaseColumn> = raw_columns
             .into_iter()
             .map(|col| {
                 let column_name = col.column_name;
                 let character_maximum_length = col.character_maximum_length;
+                let numeric_precision_scale = col.numeric_precision.zip(col.numeric_scale);
+
+                let ty = if col.is_enum {
+                    Ok(DatabaseType::Enum(col.udt_name))
+                } else if col.data_type == "ARRAY" {
+                    // Array types are named after their element type with a `_` prefix, such as `_int8`
+                    col.udt_name
+                        .trim_start_matches('_')
+                        .parse()
+                        .map(|database_type| DatabaseType::Array(Box::new(database_type)))
+                } else {
+                    col.data_type.parse::<DatabaseType>()
+                };
 
                 Ok(DatabaseColumn {
                     name: column_name.clone(),
-                    ty: col
-                        .data_type
-                        .parse::<DatabaseType>()
+                    ty: ty
                         .map(|database_type| {
                             if let Some(max_len) = character_maximum_length {
                                 if matches!(database_type, DatabaseType::Text(None)) {
                                     return DatabaseType::Text(Some(max_len));
                                 }
                             }
+                            // Integer columns have a precision too, so only numeric columns are given theirs
+                            if let Some((precision, scale)) = numeric_precision_scale {
+                                if matches!(database_type, DatabaseType::Numeric(None)) {
+                                    return DatabaseType::Numeric(Some((
+                                        precision as u16,
+                                        scale as u16,
+                                    )));
+                                }
+                            }
 
                             database_type
                         })
-                        .map_err(|_| Error::UnsupportedType(table.name.clone(), column_name))?,
+                        .map_err(|_| Error::UnsupportedType(table_name.to_string(), column_name))?,
                     nullable: col.is_nullable == "YES",
-                    default: col.column_default.map(|def| {
-                        if def.starts_with('\'') {
-                            let s = def
-                                .strip_prefix('\'')
-                                .unwrap()
-                                .splitn(2, '\'')
-                                .next()
-                                .unwrap()
-                                .to_string();
-                            DatabaseDefault::String(s)
-                        } else if def == "true" {
-                            DatabaseDefault::Bool(true)
-                        } else if def == "false" {
-                            DatabaseDefault::Bool(false)
-                        } else if let Ok(num) = def.parse::<u64>() {
-                            DatabaseDefault::Int(num)
-                        } else if let Ok(num) = def.parse::<i64>() {
-                            DatabaseDefault::Float(num)
-                        } else {
-                            DatabaseDefault::Raw(def)
-                        }
-                    }),
+                    default: if col.is_identity {
+                        Some(DatabaseDefault::Identity)
+                    } else {
+                        col.column_default
+                            .map(|default| self.backend.parse_default(default))
+                    },
+                    on_update_now: false,
                     unique: col.is_unique,
                     constraint: None,
                     primary_key: col.is_primary_key,
@@ -399,220 +1474,1173 @@ impl<'pool> DatabaseCompiler<'pool> {
                         } else {
                             None
                         }
-                    } else {
-                        None
-                    },
-                })
-            })
-            .collect::<Result<_, _>>()?;
-
-        Ok(Some(columns))
+                    } else {
+                        None
+                    },
+                    renamed_from: Vec::new(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let indexes = self.fetch_indexes(conn, table_name).await?;
+        let mut constraints = self.fetch_constraints(conn, table_name).await?;
+
+        // Check constraints named after a column belong to the column
+        for column in &mut columns {
+            let check_name = DatabaseConstraint::column_check_name(table_name, &column.name);
+            if let Some(i) = constraints.iter().position(|constraint| {
+                constraint.name == check_name && matches!(constraint.kind, ConstraintKind::Check(_))
+            }) {
+                if let ConstraintKind::Check(check) = constraints.remove(i).kind {
+                    column.constraint = Some(check);
+                }
+            }
+        }
+
+        // Triggers setting a column to the current time on update are named after the column
+        let triggers = self.fetch_triggers(conn, table_name).await?;
+        for column in &mut columns {
+            let trigger_name = self
+                .backend
+                .on_update_trigger_name(table_name, &column.name);
+            column.on_update_now = triggers.contains(&trigger_name);
+        }
+
+        Ok(Some(DatabaseTable {
+            name: table_name.to_string(),
+            columns,
+            indexes,
+            constraints,
+            renamed_from: Vec::new(),
+        }))
+    }
+
+    /// Fetches an enum type and its values, in their sort order.
+    async fn fetch_enum(
+        &self,
+        conn: &mut AnyConnection,
+        enum_name: &str,
+    ) -> Result<Option<DatabaseEnum>, Error> {
+        let values: Vec<(String,)> = sqlx::query_as(FETCH_ENUM_QUERY)
+            .bind(&self.schema)
+            .bind(enum_name)
+            .fetch_all(conn)
+            .await
+            .map_err(Error::Sqlx)?;
+
+        if values.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(DatabaseEnum {
+            name: enum_name.to_string(),
+            values: values.into_iter().map(|(value,)| value).collect(),
+        }))
+    }
+
+    /// Fetches the indexes of a table, excluding those backing constraints such as the primary key.
+    async fn fetch_indexes(
+        &self,
+        conn: &mut AnyConnection,
+        table_name: &str,
+    ) -> Result<Vec<DatabaseIndex>, Error> {
+        #[derive(Debug, sqlx::FromRow)]
+        struct IndexesQuery {
+            index_name: String,
+            columns: String,
+            is_unique: bool,
+            method: String,
+        }
+
+        let raw_indexes: Vec<IndexesQuery> = sqlx::query_as(FETCH_INDEXES_QUERY)
+            .bind(&self.schema)
+            .bind(table_name)
+            .fetch_all(conn)
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(raw_indexes
+            .into_iter()
+            .filter_map(|index| {
+                Some(DatabaseIndex {
+                    // Indexes using an unsupported method are left alone
+                    method: index.method.parse().ok()?,
+                    name: index.index_name,
+                    columns: split_columns(&index.columns),
+                    unique: index.is_unique,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetches the table level constraints of a table.
+    ///
+    /// Single column unique constraints are fetched as part of their column instead.
+    /// The expression of a check constraint is read from its comment when it has one.
+    /// The primary key is fetched too, as its name is needed to drop it.
+    async fn fetch_constraints(
+        &self,
+        conn: &mut AnyConnection,
+        table_name: &str,
+    ) -> Result<Vec<DatabaseConstraint>, Error> {
+        #[derive(Debug, sqlx::FromRow)]
+        struct ConstraintsQuery {
+            constraint_name: String,
+            constraint_type: String,
+            columns: String,
+            check_comment: Option<String>,
+            check_expr: Option<String>,
+        }
+
+        let raw_constraints: Vec<ConstraintsQuery> = sqlx::query_as(FETCH_CONSTRAINTS_QUERY)
+            .bind(&self.schema)
+            .bind(table_name)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(Error::Sqlx)?;
+
+        let mut constraints = Vec::with_capacity(raw_constraints.len());
+        for constraint in raw_constraints {
+            let kind = match (
+                constraint.constraint_type.as_str(),
+                constraint.check_comment,
+                constraint.check_expr,
+            ) {
+                ("c", Some(check_comment), _) => ConstraintKind::Check(check_comment),
+                ("c", None, Some(check_expr)) => ConstraintKind::Check(
+                    self.uncommented_check(
+                        conn,
+                        table_name,
+                        &constraint.constraint_name,
+                        check_expr,
+                    )
+                    .await?,
+                ),
+                ("p", _, _) => ConstraintKind::PrimaryKey(split_columns(&constraint.columns)),
+                _ => ConstraintKind::Unique(split_columns(&constraint.columns)),
+            };
+            constraints.push(DatabaseConstraint {
+                name: constraint.constraint_name,
+                kind,
+            });
+        }
+
+        Ok(constraints)
+    }
+
+    /// Returns the expression of a check constraint which has no comment, such as one added by hand.
+    ///
+    /// Postgres rewrites check expressions, so the schema's expression is rewritten too and
+    /// returned if it matches, rather than recreating the check.
+    async fn uncommented_check(
+        &self,
+        conn: &mut AnyConnection,
+        table_name: &str,
+        name: &str,
+        check_expr: String,
+    ) -> Result<String, Error> {
+        let schema_check = self
+            .database_tables()
+            .into_iter()
+            .filter(|(_, table)| {
+                table.name == table_name || table.renamed_from.iter().any(|name| name == table_name)
+            })
+            .find_map(|(_, table)| {
+                let table_check = table
+                    .constraints
+                    .iter()
+                    .find_map(|constraint| match &constraint.kind {
+                        ConstraintKind::Check(check) if constraint.name == name => Some(check),
+                        _ => None,
+                    });
+                let column_check = || {
+                    table.columns.iter().find_map(|column| {
+                        column.constraint.as_ref().filter(|_| {
+                            DatabaseConstraint::column_check_name(table_name, &column.name) == name
+                        })
+                    })
+                };
+
+                table_check.or_else(column_check)
+            });
+
+        let schema_check = match schema_check {
+            Some(schema_check) => schema_check,
+            None => return Ok(check_expr),
+        };
+        let rewritten_check = self.rewrite_check(conn, table_name, schema_check).await?;
+        if rewritten_check.as_ref() == Some(&check_expr) {
+            Ok(schema_check.clone())
+        } else {
+            Ok(check_expr)
+        }
+    }
+
+    /// Returns a check expression as Postgres rewrites it, by adding it to an empty copy of a table.
+    ///
+    /// The copy is made within a savepoint which is rolled back.
+    /// Returns `None` if the expression is not valid for the table.
+    async fn rewrite_check(
+        &self,
+        conn: &mut AnyConnection,
+        table_name: &str,
+        check: &str,
+    ) -> Result<Option<String>, Error> {
+        let mut probe = conn.begin().await.map_err(Error::Sqlx)?;
+
+        let sql = format!(
+            "CREATE TEMPORARY TABLE {probe} (LIKE {table});
+ALTER TABLE {probe} ADD CONSTRAINT {name} CHECK ({check}) NOT VALID;",
+            probe = CHECK_PROBE_TABLE,
+            table = self.qualify(table_name),
+            name = CHECK_PROBE_NAME,
+            check = check,
+        );
+        let rewritten_check = match (&mut *probe).execute(sql.as_str()).await {
+            Ok(_) => sqlx::query_as::<_, (String,)>(FETCH_CHECK_PROBE_QUERY)
+                .fetch_optional(&mut *probe)
+                .await
+                .map_err(Error::Sqlx)?
+                .map(|(check,)| check),
+            Err(_) => None,
+        };
+
+        probe.rollback().await.map_err(Error::Sqlx)?;
+
+        Ok(rewritten_check)
+    }
+
+    /// Fetches the names of the triggers on a table.
+    async fn fetch_triggers(
+        &self,
+        conn: &mut AnyConnection,
+        table_name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let triggers: Vec<(String,)> = sqlx::query_as(FETCH_TRIGGERS_QUERY)
+            .bind(&self.schema)
+            .bind(table_name)
+            .fetch_all(conn)
+            .await
+            .map_err(Error::Sqlx)?;
+
+        Ok(triggers.into_iter().map(|(trigger,)| trigger).collect())
+    }
+
+    async fn table_has_rows(&self, conn: &mut AnyConnection, table: &str) -> Result<bool, Error> {
+        let (has_rows,): (bool,) = sqlx::query_as(&format!(
+            "SELECT EXISTS (SELECT 1 FROM {})",
+            self.qualify(table)
+        ))
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Sqlx)?;
+
+        Ok(has_rows)
+    }
+
+    /// Writes the sql adding the values missing from enums in a diff.
+    ///
+    /// Postgres can't use enum values within the transaction adding them,
+    /// so they are added before the rest of the diff.
+    fn write_enum_values_sql(&self, diff: &SchemaDiff) -> String {
+        diff.enums
+            .iter()
+            .filter(|enum_change| matches!(enum_change, EnumChange::AddValues { .. }))
+            .map(|enum_change| self.write_enum_change_sql(enum_change))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn write_enum_change_sql(&self, enum_change: &EnumChange) -> String {
+        match enum_change {
+            EnumChange::Create(database_enum) => format!(
+                "CREATE TYPE {} AS ENUM ({});",
+                self.qualify(&database_enum.name),
+                database_enum
+                    .values
+                    .iter()
+                    .map(|value| quote_literal(value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            EnumChange::Drop(database_enum) => {
+                format!("DROP TYPE {};", self.qualify(&database_enum.name))
+            }
+            EnumChange::AddValues { name, values } => values
+                .iter()
+                .map(|value| {
+                    format!(
+                        "ALTER TYPE {} ADD VALUE IF NOT EXISTS {};",
+                        self.qualify(name),
+                        quote_literal(value)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    fn write_table_create_sql(&self, table: &DatabaseTable) -> String {
+        let mut sql = String::new();
+
+        writeln!(
+            sql,
+            "CREATE TABLE IF NOT EXISTS {} (",
+            self.qualify(&table.name)
+        )
+        .unwrap();
+
+        // Primary keys across several columns, or on backends without inline constraints, follow the columns
+        let primary_key = table.primary_key();
+        let inline_primary_key = primary_key.len() == 1 && self.backend.has_inline_constraints();
+        let columns: Vec<_> = table
+            .columns
+            .iter()
+            .map(|column| DatabaseColumn {
+                primary_key: column.primary_key && inline_primary_key,
+                ..column.clone()
+            })
+            .collect();
+        let table_primary_key = if inline_primary_key || primary_key.is_empty() {
+            None
+        } else {
+            Some(format!("PRIMARY KEY ({})", primary_key.join(", ")))
+        };
+        let column_constraints = columns
+            .iter()
+            .flat_map(|column| self.write_column_constraints_sql(&table.name, column));
+        let definitions: Vec<_> = columns
+            .iter()
+            .map(|column| self.write_column_sql(column))
+            .chain(table_primary_key)
+            .chain(column_constraints)
+            .chain(
+                table
+                    .constraints
+                    .iter()
+                    .filter(|constraint| !matches!(constraint.kind, ConstraintKind::PrimaryKey(_)))
+                    .map(|constraint| {
+                        format!("CONSTRAINT {} {}", constraint.name, constraint.kind)
+                    }),
+            )
+            .collect();
+        for (i, definition) in definitions.iter().enumerate() {
+            write!(sql, "  {}", definition).unwrap();
+
+            if i < definitions.len() - 1 {
+                writeln!(sql, ",").unwrap();
+            } else {
+                writeln!(sql).unwrap();
+            }
+        }
+
+        writeln!(sql, ");").unwrap();
+
+        let checks = table
+            .columns
+            .iter()
+            .filter_map(|column| {
+                let check = column.constraint.as_ref()?;
+                let name = DatabaseConstraint::column_check_name(&table.name, &column.name);
+                Some((name, check))
+            })
+            .chain(
+                table
+                    .constraints
+                    .iter()
+                    .filter_map(|constraint| match &constraint.kind {
+                        ConstraintKind::Check(check) => Some((constraint.name.clone(), check)),
+                        ConstraintKind::Unique(_) | ConstraintKind::PrimaryKey(_) => None,
+                    }),
+            );
+        for (name, check) in checks {
+            if let Some(comment_sql) = self.write_check_comment_sql(&table.name, &name, check) {
+                writeln!(sql, "{}", comment_sql).unwrap();
+            }
+        }
+        for index in &table.indexes {
+            writeln!(sql, "{}", self.write_index_create_sql(&table.name, index)).unwrap();
+        }
+
+        sql
+    }
+
+    /// Writes the expression of a check constraint as its comment.
+    ///
+    /// Postgres rewrites check expressions, so the comment is compared with the schema instead.
+    /// Backends which keep check expressions as written have no need for comments.
+    fn write_check_comment_sql(&self, table: &str, name: &str, check: &str) -> Option<String> {
+        if !self.backend.has_constraint_comments() {
+            return None;
+        }
+
+        Some(format!(
+            "COMMENT ON CONSTRAINT {} ON {} IS {};",
+            name,
+            self.qualify(table),
+            quote_literal(check)
+        ))
+    }
+
+    fn write_index_create_sql(&self, table: &str, index: &DatabaseIndex) -> String {
+        let mut sql = String::new();
+
+        write!(sql, "CREATE ").unwrap();
+        if index.unique {
+            write!(sql, "UNIQUE ").unwrap();
+        }
+        write!(sql, "INDEX {} ON {}", index.name, self.qualify(table)).unwrap();
+        if index.method != IndexMethod::default() {
+            write!(sql, " USING {}", index.method).unwrap();
+        }
+        write!(sql, " ({});", index.columns.join(", ")).unwrap();
+
+        sql
+    }
+
+    fn write_column_sql(&self, column: &DatabaseColumn) -> String {
+        let mut sql = String::new();
+
+        let ty = match (&column.default, self.backend) {
+            // Postgres serial columns are declared with a serial type, which creates their sequence
+            (Some(DatabaseDefault::Serial), Backend::Postgres) => match column.ty {
+                DatabaseType::SmallInt => "smallserial".to_string(),
+                DatabaseType::Integer => "serial".to_string(),
+                _ => "bigserial".to_string(),
+            },
+            _ => self.write_type_sql(&column.ty),
+        };
+        write!(sql, "{} {}", column.name, ty).unwrap();
+
+        if !column.nullable {
+            write!(sql, " NOT NULL",).unwrap();
+        }
+
+        let generated = matches!(
+            column.default,
+            Some(DatabaseDefault::Serial | DatabaseDefault::Identity)
+        );
+        match &column.default {
+            Some(DatabaseDefault::Serial) if self.backend == Backend::Postgres => {}
+            // SQLite only generates the values of an integer primary key, so its clause follows the primary key
+            Some(_) if generated && self.backend != Backend::Sqlite => {
+                write!(sql, " {}", self.backend.identity()).unwrap();
+            }
+            Some(_) if generated => {}
+            Some(default) => {
+                write!(sql, " DEFAULT {}", self.backend.write_default(default)).unwrap();
+            }
+            None => {}
+        }
+
+        if column.on_update_now && !self.backend.has_on_update_triggers() {
+            write!(sql, " ON UPDATE {}", self.backend.now()).unwrap();
+        }
+
+        if !self.backend.has_inline_constraints() {
+            return sql;
+        }
+
+        if column.unique {
+            write!(sql, " UNIQUE").unwrap();
+        }
+
+        if let Some(constraint) = &column.constraint {
+            write!(sql, " CHECK ({})", constraint).unwrap();
+        }
+
+        if column.primary_key {
+            write!(sql, " PRIMARY KEY").unwrap();
+            if generated && self.backend == Backend::Sqlite {
+                write!(sql, " {}", self.backend.identity()).unwrap();
+            }
+        }
+
+        if let Some((table, col)) = &column.references {
+            write!(sql, " REFERENCES {}({})", self.qualify(table), col).unwrap();
+        }
+
+        sql
+    }
+
+    /// Writes the constraints of a column as table constraints, on backends without inline constraints.
+    ///
+    /// Constraints are named as Postgres names inline constraints.
+    /// The primary key is written along with the table instead.
+    fn write_column_constraints_sql(&self, table: &str, column: &DatabaseColumn) -> Vec<String> {
+        let mut constraints = Vec::new();
+        if self.backend.has_inline_constraints() {
+            return constraints;
+        }
+
+        if column.unique {
+            constraints.push(format!(
+                "CONSTRAINT {} UNIQUE ({})",
+                DatabaseConstraint::default_unique_name(table, &[&column.name]),
+                column.name
+            ));
+        }
+
+        if let Some(check) = &column.constraint {
+            constraints.push(format!(
+                "CONSTRAINT {} CHECK ({})",
+                DatabaseConstraint::column_check_name(table, &column.name),
+                check
+            ));
+        }
+
+        if let Some((reference_table, reference_column)) = &column.references {
+            constraints.push(format!(
+                "CONSTRAINT {}_{}_fkey FOREIGN KEY ({}) REFERENCES {} ({})",
+                table,
+                column.name,
+                column.name,
+                self.qualify(reference_table),
+                reference_column
+            ));
+        }
+
+        constraints
+    }
+
+    fn write_table_change_sql(
+        &self,
+        table_change: &TableChange,
+        tables: &[DatabaseTable],
+    ) -> String {
+        match table_change {
+            TableChange::Create(table) => {
+                let mut sql = self.write_table_create_sql(table);
+                for trigger_sql in self.write_on_update_triggers_sql(table) {
+                    writeln!(sql, "{}", trigger_sql).unwrap();
+                }
+                sql
+            }
+            TableChange::Rename { from, to } => {
+                format!("ALTER TABLE {} RENAME TO {};", self.qualify(from), to)
+            }
+            TableChange::Drop(table) => format!("DROP TABLE {};", self.qualify(&table.name)),
+            TableChange::Alter {
+                table,
+                columns,
+                indexes,
+                constraints,
+            } if self.backend.rebuilds_tables()
+                && (!constraints.is_empty()
+                    || columns.iter().any(|column_change| {
+                        !matches!(
+                            column_change,
+                            ColumnChange::SetOnUpdateNow { .. }
+                                | ColumnChange::DropOnUpdateNow { .. }
+                        )
+                    })) =>
+            {
+                match tables.iter().find(|source| &source.name == table) {
+                    Some(source) => {
+                        self.write_table_rebuild_sql(source, columns, indexes, constraints)
+                    }
+                    None => String::new(),
+                }
+            }
+            TableChange::Alter {
+                table,
+                columns,
+                indexes,
+                constraints,
+            } => {
+                let mut sql = String::new();
+                // Indexes and constraints are dropped before changing columns,
+                // as dropping a column drops its indexes and constraints
+                for index_change in indexes {
+                    if let IndexChange::Drop(index) = index_change {
+                        let drop_sql = match self.backend {
+                            Backend::MySql => {
+                                format!("DROP INDEX {} ON {};", index.name, self.qualify(table))
+                            }
+                            Backend::Postgres | Backend::Sqlite => {
+                                format!("DROP INDEX {};", self.qualify(&index.name))
+                            }
+                        };
+                        writeln!(sql, "{}", drop_sql).unwrap();
+                    }
+                }
+                for constraint_change in constraints {
+                    if let ConstraintChange::Drop(constraint) = constraint_change {
+                        // MySQL unique constraints are unique indexes, and its primary keys are unnamed
+                        let drop = match (self.backend, &constraint.kind) {
+                            (Backend::MySql, ConstraintKind::PrimaryKey(_)) => {
+                                "PRIMARY KEY".to_string()
+                            }
+                            (Backend::MySql, ConstraintKind::Unique(_)) => {
+                                format!("INDEX {}", constraint.name)
+                            }
+                            _ => format!("CONSTRAINT {}", constraint.name),
+                        };
+                        writeln!(sql, "ALTER TABLE {} DROP {};", self.qualify(table), drop)
+                            .unwrap();
+                    }
+                }
+                // Backends which alter columns by redefining them redefine each altered column once,
+                // as it is after all of its changes.
+                // Rebuilt tables only get here to change their triggers
+                let redefines_columns =
+                    self.backend.alters_from_table() && !self.backend.rebuilds_tables();
+                let altered = if redefines_columns {
+                    tables
+                        .iter()
+                        .find(|source| &source.name == table)
+                        .map(|source| altered_table(source, columns, indexes, constraints))
+                } else {
+                    None
+                };
+                let mut redefined_columns = Vec::new();
+                for column_change in columns {
+                    match (column_change, &altered) {
+                        (
+                            ColumnChange::ChangeType { column, .. }
+                            | ColumnChange::SetDefault { column, .. }
+                            | ColumnChange::DropDefault { column, .. }
+                            | ColumnChange::SetNotNull { column }
+                            | ColumnChange::DropNotNull { column }
+                            | ColumnChange::SetOnUpdateNow { column }
+                            | ColumnChange::DropOnUpdateNow { column },
+                            Some(altered),
+                        ) => {
+                            if redefined_columns.contains(&column) {
+                                continue;
+                            }
+                            redefined_columns.push(column);
+                            if let Some(altered_column) =
+                                altered.columns.iter().find(|col| &col.name == column)
+                            {
+                                writeln!(
+                                    sql,
+                                    "ALTER TABLE {} MODIFY COLUMN {};",
+                                    self.qualify(table),
+                                    self.write_column_sql(altered_column)
+                                )
+                                .unwrap();
+                            }
+                        }
+                        _ => {
+                            let column_sql = self.write_column_change_sql(table, column_change);
+                            writeln!(sql, "{}", column_sql).unwrap();
+                        }
+                    }
+                }
+                for constraint_change in constraints {
+                    if let ConstraintChange::Add(constraint) = constraint_change {
+                        writeln!(
+                            sql,
+                            "ALTER TABLE {} ADD CONSTRAINT {} {};",
+                            self.qualify(table),
+                            constraint.name,
+                            constraint.kind
+                        )
+                        .unwrap();
+                        if let ConstraintKind::Check(check) = &constraint.kind {
+                            if let Some(comment_sql) =
+                                self.write_check_comment_sql(table, &constraint.name, check)
+                            {
+                                writeln!(sql, "{}", comment_sql).unwrap();
+                            }
+                        }
+                    }
+                }
+                for index_change in indexes {
+                    if let IndexChange::Create(index) = index_change {
+                        writeln!(sql, "{}", self.write_index_create_sql(table, index)).unwrap();
+                    }
+                }
+                sql
+            }
+        }
     }
 
-    fn write_table_create_sql(&self, table: &DatabaseTable) -> String {
-        let mut sql = String::new();
-
-        writeln!(sql, "CREATE TABLE IF NOT EXISTS {} (", table.name).unwrap();
-
-        for (i, column) in table.columns.iter().enumerate() {
-            write!(sql, "  {}", self.write_column_sql(column)).unwrap();
-
-            if i < table.columns.len() - 1 {
-                writeln!(sql, ",").unwrap();
-            } else {
-                writeln!(sql).unwrap();
-            }
+    /// Rebuilds a table with the changes of an alter, for backends which can't alter columns in place.
+    ///
+    /// The altered table is created under a temporary name and filled with the rows of the table, before replacing it.
+    fn write_table_rebuild_sql(
+        &self,
+        table: &DatabaseTable,
+        columns: &[ColumnChange],
+        indexes: &[IndexChange],
+        constraints: &[ConstraintChange],
+    ) -> String {
+        let altered = altered_table(table, columns, indexes, constraints);
+        let rebuilt_name = format!("{}{}", REBUILT_TABLE_PREFIX, altered.name);
+
+        let mut sql = self.write_table_create_sql(&DatabaseTable {
+            name: rebuilt_name.clone(),
+            indexes: Vec::new(),
+            ..altered.clone()
+        });
+
+        // Added columns have no rows to copy, and renamed columns are copied from their previous name
+        let (copied_columns, source_columns): (Vec<_>, Vec<_>) = altered
+            .columns
+            .iter()
+            .filter(|column| {
+                !columns.iter().any(|column_change| {
+                    matches!(column_change, ColumnChange::Add(added) if added.name == column.name)
+                })
+            })
+            .map(|column| {
+                let source = columns
+                    .iter()
+                    .rev()
+                    .find_map(|column_change| match column_change {
+                        ColumnChange::Rename { from, to } if to == &column.name => Some(from),
+                        _ => None,
+                    })
+                    .unwrap_or(&column.name);
+                (column.name.as_str(), source.as_str())
+            })
+            .unzip();
+        if !copied_columns.is_empty() {
+            writeln!(
+                sql,
+                "INSERT INTO {} ({}) SELECT {} FROM {};",
+                self.qualify(&rebuilt_name),
+                copied_columns.join(", "),
+                source_columns.join(", "),
+                self.qualify(&altered.name)
+            )
+            .unwrap();
         }
 
-        writeln!(sql, ");").unwrap();
+        writeln!(sql, "DROP TABLE {};", self.qualify(&altered.name)).unwrap();
+        writeln!(
+            sql,
+            "ALTER TABLE {} RENAME TO {};",
+            self.qualify(&rebuilt_name),
+            altered.name
+        )
+        .unwrap();
+        for index in &altered.indexes {
+            writeln!(sql, "{}", self.write_index_create_sql(&altered.name, index)).unwrap();
+        }
+        // Dropping the table dropped its triggers
+        for trigger_sql in self.write_on_update_triggers_sql(&altered) {
+            writeln!(sql, "{}", trigger_sql).unwrap();
+        }
 
         sql
     }
 
-    fn write_column_sql(&self, column: &DatabaseColumn) -> String {
-        let mut sql = String::new();
+    /// Returns `true` if a diff creates or alters a table with a column set to the current time on update.
+    fn sets_on_update(diff: &SchemaDiff) -> bool {
+        diff.tables.iter().any(|table_change| match table_change {
+            TableChange::Create(table) => table.columns.iter().any(|column| column.on_update_now),
+            TableChange::Alter { columns, .. } => {
+                columns.iter().any(|column_change| match column_change {
+                    ColumnChange::Add(column) => column.on_update_now,
+                    ColumnChange::SetOnUpdateNow { .. } => true,
+                    _ => false,
+                })
+            }
+            TableChange::Rename { .. } | TableChange::Drop(_) => false,
+        })
+    }
 
-        write!(sql, "{} {}", column.name, column.ty,).unwrap();
+    /// Writes the Postgres trigger function setting a column to the current time,
+    /// shared by the triggers of every table.
+    fn write_on_update_function_sql(&self) -> String {
+        format!(
+            "\
+CREATE OR REPLACE FUNCTION {}() RETURNS trigger AS $$
+BEGIN
+  NEW := jsonb_populate_record(NEW, jsonb_build_object(TG_ARGV[0], NOW()));
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;",
+            self.qualify(ON_UPDATE_FUNCTION)
+        )
+    }
 
-        if !column.nullable {
-            write!(sql, " NOT NULL",).unwrap();
+    /// Writes the triggers setting the columns of a table to the current time whenever a row is updated.
+    fn write_on_update_triggers_sql(&self, table: &DatabaseTable) -> Vec<String> {
+        if !self.backend.has_on_update_triggers() {
+            return Vec::new();
         }
 
-        if let Some(default) = &column.default {
-            write!(sql, " DEFAULT {}", default).unwrap();
-        }
+        table
+            .columns
+            .iter()
+            .filter(|column| column.on_update_now)
+            .map(|column| self.write_on_update_trigger_sql(&table.name, &column.name))
+            .collect()
+    }
 
-        if let Some(constraint) = &column.constraint {
-            write!(sql, " CHECK ({})", constraint).unwrap();
+    /// Writes the trigger setting a column to the current time whenever its row is updated.
+    ///
+    /// SQLite triggers can't assign to `NEW`, so the SQLite trigger updates the row again after the update.
+    /// It only fires when the update leaves the column unchanged, so it doesn't fire for its own update.
+    /// MySQL sets the column with an `ON UPDATE` clause in its definition instead.
+    fn write_on_update_trigger_sql(&self, table: &str, column: &str) -> String {
+        let trigger = self.backend.on_update_trigger_name(table, column);
+        let qualified_table = self.qualify(table);
+
+        match self.backend {
+            Backend::Postgres => format!(
+                "CREATE TRIGGER {} BEFORE UPDATE ON {} FOR EACH ROW EXECUTE PROCEDURE {}('{}');",
+                trigger,
+                qualified_table,
+                self.qualify(ON_UPDATE_FUNCTION),
+                column
+            ),
+            Backend::Sqlite => format!(
+                "CREATE TRIGGER {trigger} AFTER UPDATE ON {table} FOR EACH ROW WHEN NEW.{column} IS OLD.{column} BEGIN UPDATE {table} SET {column} = {now} WHERE rowid = NEW.rowid; END;",
+                trigger = self.qualify(&trigger),
+                table = qualified_table,
+                column = column,
+                now = self.backend.now()
+            ),
+            Backend::MySql => String::new(),
         }
+    }
 
-        if column.primary_key {
-            write!(sql, " PRIMARY KEY").unwrap();
-        }
+    /// Writes the drop of the trigger setting a column to the current time whenever its row is updated.
+    ///
+    /// MySQL columns are redefined without their `ON UPDATE` clause instead.
+    fn write_drop_on_update_trigger_sql(&self, table: &str, column: &str) -> String {
+        let trigger = self.backend.on_update_trigger_name(table, column);
 
-        if let Some((table, col)) = &column.references {
-            write!(sql, " REFERENCES {}({})", table, col).unwrap();
+        match self.backend {
+            Backend::Postgres => format!("DROP TRIGGER {} ON {};", trigger, self.qualify(table)),
+            Backend::Sqlite => format!("DROP TRIGGER {};", self.qualify(&trigger)),
+            Backend::MySql => String::new(),
         }
-
-        sql
     }
 
-    async fn write_sync_sql(&self, table: &DatabaseTable, db_columns: &[DatabaseColumn]) -> String {
-        let mut sql = String::new();
+    fn write_column_change_sql(&self, table: &str, column_change: &ColumnChange) -> String {
+        let qualified_table = self.qualify(table);
 
-        for schema_col in &table.columns {
-            let db_col = match db_columns
-                .iter()
-                .find(|db_col| db_col.name == schema_col.name)
-            {
-                Some(db_col) => db_col,
-                None => {
-                    // Column does not exist in DB
-                    writeln!(
-                        sql,
-                        "ALTER TABLE {} ADD COLUMN {};",
-                        table.name,
-                        self.write_column_sql(schema_col)
-                    )
-                    .unwrap();
-                    continue;
+        match column_change {
+            ColumnChange::Add(column) => {
+                // Primary keys are added by changes to the primary key
+                let column = &DatabaseColumn {
+                    primary_key: false,
+                    ..column.clone()
+                };
+                let mut sql = format!(
+                    "ALTER TABLE {} ADD COLUMN {};",
+                    qualified_table,
+                    self.write_column_sql(column)
+                );
+                for constraint in self.write_column_constraints_sql(table, column) {
+                    write!(sql, "\nALTER TABLE {} ADD {};", qualified_table, constraint).unwrap();
                 }
-            };
-
-            // Check for type mismatch
-            if schema_col.ty != db_col.ty {
-                writeln!(
-                    sql,
-                    "ALTER TABLE {table} ALTER COLUMN {column} TYPE {ty} USING {column}::{ty};",
-                    table = table.name,
-                    column = schema_col.name,
-                    ty = schema_col.ty.to_string(),
-                )
-                .unwrap();
-            }
-
-            // Check for nullable mismatch
-            if schema_col.nullable != db_col.nullable {
-                if db_col.nullable {
-                    writeln!(
-                        sql,
-                        "ALTER TABLE {table} ALTER COLUMN {column} SET NOT NULL;",
-                        table = table.name,
-                        column = schema_col.name
-                    )
-                    .unwrap();
-                } else {
-                    writeln!(
+                if let Some(check) = &column.constraint {
+                    let name = DatabaseConstraint::column_check_name(table, &column.name);
+                    if let Some(comment_sql) = self.write_check_comment_sql(table, &name, check) {
+                        write!(sql, "\n{}", comment_sql).unwrap();
+                    }
+                }
+                if column.on_update_now && self.backend.has_on_update_triggers() {
+                    write!(
                         sql,
-                        "ALTER TABLE {table} ALTER COLUMN {column} DROP NOT NULL;",
-                        table = table.name,
-                        column = schema_col.name
+                        "\n{}",
+                        self.write_on_update_trigger_sql(table, &column.name)
                     )
                     .unwrap();
                 }
+                sql
             }
-
-            // Check for default mismatch
-            if schema_col.default != db_col.default {
-                if let Some(default) = &schema_col.default {
-                    writeln!(
-                        sql,
-                        "ALTER TABLE {table} ALTER COLUMN {column} SET DEFAULT {default};",
-                        table = table.name,
-                        column = schema_col.name,
-                        default = default
-                    )
-                    .unwrap();
-                } else {
+            ColumnChange::Drop(column) => {
+                let mut sql = String::new();
+                // Postgres triggers outlive the columns they set
+                if column.on_update_now && self.backend == Backend::Postgres {
                     writeln!(
                         sql,
-                        "ALTER TABLE {table} ALTER COLUMN {column} DROP DEFAULT;",
-                        table = table.name,
-                        column = schema_col.name
+                        "{}",
+                        self.write_drop_on_update_trigger_sql(table, &column.name)
                     )
                     .unwrap();
                 }
+                write!(
+                    sql,
+                    "ALTER TABLE {} DROP COLUMN {};",
+                    qualified_table, column.name
+                )
+                .unwrap();
+                sql
             }
-
-            // Check for unique mismatch
-            if schema_col.unique != db_col.unique {
-                if db_col.unique {
-                    writeln!(
-                        sql,
-                        "ALTER TABLE {table} DROP CONSTRAINT {table}_{column}_key;",
-                        table = table.name,
-                        column = schema_col.name
-                    )
-                    .unwrap();
-                } else {
+            ColumnChange::Rename { from, to } => format!(
+                "ALTER TABLE {table} RENAME COLUMN {from} TO {to};",
+                table = qualified_table,
+                from = from,
+                to = to
+            ),
+            ColumnChange::ChangeType { column, to, .. } => format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} TYPE {ty} USING {column}::{ty};",
+                table = qualified_table,
+                column = column,
+                ty = self.write_type_sql(to),
+            ),
+            ColumnChange::SetDefault { column, from, to } => {
+                let mut sql = String::new();
+                // An identity column stops being one before it's given another default
+                if from == &Some(DatabaseDefault::Identity) {
                     writeln!(
                         sql,
-                        "ALTER TABLE {table} ADD CONSTRAINT {table}_{column}_key UNIQUE ({column});",
-                        table = table.name,
-                        column = schema_col.name
+                        "ALTER TABLE {} ALTER COLUMN {} DROP IDENTITY;",
+                        qualified_table, column
                     )
                     .unwrap();
                 }
-            }
-
-            // Check for references mismatch
-            if schema_col.references != db_col.references {
-                if let Some(references) = &schema_col.references {
-                    if db_col.references.is_some() {
+                let sequence = self.qualify(&format!("{}_{}_seq", table, column));
+                match to {
+                    DatabaseDefault::Identity => {
+                        // The sequence of a serial column would otherwise be kept alongside the identity's sequence
+                        if from == &Some(DatabaseDefault::Serial) {
+                            writeln!(
+                                sql,
+                                "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;",
+                                qualified_table, column
+                            )
+                            .unwrap();
+                            writeln!(sql, "DROP SEQUENCE IF EXISTS {};", sequence).unwrap();
+                        }
                         writeln!(
                             sql,
-                            "ALTER TABLE {table} DROP CONSTRAINT {table}_{column}_fkey;",
-                            table = table.name,
-                            column = schema_col.name
+                            "ALTER TABLE {} ALTER COLUMN {} ADD GENERATED BY DEFAULT AS IDENTITY;",
+                            qualified_table, column
                         )
                         .unwrap();
                     }
-                    writeln!(
-                        sql,
-                        "ALTER TABLE {table} ADD CONSTRAINT {table}_{column}_fkey FOREIGN KEY ({column}) REFERENCES {reference_table} ({reference_column});",
-                        table = table.name,
-                        column = schema_col.name,
-                        reference_table = references.0,
-                        reference_column = references.1,
-                    )
-                    .unwrap();
-                } else {
-                    writeln!(
-                        sql,
-                        "ALTER TABLE {table} DROP CONSTRAINT {table}_{column}_fkey;",
-                        table = table.name,
-                        column = schema_col.name
-                    )
-                    .unwrap();
+                    DatabaseDefault::Serial => {
+                        writeln!(
+                            sql,
+                            "CREATE SEQUENCE {} OWNED BY {}.{};",
+                            sequence, qualified_table, column
+                        )
+                        .unwrap();
+                        writeln!(
+                            sql,
+                            "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT nextval({});",
+                            qualified_table,
+                            column,
+                            quote_literal(&sequence)
+                        )
+                        .unwrap();
+                    }
+                    to => {
+                        write!(
+                            sql,
+                            "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};",
+                            qualified_table, column, to
+                        )
+                        .unwrap();
+                        return sql;
+                    }
                 }
-            }
-        }
-
-        // Delete columns that exist in db but don't exist in schema
-        db_columns
-            .iter()
-            .filter(|db_col| {
-                table
-                    .columns
-                    .iter()
-                    .all(|schema_col| schema_col.name != db_col.name)
-            })
-            .for_each(|db_col| {
-                writeln!(
+                // Generated values continue from the values already in the column
+                write!(
                     sql,
-                    "ALTER TABLE {table} DROP COLUMN {column};",
-                    table = table.name,
-                    column = db_col.name
+                    "SELECT setval(pg_get_serial_sequence({table_literal}, '{column}'), COALESCE(MAX({column}), 0) + 1, false) FROM {table};",
+                    table_literal = quote_literal(&qualified_table),
+                    table = qualified_table,
+                    column = column
                 )
                 .unwrap();
-            });
+                sql
+            }
+            ColumnChange::DropDefault {
+                column,
+                from: DatabaseDefault::Identity,
+            } => format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} DROP IDENTITY;",
+                table = qualified_table,
+                column = column
+            ),
+            ColumnChange::DropDefault { column, .. } => format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} DROP DEFAULT;",
+                table = qualified_table,
+                column = column
+            ),
+            ColumnChange::SetNotNull { column } => format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} SET NOT NULL;",
+                table = qualified_table,
+                column = column
+            ),
+            ColumnChange::DropNotNull { column } => format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} DROP NOT NULL;",
+                table = qualified_table,
+                column = column
+            ),
+            ColumnChange::SetOnUpdateNow { column } => {
+                self.write_on_update_trigger_sql(table, column)
+            }
+            ColumnChange::DropOnUpdateNow { column } => {
+                self.write_drop_on_update_trigger_sql(table, column)
+            }
+            ColumnChange::AddUnique { column } => format!(
+                "ALTER TABLE {qualified_table} ADD CONSTRAINT {table}_{column}_key UNIQUE ({column});",
+                qualified_table = qualified_table,
+                table = table,
+                column = column
+            ),
+            ColumnChange::DropUnique { column } => format!(
+                "ALTER TABLE {qualified_table} DROP {drop} {table}_{column}_key;",
+                qualified_table = qualified_table,
+                drop = match self.backend {
+                    Backend::MySql => "INDEX",
+                    Backend::Postgres | Backend::Sqlite => "CONSTRAINT",
+                },
+                table = table,
+                column = column
+            ),
+            ColumnChange::AddForeignKey { column, references } => format!(
+                "ALTER TABLE {qualified_table} ADD CONSTRAINT {table}_{column}_fkey FOREIGN KEY ({column}) REFERENCES {reference_table} ({reference_column});",
+                qualified_table = qualified_table,
+                table = table,
+                column = column,
+                reference_table = self.qualify(&references.0),
+                reference_column = references.1,
+            ),
+            ColumnChange::DropForeignKey { column, .. } => format!(
+                "ALTER TABLE {qualified_table} DROP {drop} {table}_{column}_fkey;",
+                qualified_table = qualified_table,
+                drop = match self.backend {
+                    Backend::MySql => "FOREIGN KEY",
+                    Backend::Postgres | Backend::Sqlite => "CONSTRAINT",
+                },
+                table = table,
+                column = column
+            ),
+            ColumnChange::AddCheck { column, check } => {
+                let name = DatabaseConstraint::column_check_name(table, column);
+                let mut sql = format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} CHECK ({});",
+                    qualified_table, name, check,
+                );
+                if let Some(comment_sql) = self.write_check_comment_sql(table, &name, check) {
+                    write!(sql, "\n{}", comment_sql).unwrap();
+                }
+                sql
+            }
+            ColumnChange::DropCheck { column, .. } => format!(
+                "ALTER TABLE {} DROP CONSTRAINT {};",
+                qualified_table,
+                DatabaseConstraint::column_check_name(table, column)
+            ),
+        }
+    }
 
-        sql
+    /// Qualifies the name of a table, type or index with the schema.
+    fn qualify(&self, name: &str) -> String {
+        self.backend.qualify(&self.schema, name)
     }
+
+    /// Writes a column type, qualifying enum types with the schema.
+    fn write_type_sql(&self, ty: &DatabaseType) -> String {
+        self.backend.write_type(&self.schema, ty)
+    }
+}
+
+/// Splits the comma separated columns of an index or constraint.
+fn split_columns(columns: &str) -> Vec<String> {
+    columns
+        .split(',')
+        .filter(|column| !column.is_empty())
+        .map(ToString::to_string)
+        .collect()
 }
 
+const FETCH_ENUM_QUERY: &str = "
+SELECT e.enumlabel::text
+FROM pg_enum e
+JOIN pg_type t ON t.oid = e.enumtypid
+JOIN pg_namespace n ON n.oid = t.typnamespace
+WHERE
+    n.nspname = $1 AND
+    t.typname = $2
+ORDER BY e.enumsortorder
+";
+
+const FETCH_INDEXES_QUERY: &str = "
+SELECT
+    i.relname::text AS index_name,
+    array_to_string(ARRAY(
+        SELECT a.attname::text
+        FROM unnest(ix.indkey::int2[]) WITH ORDINALITY AS k(attnum, position)
+        JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+        ORDER BY k.position
+    ), ',') AS columns,
+    ix.indisunique AS is_unique,
+    am.amname::text AS method
+FROM pg_index ix
+JOIN pg_class t ON t.oid = ix.indrelid
+JOIN pg_class i ON i.oid = ix.indexrelid
+JOIN pg_namespace n ON n.oid = t.relnamespace
+JOIN pg_am am ON am.oid = i.relam
+WHERE
+    n.nspname = $1 AND
+    t.relname = $2 AND
+    NOT EXISTS (SELECT 1 FROM pg_constraint c WHERE c.conindid = ix.indexrelid)
+ORDER BY i.relname
+";
+
+const FETCH_CONSTRAINTS_QUERY: &str = "
+SELECT
+    c.conname::text AS constraint_name,
+    c.contype::text AS constraint_type,
+    array_to_string(ARRAY(
+        SELECT a.attname::text
+        FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, position)
+        JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+        ORDER BY k.position
+    ), ',') AS columns,
+    obj_description(c.oid, 'pg_constraint') AS check_comment,
+    pg_get_expr(c.conbin, c.conrelid) AS check_expr
+FROM pg_constraint c
+JOIN pg_class t ON t.oid = c.conrelid
+JOIN pg_namespace n ON n.oid = t.relnamespace
+WHERE
+    n.nspname = $1 AND
+    t.relname = $2 AND
+    (
+        (c.contype = 'u' AND array_length(c.conkey, 1) > 1) OR
+        c.contype IN ('c', 'p')
+    )
+ORDER BY c.conname
+";
+
+const CHECK_PROBE_TABLE: &str = "pg_temp.awto_check_probe";
+const CHECK_PROBE_NAME: &str = "awto_check_probe_check";
+
+const FETCH_CHECK_PROBE_QUERY: &str = "
+SELECT pg_get_expr(c.conbin, c.conrelid)
+FROM pg_constraint c
+WHERE c.conrelid = 'pg_temp.awto_check_probe'::regclass AND c.contype = 'c'
+";
+
+const FETCH_TRIGGERS_QUERY: &str = "
+SELECT tr.tgname::text
+FROM pg_trigger tr
+JOIN pg_class t ON t.oid = tr.tgrelid
+JOIN pg_namespace n ON n.oid = t.relnamespace
+WHERE
+    n.nspname = $1 AND
+    t.relname = $2 AND
+    NOT tr.tgisinternal
+ORDER BY tr.tgname
+";
+
 const FETCH_TABLE_QUERY: &str = "
-SELECT column_name, column_default, is_nullable, data_type, character_maximum_length,
+SELECT column_name, column_default, is_nullable, data_type, udt_name::text, character_maximum_length,
+numeric_precision::integer, numeric_scale::integer, is_identity = 'YES' AS is_identity,
+(
+    SELECT
+        COUNT(*) > 0
+    FROM pg_type t
+    JOIN pg_namespace n ON n.oid = t.typnamespace
+    WHERE
+        t.typtype = 'e' AND
+        t.typname = information_schema.columns.udt_name AND
+        n.nspname = information_schema.columns.udt_schema
+) as is_enum,
 (
     SELECT
         COUNT(*) > 0
@@ -639,7 +2667,14 @@ SELECT column_name, column_default, is_nullable, data_type, character_maximum_le
         tco.constraint_type = 'UNIQUE' AND
         kcu.table_schema = $1 AND
         kcu.table_name = $2 AND
-        kcu.column_name = information_schema.columns.column_name
+        kcu.column_name = information_schema.columns.column_name AND
+        (
+            SELECT COUNT(*)
+            FROM information_schema.key_column_usage constraint_kcu
+            WHERE
+                constraint_kcu.constraint_schema = tco.constraint_schema AND
+                constraint_kcu.constraint_name = tco.constraint_name
+        ) = 1
 ) as is_unique,
 (
     SELECT CONCAT(
@@ -679,6 +2714,7 @@ SELECT column_name, column_default, is_nullable, data_type, character_maximum_le
         rco.unique_constraint_name = rel_tco.constraint_name
     WHERE
         tco.constraint_type = 'FOREIGN KEY' AND
+        kcu.table_schema = $1 AND
         kcu.table_name = $2 AND
         kcu.column_name = information_schema.columns.column_name
     GROUP BY
@@ -695,3 +2731,685 @@ FROM information_schema.columns
 WHERE table_schema = $1
 AND table_name = $2;
 ";
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "sqlite")]
+    use awto::tests_cfg::Review;
+    use awto::{
+        database::IntoDatabaseTable,
+        tests_cfg::{Product, ProductTag, Tag},
+    };
+    #[cfg(feature = "sqlite")]
+    use std::fs;
+
+    #[cfg(feature = "sqlite")]
+    use sqlx::{any::AnyPoolOptions, Executor};
+
+    use super::*;
+
+    #[cfg(feature = "sqlite")]
+    fn table_model(table: DatabaseTable) -> Model {
+        Model {
+            name: table.name.clone(),
+            roles: vec![Role::DatabaseTable(table)],
+            fields: vec![],
+            variants: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn qualify_sql_with_schema() {
+        let pool = AnyPool::connect_lazy("postgres://localhost/awto").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![]).with_schema(r#"my"shop"#);
+
+        let status = DatabaseColumn {
+            name: "status".to_string(),
+            ty: DatabaseType::Enum("order_status".to_string()),
+            nullable: false,
+            default: None,
+            on_update_now: false,
+            unique: false,
+            constraint: None,
+            primary_key: false,
+            references: None,
+            renamed_from: vec![],
+        };
+        let diff = SchemaDiff {
+            enums: vec![EnumChange::Create(DatabaseEnum {
+                name: "order_status".to_string(),
+                values: vec!["pending".to_string()],
+            })],
+            tables: vec![
+                TableChange::Rename {
+                    from: "order".to_string(),
+                    to: "orders".to_string(),
+                },
+                TableChange::Alter {
+                    table: "orders".to_string(),
+                    columns: vec![
+                        ColumnChange::Add(status),
+                        ColumnChange::AddForeignKey {
+                            column: "product_id".to_string(),
+                            references: ("product".to_string(), "id".to_string()),
+                        },
+                    ],
+                    indexes: vec![IndexChange::Drop(DatabaseIndex {
+                        name: "orders_total_idx".to_string(),
+                        columns: vec!["total".to_string()],
+                        unique: false,
+                        method: IndexMethod::BTree,
+                    })],
+                    constraints: vec![],
+                },
+            ],
+        };
+
+        assert_eq!(
+            compiler.write_diff_sql(&diff, &[]),
+            r#"CREATE TYPE "my""shop".order_status AS ENUM ('pending');
+ALTER TABLE "my""shop".order RENAME TO orders;
+DROP INDEX "my""shop".orders_total_idx;
+ALTER TABLE "my""shop".orders ADD COLUMN status "my""shop".order_status NOT NULL;
+ALTER TABLE "my""shop".orders ADD CONSTRAINT orders_product_id_fkey FOREIGN KEY (product_id) REFERENCES "my""shop".product (id);"#
+        );
+    }
+
+    #[tokio::test]
+    async fn json_conversions() {
+        let pool = AnyPool::connect_lazy("postgres://localhost/awto").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, awto::tests_cfg::MODELS.to_vec());
+        let code = compiler.compile_generated_code();
+
+        // Json which doesn't match the field's type is an error rather than a panic
+        assert!(code.contains(
+            "impl :: std :: convert :: TryFrom < crate :: product_reviews :: Model > for :: schema :: Review"
+        ));
+        assert!(code.contains(
+            "TryFromDatabaseError :: InvalidJson (\"product_reviews.tags\" . to_string ())"
+        ));
+        assert!(!code.contains("invalid json in column 'product_reviews.tags'"));
+    }
+
+    #[tokio::test]
+    async fn quote_schema_in_literals() {
+        let pool = AnyPool::connect_lazy("postgres://localhost/awto").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![]).with_schema("shop's");
+
+        let diff = SchemaDiff {
+            enums: vec![],
+            tables: vec![TableChange::Alter {
+                table: "tag".to_string(),
+                columns: vec![ColumnChange::SetDefault {
+                    column: "id".to_string(),
+                    from: None,
+                    to: DatabaseDefault::Serial,
+                }],
+                indexes: vec![],
+                constraints: vec![],
+            }],
+        };
+
+        assert_eq!(
+            compiler.write_diff_sql(&diff, &[]),
+            r#"CREATE SEQUENCE "shop's".tag_id_seq OWNED BY "shop's".tag.id;
+ALTER TABLE "shop's".tag ALTER COLUMN id SET DEFAULT nextval('"shop''s".tag_id_seq');
+SELECT setval(pg_get_serial_sequence('"shop''s".tag', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM "shop's".tag;"#
+        );
+    }
+
+    #[tokio::test]
+    async fn drop_renamed_table_named_columns() {
+        let mut product = Product::database_table();
+        for column in &mut product.columns {
+            if column.name == "name" {
+                column.unique = true;
+            }
+        }
+
+        // Postgres constraints are named after the table, while its triggers are named after the column
+        let pool = AnyPool::connect_lazy("postgres://localhost/awto").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![]);
+        let mut db_table = product.clone();
+        assert_eq!(
+            compiler.drop_table_named_columns(&mut db_table, "products"),
+            Some(TableChange::Alter {
+                table: "product".to_string(),
+                columns: vec![
+                    ColumnChange::DropUnique {
+                        column: "name".to_string(),
+                    },
+                    ColumnChange::DropCheck {
+                        column: "price".to_string(),
+                        check: "price >= 0".to_string(),
+                    },
+                ],
+                indexes: vec![],
+                constraints: vec![],
+            })
+        );
+        assert_eq!(
+            diff_table(
+                &DatabaseTable {
+                    name: "products".to_string(),
+                    ..product.clone()
+                },
+                Some(&db_table)
+            ),
+            Some(TableChange::Alter {
+                table: "products".to_string(),
+                columns: vec![
+                    ColumnChange::AddUnique {
+                        column: "name".to_string(),
+                    },
+                    ColumnChange::AddCheck {
+                        column: "price".to_string(),
+                        check: "price >= 0".to_string(),
+                    },
+                ],
+                indexes: vec![],
+                constraints: vec![],
+            })
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn drop_renamed_sqlite_table_named_columns() {
+        let mut product = Product::database_table();
+        for column in &mut product.columns {
+            if column.name == "name" {
+                column.unique = true;
+            }
+        }
+
+        // SQLite triggers are named after the table, while its constraints are found by their columns
+        let pool = AnyPool::connect_lazy("sqlite::memory:").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![]);
+        let mut db_table = product;
+        assert_eq!(
+            compiler.drop_table_named_columns(&mut db_table, "products"),
+            Some(TableChange::Alter {
+                table: "product".to_string(),
+                columns: vec![ColumnChange::DropOnUpdateNow {
+                    column: "updated_at".to_string(),
+                }],
+                indexes: vec![],
+                constraints: vec![],
+            })
+        );
+        assert!(db_table.columns.iter().all(|column| !column.on_update_now));
+    }
+
+    #[tokio::test]
+    async fn write_primary_key_sql() {
+        let pool = AnyPool::connect_lazy("postgres://localhost/awto").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![]);
+
+        // Primary keys across several columns follow the columns,
+        // and `updated_at` is set by a trigger
+        let create_diff = SchemaDiff {
+            enums: vec![],
+            tables: vec![
+                TableChange::Create(Tag::database_table()),
+                TableChange::Create(ProductTag::database_table()),
+            ],
+        };
+        assert_eq!(
+            compiler.write_diff_sql(&create_diff, &[]),
+            r#"CREATE OR REPLACE FUNCTION "public".awto_set_updated_at() RETURNS trigger AS $$
+BEGIN
+  NEW := jsonb_populate_record(NEW, jsonb_build_object(TG_ARGV[0], NOW()));
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE TABLE IF NOT EXISTS "public".tag (
+  id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  inserted_at timestamp with time zone NOT NULL DEFAULT NOW(),
+  updated_at timestamp with time zone NOT NULL DEFAULT NOW(),
+  name character varying(40) NOT NULL UNIQUE
+);
+CREATE TRIGGER updated_at_on_update BEFORE UPDATE ON "public".tag FOR EACH ROW EXECUTE PROCEDURE "public".awto_set_updated_at('updated_at');
+
+CREATE TABLE IF NOT EXISTS "public".product_tag (
+  product_id uuid NOT NULL REFERENCES "public".product(id),
+  tag_id bigint NOT NULL REFERENCES "public".tag(id),
+  PRIMARY KEY (product_id, tag_id)
+);"#
+        );
+
+        // A changed primary key is dropped and added again
+        let mut db_product_tag = ProductTag::database_table();
+        for column in &mut db_product_tag.columns {
+            column.primary_key = column.name == "product_id";
+        }
+        db_product_tag.constraints.push(DatabaseConstraint {
+            name: "product_tag_pkey".to_string(),
+            kind: ConstraintKind::PrimaryKey(vec!["product_id".to_string()]),
+        });
+        let alter_diff = SchemaDiff {
+            enums: vec![],
+            tables: diff_table(&ProductTag::database_table(), Some(&db_product_tag))
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(
+            compiler.write_diff_sql(&alter_diff, &[]),
+            r#"ALTER TABLE "public".product_tag DROP CONSTRAINT product_tag_pkey;
+ALTER TABLE "public".product_tag ADD CONSTRAINT product_tag_pkey PRIMARY KEY (product_id, tag_id);"#
+        );
+    }
+
+    #[cfg(feature = "mysql")]
+    #[tokio::test]
+    async fn write_mysql_sql() {
+        let pool = AnyPool::connect_lazy("mysql://localhost/awto").unwrap();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![]);
+        let mut product = Product::database_table();
+        for column in &mut product.columns {
+            if column.name == "name" {
+                column.unique = true;
+            }
+        }
+        // MySQL only indexes text columns with a maximum length
+        let product = Backend::MySql.table(&product).unwrap();
+
+        // Constraints are declared after the columns
+        let create_diff = SchemaDiff {
+            enums: vec![],
+            tables: vec![TableChange::Create(product.clone())],
+        };
+        assert_eq!(
+            compiler.write_diff_sql(&create_diff, &[]),
+            "\
+CREATE TABLE IF NOT EXISTS product (
+  id binary(16) NOT NULL DEFAULT (uuid_to_bin(uuid())),
+  created_at datetime(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+  updated_at datetime(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6),
+  name varchar(255) NOT NULL,
+  price bigint NOT NULL DEFAULT 0,
+  description varchar(120),
+  PRIMARY KEY (id),
+  CONSTRAINT product_name_key UNIQUE (name),
+  CONSTRAINT product_price_check CHECK (price >= 0)
+);
+CREATE INDEX product_name_idx ON product (name);"
+        );
+
+        // Altered columns are redefined once, as they are after all of their changes
+        let alter_diff = SchemaDiff {
+            enums: vec![],
+            tables: vec![TableChange::Alter {
+                table: "product".to_string(),
+                columns: vec![
+                    ColumnChange::ChangeType {
+                        column: "description".to_string(),
+                        from: DatabaseType::Text(Some(120)),
+                        to: DatabaseType::Text(Some(240)),
+                    },
+                    ColumnChange::SetNotNull {
+                        column: "description".to_string(),
+                    },
+                    ColumnChange::DropUnique {
+                        column: "name".to_string(),
+                    },
+                ],
+                indexes: vec![],
+                constraints: vec![],
+            }],
+        };
+        assert_eq!(
+            compiler.write_diff_sql(&alter_diff, &[product]),
+            "\
+ALTER TABLE product MODIFY COLUMN description varchar(240) NOT NULL;
+ALTER TABLE product DROP INDEX product_name_key;"
+        );
+    }
+
+    /// Asserts that updating a row of a table sets its `updated_at` column.
+    #[cfg(feature = "sqlite")]
+    async fn assert_updated_at_on_update(pool: &AnyPool, table: &str) {
+        pool.execute(format!("UPDATE {} SET updated_at = '2000-01-01 00:00:00'", table).as_str())
+            .await
+            .unwrap();
+        pool.execute(format!("UPDATE {} SET price = price + 1", table).as_str())
+            .await
+            .unwrap();
+        let (updated_at,): (String,) = sqlx::query_as(&format!("SELECT updated_at FROM {}", table))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert!(updated_at.as_str() > "2000-01-01 00:00:00");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sync_sqlite() {
+        // A single connection keeps the in-memory database alive
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let product = Product::database_table();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![table_model(product.clone())]);
+
+        sync_database(&compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(compiler.plan().await.unwrap().sql, "");
+
+        pool.execute(
+            "INSERT INTO product (id, name, price, description) VALUES (randomblob(16), 'chair', 40, 'oak')",
+        )
+        .await
+        .unwrap();
+
+        // SQLite can't alter columns in place, so the table is rebuilt
+        let mut altered_product = product.clone();
+        for column in &mut altered_product.columns {
+            match column.name.as_str() {
+                "description" => {
+                    column.name = "details".to_string();
+                    column.renamed_from = vec!["description".to_string()];
+                    column.ty = DatabaseType::Text(Some(240));
+                }
+                "price" => column.constraint = Some("price > 0".to_string()),
+                _ => {}
+            }
+        }
+        let altered_compiler =
+            DatabaseCompiler::from_pool(&pool, vec![table_model(altered_product)]);
+        let plan = altered_compiler.plan().await.unwrap();
+        assert!(plan.sql.contains("DROP TABLE product;"));
+
+        sync_database(&altered_compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(altered_compiler.plan().await.unwrap().sql, "");
+        let (details,): (String,) = sqlx::query_as("SELECT details FROM product")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(details, "oak");
+        assert_updated_at_on_update(&pool, "product").await;
+
+        // The down sql restores the table
+        pool.execute(plan.down_sql.as_str()).await.unwrap();
+        assert_eq!(compiler.plan().await.unwrap().sql, "");
+
+        // Triggers are named after their table, so they're created again when it's renamed
+        let renamed_product = DatabaseTable {
+            name: "products".to_string(),
+            renamed_from: vec!["product".to_string()],
+            ..product
+        };
+        let renamed_compiler =
+            DatabaseCompiler::from_pool(&pool, vec![table_model(renamed_product)]);
+        sync_database(&renamed_compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(renamed_compiler.plan().await.unwrap().sql, "");
+        let triggers: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'trigger'")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            triggers,
+            vec![("products_updated_at_on_update".to_string(),)]
+        );
+        assert_updated_at_on_update(&pool, "products").await;
+
+        // SQLite has no array types
+        let review_compiler =
+            DatabaseCompiler::from_pool(&pool, vec![table_model(Review::database_table())]);
+        assert!(matches!(
+            review_compiler.plan().await,
+            Err(Error::UnsupportedBackendType(Backend::Sqlite, _, _))
+        ));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sync_sqlite_primary_keys() {
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let compiler = DatabaseCompiler::from_pool(
+            &pool,
+            vec![
+                table_model(Product::database_table()),
+                table_model(Tag::database_table()),
+                table_model(ProductTag::database_table()),
+            ],
+        );
+
+        let plan = compiler.plan().await.unwrap();
+        assert!(plan
+            .sql
+            .contains("id integer NOT NULL PRIMARY KEY AUTOINCREMENT"));
+        assert!(plan.sql.contains("PRIMARY KEY (product_id, tag_id)"));
+
+        sync_database(&compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(compiler.plan().await.unwrap().sql, "");
+
+        pool.execute("INSERT INTO tag (name) VALUES ('oak'), ('pine')")
+            .await
+            .unwrap();
+        let (id,): (i64,) = sqlx::query_as("SELECT MAX(id) FROM tag")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(id, 2);
+
+        // `updated_at` is set by a trigger whenever a row is updated, unless the update sets it
+        pool.execute("UPDATE tag SET updated_at = '2000-01-01 00:00:00'")
+            .await
+            .unwrap();
+        pool.execute("UPDATE tag SET name = 'birch' WHERE id = 1")
+            .await
+            .unwrap();
+        let updated: Vec<(String,)> = sqlx::query_as("SELECT updated_at FROM tag ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert!(updated[0].0.as_str() > "2000-01-01 00:00:00");
+        assert_eq!(updated[1].0, "2000-01-01 00:00:00");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn sync_sqlite_renamed_constraints() {
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let product = Product::database_table();
+        let mut tag = Tag::database_table();
+        let mut product_id = product.columns[0].clone();
+        product_id.name = "product_id".to_string();
+        product_id.nullable = true;
+        product_id.default = None;
+        product_id.unique = true;
+        product_id.primary_key = false;
+        product_id.references = Some(("product".to_string(), "id".to_string()));
+        tag.columns.push(product_id);
+        let compiler = DatabaseCompiler::from_pool(
+            &pool,
+            vec![table_model(product.clone()), table_model(tag.clone())],
+        );
+        sync_database(&compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+
+        // Unique and foreign key columns are renamed along with their constraints
+        let mut renamed_tag = tag;
+        for column in &mut renamed_tag.columns {
+            if column.name == "product_id" {
+                column.name = "item_id".to_string();
+                column.renamed_from = vec!["product_id".to_string()];
+            }
+        }
+        let renamed_compiler = DatabaseCompiler::from_pool(
+            &pool,
+            vec![
+                table_model(product.clone()),
+                table_model(renamed_tag.clone()),
+            ],
+        );
+        sync_database(&renamed_compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(renamed_compiler.plan().await.unwrap().sql, "");
+
+        // The renamed constraints can then be dropped
+        let mut dropped_tag = renamed_tag;
+        for column in &mut dropped_tag.columns {
+            if column.name == "item_id" {
+                column.unique = false;
+                column.references = None;
+            }
+        }
+        let dropped_compiler = DatabaseCompiler::from_pool(
+            &pool,
+            vec![table_model(product), table_model(dropped_tag)],
+        );
+        sync_database(&dropped_compiler, &CompileDatabaseOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(dropped_compiler.plan().await.unwrap().sql, "");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn write_sqlite_migrations() {
+        let dir = env::temp_dir().join(format!("awto-migrations-{}", uuid::Uuid::new_v4()));
+        let migrations_dir = dir.join("migrations");
+        fs::create_dir_all(&dir).unwrap();
+        let uri = format!("sqlite:{}?mode=rwc", dir.join("awto.db").display());
+        let pool = AnyPool::connect(&uri).await.unwrap();
+        let options = CompileDatabaseOptions {
+            migrations_dir: Some(migrations_dir.clone()),
+            ..Default::default()
+        };
+        let compiler =
+            DatabaseCompiler::from_pool(&pool, vec![table_model(Product::database_table())]);
+
+        // Migrations are only written, and the changes after pending migrations aren't written again
+        let result = sync_database(&compiler, &options).await.unwrap();
+        assert!(result.migration_file.is_some());
+        let result = sync_database(&compiler, &options).await.unwrap();
+        assert_eq!(result.migration_file, None);
+        assert_eq!(read_migrations(&migrations_dir).unwrap().len(), 1);
+        assert!(compiler.plan().await.unwrap().sql.contains("CREATE TABLE"));
+
+        let apply_options = CompileDatabaseOptions {
+            apply_migrations: true,
+            ..options
+        };
+        let result = sync_database(&compiler, &apply_options).await.unwrap();
+        assert_eq!(result.migration_file, None);
+        assert_eq!(compiler.plan().await.unwrap().sql, "");
+
+        pool.close().await;
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn rollback_sqlite_migrations() {
+        let dir = env::temp_dir().join(format!("awto-rollback-{}", uuid::Uuid::new_v4()));
+        let migrations_dir = dir.join("migrations");
+        fs::create_dir_all(&dir).unwrap();
+        let uri = format!("sqlite:{}?mode=rwc", dir.join("awto.db").display());
+        let pool = AnyPool::connect(&uri).await.unwrap();
+        let options = CompileDatabaseOptions {
+            migrations_dir: Some(migrations_dir.clone()),
+            apply_migrations: true,
+            ..Default::default()
+        };
+
+        let product = Product::database_table();
+        let compiler = DatabaseCompiler::from_pool(&pool, vec![table_model(product.clone())]);
+        sync_database(&compiler, &options).await.unwrap();
+
+        let mut altered_product = product;
+        let mut stock = altered_product
+            .columns
+            .iter()
+            .find(|column| column.name == "description")
+            .unwrap()
+            .clone();
+        stock.name = "stock".to_string();
+        altered_product.columns.push(stock);
+        let altered_compiler =
+            DatabaseCompiler::from_pool(&pool, vec![table_model(altered_product)]);
+        sync_database(&altered_compiler, &options).await.unwrap();
+        assert_eq!(read_migrations(&migrations_dir).unwrap().len(), 2);
+
+        let rolled_back = rollback_database(
+            &uri,
+            RollbackOptions {
+                allow_irreversible: true,
+                migrations_dir: Some(migrations_dir.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(rolled_back[0].version, 2);
+
+        // The rolled back migration is reverted by a migration of its own, keeping its file
+        let migrations = read_migrations(&migrations_dir).unwrap();
+        assert_eq!(migrations.len(), 3);
+        assert_eq!(migrations[2].version, 3);
+        assert_eq!(migrations[2].name, "revert_0002");
+
+        // The rolled back migration isn't applied again
+        let result = sync_database(&compiler, &options).await.unwrap();
+        assert_eq!(result.migration_file, None);
+        assert_eq!(compiler.plan().await.unwrap().sql, "");
+
+        // Rolling back again skips the revert and the reverted migration
+        let rolled_back = rollback_database(
+            &uri,
+            RollbackOptions {
+                allow_irreversible: true,
+                migrations_dir: Some(migrations_dir.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(rolled_back[0].version, 1);
+        assert_eq!(read_migrations(&migrations_dir).unwrap().len(), 4);
+
+        // Pending migration files are applied before rolling back
+        write_migration(
+            &migrations_dir,
+            &Migration::new(5, "sync", "CREATE TABLE pending (id integer);\n"),
+        )
+        .unwrap();
+        let result = rollback_database(
+            &uri,
+            RollbackOptions {
+                migrations_dir: Some(migrations_dir.clone()),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<Error>(),
+            Some(Error::PendingMigrations(_))
+        ));
+
+        pool.close().await;
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,618 @@
+use std::fmt;
+
+use awto::database::{ConstraintKind, DatabaseDefault, DatabaseTable, DatabaseType, IndexMethod};
+use sqlx::{any::AnyKind, AnyConnection};
+
+use crate::error::Error;
+
+/// Raw default of uuid columns, generating a random uuid.
+const UUID_DEFAULT: &str = "uuid_generate_v4()";
+/// Raw default of timestamp columns, the current time.
+const NOW_DEFAULT: &str = "NOW()";
+/// MySQL default of uuid columns, as MySQL reports it.
+const MYSQL_UUID_DEFAULT: &str = "uuid_to_bin(uuid())";
+/// Precision and scale of MySQL decimals without a precision, the largest MySQL allows.
+const MYSQL_MAX_NUMERIC: (u16, u16) = (65, 30);
+/// Precision and scale of MySQL decimals storing money.
+const MYSQL_MONEY: (u16, u16) = (19, 4);
+/// Maximum length of MySQL columns storing enums.
+const MYSQL_ENUM_LEN: i32 = 255;
+/// Maximum length of MySQL text columns in keys, as MySQL can't index `TEXT` columns.
+const MYSQL_KEY_LEN: i32 = 255;
+
+/// Database the database compiler syncs, deciding the sql it writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Postgres,
+    /// SQLite can't alter columns in place, so tables with altered columns are rebuilt.
+    ///
+    /// SQLite has no schemas or enum types, enums are stored as text.
+    Sqlite,
+    /// MySQL columns are altered by redefining them as a whole.
+    ///
+    /// MySQL has no schemas apart from databases, so tables are synced into the connection's database.
+    /// Enums are stored as text, and timestamps in `DATETIME(6)` columns.
+    /// MySQL implicitly commits schema changes, so they are not atomic even within a transaction.
+    MySql,
+}
+
+impl Backend {
+    /// Returns the backend of a connection.
+    pub fn of(conn: &AnyConnection) -> Self {
+        conn.kind().into()
+    }
+
+    /// Qualifies the name of a table, type or index with its schema, on backends with schemas.
+    ///
+    /// The schema is quoted, as it can be any name.
+    pub fn qualify(&self, schema: &str, name: &str) -> String {
+        match self {
+            Self::Postgres => format!("{}.{}", quote_identifier(schema), name),
+            Self::Sqlite | Self::MySql => name.to_string(),
+        }
+    }
+
+    /// Returns the placeholder of the `n`th parameter of a query, starting from 1.
+    pub fn param(&self, n: usize) -> String {
+        match self {
+            Self::Postgres | Self::Sqlite => format!("${}", n),
+            Self::MySql => "?".to_string(),
+        }
+    }
+
+    /// Returns `true` if the backend has enum types, rather than storing enums as text.
+    pub fn has_enum_types(&self) -> bool {
+        matches!(self, Self::Postgres)
+    }
+
+    /// Returns `true` if the expressions of check constraints are stored as comments to compare them with the schema,
+    /// as Postgres rewrites check expressions.
+    pub fn has_constraint_comments(&self) -> bool {
+        matches!(self, Self::Postgres)
+    }
+
+    /// Returns `true` if columns are altered by rebuilding their table.
+    pub fn rebuilds_tables(&self) -> bool {
+        matches!(self, Self::Sqlite)
+    }
+
+    /// Returns `true` if altering a table needs its columns before the changes,
+    /// to rebuild the table or to redefine its columns.
+    pub fn alters_from_table(&self) -> bool {
+        matches!(self, Self::Sqlite | Self::MySql)
+    }
+
+    /// Returns `true` if columns are declared with their unique, check and foreign key constraints inline.
+    ///
+    /// MySQL ignores inline foreign keys and names inline constraints itself,
+    /// so constraints are declared after the columns instead.
+    pub fn has_inline_constraints(&self) -> bool {
+        !matches!(self, Self::MySql)
+    }
+
+    /// Returns the expression of the current time.
+    pub fn now(&self) -> &'static str {
+        match self {
+            Self::Postgres => NOW_DEFAULT,
+            Self::Sqlite => "CURRENT_TIMESTAMP",
+            // The precision must match the precision of the column
+            Self::MySql => "CURRENT_TIMESTAMP(6)",
+        }
+    }
+
+    /// Returns the clause generating the values of an identity column, declared in place of its default.
+    ///
+    /// SQLite only generates the values of an integer primary key, so its clause follows the primary key.
+    pub fn identity(&self) -> &'static str {
+        match self {
+            Self::Postgres => "GENERATED BY DEFAULT AS IDENTITY",
+            Self::Sqlite => "AUTOINCREMENT",
+            Self::MySql => "AUTO_INCREMENT",
+        }
+    }
+
+    /// Returns `true` if columns are set to the current time on update by triggers,
+    /// rather than by MySQL's `ON UPDATE` clause.
+    pub fn has_on_update_triggers(&self) -> bool {
+        !matches!(self, Self::MySql)
+    }
+
+    /// Returns the name of the trigger setting a column to the current time whenever its row is updated.
+    ///
+    /// Postgres trigger names are scoped to their table, while SQLite trigger names are unique across the database.
+    pub fn on_update_trigger_name(&self, table: &str, column: &str) -> String {
+        match self {
+            Self::Postgres => format!("{}_on_update", column),
+            Self::Sqlite | Self::MySql => format!("{}_{}_on_update", table, column),
+        }
+    }
+
+    /// Returns a query selecting whether the table named by the first parameter exists.
+    pub fn table_exists_query(&self) -> &'static str {
+        match self {
+            Self::Postgres => "SELECT to_regclass($1) IS NOT NULL",
+            Self::Sqlite => {
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = $1)"
+            }
+            Self::MySql => {
+                "SELECT EXISTS (SELECT 1 FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = ?)"
+            }
+        }
+    }
+
+    /// Adapts a schema table to what the backend supports.
+    ///
+    /// SQLite and MySQL indexes are always btrees, so index methods are ignored,
+    /// and serial columns are identity columns as they have no sequences.
+    /// MySQL columns are given the type they are stored as, such as `Text` for enums,
+    /// and text columns without a maximum length in keys or indexes are limited to 255 characters.
+    /// Fails if a column has a type the backend doesn't support.
+    pub fn table(&self, table: &DatabaseTable) -> Result<DatabaseTable, Error> {
+        let mut table = table.clone();
+        let primary_key_len = table.primary_key().len();
+
+        if let Self::Sqlite | Self::MySql = self {
+            if let Some(column) = table
+                .columns
+                .iter()
+                .find(|column| matches!(column.ty, DatabaseType::Array(_)))
+            {
+                return Err(Error::UnsupportedBackendType(
+                    *self,
+                    table.name.clone(),
+                    column.name.clone(),
+                ));
+            }
+
+            for index in &mut table.indexes {
+                index.method = IndexMethod::default();
+            }
+
+            for column in &mut table.columns {
+                if column.default == Some(DatabaseDefault::Serial) {
+                    column.default = Some(DatabaseDefault::Identity);
+                }
+            }
+        }
+
+        // SQLite only generates the values of a sole integer primary key, which is always 64 bits
+        if let Self::Sqlite = self {
+            for column in &mut table.columns {
+                if column.default == Some(DatabaseDefault::Identity) {
+                    if !column.primary_key || primary_key_len > 1 {
+                        return Err(Error::UnsupportedBackendType(
+                            *self,
+                            table.name.clone(),
+                            column.name.clone(),
+                        ));
+                    }
+                    column.ty = DatabaseType::Integer;
+                }
+            }
+        }
+
+        if let Self::MySql = self {
+            let keyed_columns: Vec<String> = table
+                .indexes
+                .iter()
+                .flat_map(|index| index.columns.iter().cloned())
+                .chain(
+                    table
+                        .constraints
+                        .iter()
+                        .flat_map(|constraint| match &constraint.kind {
+                            ConstraintKind::Unique(columns) => columns.clone(),
+                            _ => vec![],
+                        }),
+                )
+                .collect();
+
+            for column in &mut table.columns {
+                let keyed = column.primary_key
+                    || column.unique
+                    || column.references.is_some()
+                    || keyed_columns.contains(&column.name);
+                column.ty = match &column.ty {
+                    DatabaseType::Text(None) if keyed => DatabaseType::Text(Some(MYSQL_KEY_LEN)),
+                    DatabaseType::Money => DatabaseType::Numeric(Some(MYSQL_MONEY)),
+                    DatabaseType::Timestamp => DatabaseType::Timestamptz,
+                    DatabaseType::Timetz => DatabaseType::Time,
+                    DatabaseType::Json => DatabaseType::Jsonb,
+                    DatabaseType::Enum(_) => DatabaseType::Text(Some(MYSQL_ENUM_LEN)),
+                    ty => ty.clone(),
+                };
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Writes a column type, qualifying enum types with the schema.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::DatabaseType;
+    /// # use awto_compile::database::backend::Backend;
+    /// let status = DatabaseType::Enum("order_status".to_string());
+    /// assert_eq!(Backend::Postgres.write_type("shop", &status), r#""shop".order_status"#);
+    /// assert_eq!(Backend::Sqlite.write_type("shop", &status), "order_status");
+    /// assert_eq!(Backend::Sqlite.write_type("shop", &DatabaseType::Text(Some(40))), "varchar(40)");
+    /// assert_eq!(Backend::MySql.write_type("shop", &DatabaseType::Uuid), "binary(16)");
+    /// ```
+    pub fn write_type(&self, schema: &str, ty: &DatabaseType) -> String {
+        match (self, ty) {
+            (Self::MySql, DatabaseType::Enum(_)) => format!("varchar({})", MYSQL_ENUM_LEN),
+            (_, DatabaseType::Enum(name)) => self.qualify(schema, name),
+            (_, DatabaseType::Array(ty)) => format!("{}[]", self.write_type(schema, ty)),
+            (Self::Postgres, ty) => ty.to_string(),
+            (Self::Sqlite, DatabaseType::Text(Some(max_len))) => format!("varchar({})", max_len),
+            (Self::Sqlite, DatabaseType::Text(None)) => "text".to_string(),
+            (Self::Sqlite, DatabaseType::Binary) => "blob".to_string(),
+            (Self::Sqlite, DatabaseType::Double) => "double".to_string(),
+            (Self::Sqlite, DatabaseType::Timestamptz) => "timestamptz".to_string(),
+            (Self::Sqlite, DatabaseType::Timetz) => "timetz".to_string(),
+            (Self::Sqlite, ty) => ty.to_string(),
+            (Self::MySql, DatabaseType::Integer) => "int".to_string(),
+            (Self::MySql, DatabaseType::Numeric(Some((precision, scale)))) => {
+                format!("decimal({}, {})", precision, scale)
+            }
+            (Self::MySql, DatabaseType::Numeric(None)) => {
+                format!("decimal({}, {})", MYSQL_MAX_NUMERIC.0, MYSQL_MAX_NUMERIC.1)
+            }
+            (Self::MySql, DatabaseType::Float) => "float".to_string(),
+            (Self::MySql, DatabaseType::Double) => "double".to_string(),
+            (Self::MySql, DatabaseType::Money) => {
+                format!("decimal({}, {})", MYSQL_MONEY.0, MYSQL_MONEY.1)
+            }
+            (Self::MySql, DatabaseType::Text(Some(max_len))) => format!("varchar({})", max_len),
+            (Self::MySql, DatabaseType::Text(None)) => "text".to_string(),
+            (Self::MySql, DatabaseType::Binary) => "longblob".to_string(),
+            (Self::MySql, DatabaseType::Timestamp | DatabaseType::Timestamptz) => {
+                "datetime(6)".to_string()
+            }
+            (Self::MySql, DatabaseType::Time | DatabaseType::Timetz) => "time(6)".to_string(),
+            (Self::MySql, DatabaseType::Bool) => "tinyint(1)".to_string(),
+            (Self::MySql, DatabaseType::Uuid) => "binary(16)".to_string(),
+            (Self::MySql, DatabaseType::Json | DatabaseType::Jsonb) => "json".to_string(),
+            (Self::MySql, ty) => ty.to_string(),
+        }
+    }
+
+    /// Parses a column type written by [`Backend::write_type`].
+    ///
+    /// Unknown types are enums when they are named after one of `enum_names`.
+    /// MySQL uuids may also be stored as `CHAR(36)`.
+    pub fn parse_type(&self, ty: &str, enum_names: &[&str]) -> Option<DatabaseType> {
+        let ty = ty.to_lowercase();
+        if let Self::MySql = self {
+            return parse_mysql_type(&ty);
+        }
+        if let Self::Sqlite = self {
+            let database_type = match ty.as_str() {
+                "text" => DatabaseType::Text(None),
+                "blob" => DatabaseType::Binary,
+                "double" => DatabaseType::Double,
+                _ => {
+                    if let Some(max_len) = ty
+                        .strip_prefix("varchar(")
+                        .and_then(|max_len| max_len.strip_suffix(')'))
+                    {
+                        DatabaseType::Text(Some(max_len.parse().ok()?))
+                    } else if let Some(precision_scale) = ty
+                        .strip_prefix("numeric(")
+                        .and_then(|precision_scale| precision_scale.strip_suffix(')'))
+                    {
+                        let (precision, scale) = precision_scale.split_once(',')?;
+                        DatabaseType::Numeric(Some((
+                            precision.trim().parse().ok()?,
+                            scale.trim().parse().ok()?,
+                        )))
+                    } else if let Some(name) = enum_names.iter().find(|name| **name == ty) {
+                        DatabaseType::Enum(name.to_string())
+                    } else {
+                        ty.parse().ok()?
+                    }
+                }
+            };
+            return Some(database_type);
+        }
+
+        ty.parse().ok()
+    }
+
+    /// Writes a column default.
+    ///
+    /// The raw defaults generating uuids and timestamps are translated for backends other than Postgres.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::DatabaseDefault;
+    /// # use awto_compile::database::backend::Backend;
+    /// let uuid = DatabaseDefault::Raw("uuid_generate_v4()".to_string());
+    /// assert_eq!(Backend::Sqlite.write_default(&uuid), "(randomblob(16))");
+    /// assert_eq!(Backend::MySql.write_default(&uuid), "(uuid_to_bin(uuid()))");
+    /// ```
+    pub fn write_default(&self, default: &DatabaseDefault) -> String {
+        match (self, default) {
+            (Self::Postgres, default) => default.to_string(),
+            (Self::Sqlite, DatabaseDefault::String(s)) => quote_literal(s),
+            (Self::Sqlite, DatabaseDefault::Raw(raw)) if raw.eq_ignore_ascii_case(UUID_DEFAULT) => {
+                "(randomblob(16))".to_string()
+            }
+            (Self::Sqlite, DatabaseDefault::Raw(raw)) if raw.eq_ignore_ascii_case(NOW_DEFAULT) => {
+                self.now().to_string()
+            }
+            (Self::Sqlite, default) => default.to_string(),
+            (Self::MySql, DatabaseDefault::String(s)) => {
+                format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''"))
+            }
+            (Self::MySql, DatabaseDefault::Raw(raw)) if raw.eq_ignore_ascii_case(UUID_DEFAULT) => {
+                format!("({})", MYSQL_UUID_DEFAULT)
+            }
+            (Self::MySql, DatabaseDefault::Raw(raw)) if raw.eq_ignore_ascii_case(NOW_DEFAULT) => {
+                self.now().to_string()
+            }
+            (Self::MySql, default) => default.to_string(),
+        }
+    }
+
+    /// Parses a column default as the database reports it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::DatabaseDefault;
+    /// # use awto_compile::database::backend::Backend;
+    /// assert_eq!(
+    ///     Backend::Postgres.parse_default("'draft'::character varying".to_string()),
+    ///     DatabaseDefault::String("draft".to_string())
+    /// );
+    /// assert_eq!(
+    ///     Backend::Sqlite.parse_default("CURRENT_TIMESTAMP".to_string()),
+    ///     DatabaseDefault::Raw("NOW()".to_string())
+    /// );
+    /// ```
+    pub fn parse_default(&self, default: String) -> DatabaseDefault {
+        if let Self::MySql = self {
+            if default.eq_ignore_ascii_case(self.now()) {
+                return DatabaseDefault::Raw(NOW_DEFAULT.to_string());
+            }
+            if default.eq_ignore_ascii_case(MYSQL_UUID_DEFAULT) {
+                return DatabaseDefault::Raw(UUID_DEFAULT.to_string());
+            }
+            // MariaDB quotes string defaults, unlike MySQL
+            if let Some(s) = default
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+            {
+                return DatabaseDefault::String(s.replace("''", "'"));
+            }
+        }
+
+        if let Self::Sqlite = self {
+            if default.eq_ignore_ascii_case(self.now()) {
+                return DatabaseDefault::Raw(NOW_DEFAULT.to_string());
+            }
+            if default.eq_ignore_ascii_case("randomblob(16)") {
+                return DatabaseDefault::Raw(UUID_DEFAULT.to_string());
+            }
+            if let Some(s) = default
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+            {
+                return DatabaseDefault::String(s.replace("''", "'"));
+            }
+        }
+
+        if default.starts_with("nextval(") {
+            DatabaseDefault::Serial
+        } else if default.starts_with('\'') {
+            let s = default
+                .strip_prefix('\'')
+                .unwrap()
+                .split('\'')
+                .next()
+                .unwrap()
+                .to_string();
+            DatabaseDefault::String(s)
+        } else if default == "true" {
+            DatabaseDefault::Bool(true)
+        } else if default == "false" {
+            DatabaseDefault::Bool(false)
+        } else if let Ok(num) = default.parse::<u64>() {
+            DatabaseDefault::Int(num)
+        } else if let Ok(num) = default.parse::<i64>() {
+            DatabaseDefault::Float(num)
+        } else {
+            DatabaseDefault::Raw(default)
+        }
+    }
+}
+
+/// Quotes an identifier for Postgres, doubling any quotes within it.
+///
+/// # Examples
+///
+/// ```
+/// # use awto_compile::database::backend::quote_identifier;
+/// assert_eq!(quote_identifier("public"), r#""public""#);
+/// assert_eq!(quote_identifier(r#"my"schema"#), r#""my""schema""#);
+/// ```
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Quotes a string literal, doubling any single quotes within it.
+///
+/// Names qualified with the schema are quoted with this when passed to functions as strings,
+/// as the schema can be any name.
+///
+/// # Examples
+///
+/// ```
+/// # use awto_compile::database::backend::{quote_identifier, quote_literal};
+/// assert_eq!(quote_literal("price >= 0"), "'price >= 0'");
+/// assert_eq!(quote_literal(&quote_identifier("shop's")), r#"'"shop''s"'"#);
+/// ```
+pub fn quote_literal(literal: &str) -> String {
+    format!("'{}'", literal.replace('\'', "''"))
+}
+
+/// Parses a MySQL column type, as reported by `information_schema.columns.column_type`.
+///
+/// MariaDB and older MySQL versions report integers with a display width, such as `int(11)`.
+fn parse_mysql_type(ty: &str) -> Option<DatabaseType> {
+    let (name, args) = match ty.split_once('(') {
+        Some((name, args)) => (name, Some(args.split(')').next()?)),
+        None => (ty.split(' ').next()?, None),
+    };
+
+    let database_type = match (name, args) {
+        ("tinyint", Some("1")) | ("boolean" | "bool", _) => DatabaseType::Bool,
+        ("smallint", _) => DatabaseType::SmallInt,
+        ("int" | "integer", _) => DatabaseType::Integer,
+        ("bigint", _) => DatabaseType::BigInt,
+        ("decimal" | "numeric", Some(args)) => {
+            let (precision, scale) = args.split_once(',')?;
+            let precision_scale = (precision.trim().parse().ok()?, scale.trim().parse().ok()?);
+            if precision_scale == MYSQL_MAX_NUMERIC {
+                DatabaseType::Numeric(None)
+            } else {
+                DatabaseType::Numeric(Some(precision_scale))
+            }
+        }
+        ("float", _) => DatabaseType::Float,
+        ("double", _) => DatabaseType::Double,
+        ("binary", Some("16")) | ("char", Some("36")) => DatabaseType::Uuid,
+        ("varchar" | "char", Some(max_len)) => DatabaseType::Text(Some(max_len.parse().ok()?)),
+        ("text" | "tinytext" | "mediumtext" | "longtext", _) => DatabaseType::Text(None),
+        ("blob" | "tinyblob" | "mediumblob" | "longblob", _) => DatabaseType::Binary,
+        ("datetime" | "timestamp", _) => DatabaseType::Timestamptz,
+        ("date", _) => DatabaseType::Date,
+        ("time", _) => DatabaseType::Time,
+        ("json", _) => DatabaseType::Jsonb,
+        _ => return None,
+    };
+
+    Some(database_type)
+}
+
+impl From<AnyKind> for Backend {
+    fn from(kind: AnyKind) -> Self {
+        match kind {
+            AnyKind::Postgres => Self::Postgres,
+            #[cfg(feature = "sqlite")]
+            AnyKind::Sqlite => Self::Sqlite,
+            #[cfg(feature = "mysql")]
+            AnyKind::MySql => Self::MySql,
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Postgres => write!(f, "postgres"),
+            Self::Sqlite => write!(f, "sqlite"),
+            Self::MySql => write!(f, "mysql"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sqlite_types() {
+        use DatabaseType::*;
+
+        for ty in [
+            SmallInt,
+            Integer,
+            BigInt,
+            Numeric(None),
+            Numeric(Some((12, 2))),
+            Float,
+            Double,
+            Text(None),
+            Text(Some(120)),
+            Binary,
+            Timestamp,
+            Timestamptz,
+            Date,
+            Time,
+            Timetz,
+            Bool,
+            Uuid,
+            Json,
+            Jsonb,
+            Enum("order_status".to_string()),
+        ] {
+            let written = Backend::Sqlite.write_type("public", &ty);
+            assert_eq!(
+                Backend::Sqlite.parse_type(&written, &["order_status"]),
+                Some(ty)
+            );
+        }
+        assert_eq!(Backend::Sqlite.parse_type("order_status", &[]), None);
+    }
+
+    #[test]
+    fn mysql_types() {
+        use DatabaseType::*;
+
+        // Types MySQL stores as another type are normalized by `Backend::table`
+        for ty in [
+            SmallInt,
+            Integer,
+            BigInt,
+            Numeric(None),
+            Numeric(Some((12, 2))),
+            Float,
+            Double,
+            Text(None),
+            Text(Some(120)),
+            Binary,
+            Timestamptz,
+            Date,
+            Time,
+            Bool,
+            Uuid,
+            Jsonb,
+        ] {
+            let written = Backend::MySql.write_type("public", &ty);
+            assert_eq!(Backend::MySql.parse_type(&written, &[]), Some(ty));
+        }
+        assert_eq!(Backend::MySql.parse_type("int(11)", &[]), Some(Integer));
+        assert_eq!(
+            Backend::MySql.parse_type("bigint(20) unsigned", &[]),
+            Some(BigInt)
+        );
+        assert_eq!(Backend::MySql.parse_type("char(36)", &[]), Some(Uuid));
+        assert_eq!(Backend::MySql.parse_type("geometry", &[]), None);
+    }
+
+    #[test]
+    fn sqlite_defaults() {
+        for default in [
+            DatabaseDefault::Bool(true),
+            DatabaseDefault::Int(0),
+            DatabaseDefault::Float(-1),
+            DatabaseDefault::String("it's".to_string()),
+            DatabaseDefault::Raw(NOW_DEFAULT.to_string()),
+            DatabaseDefault::Raw(UUID_DEFAULT.to_string()),
+        ] {
+            let written = Backend::Sqlite.write_default(&default);
+            // SQLite reports default expressions without their parentheses
+            let reported = written
+                .strip_prefix('(')
+                .and_then(|written| written.strip_suffix(')'))
+                .unwrap_or(&written)
+                .to_string();
+            assert_eq!(Backend::Sqlite.parse_default(reported), default);
+        }
+    }
+}
@@ -0,0 +1,1056 @@
+use std::fmt;
+
+use awto::database::{
+    ColumnChange, ConstraintChange, ConstraintKind, DatabaseColumn, DatabaseConstraint,
+    DatabaseEnum, DatabaseTable, EnumChange, IndexChange, SchemaDiff, TableChange,
+};
+
+/// A change which can lose data, and is only applied when destructive changes are allowed.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct DestructiveChange {
+    pub table: String,
+    pub change: ColumnChange,
+}
+
+impl fmt::Display for DestructiveChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}: ", self.table, self.change.column_name())?;
+        match &self.change {
+            ColumnChange::Drop(_) => write!(f, "drop column"),
+            ColumnChange::ChangeType { from, to, .. } => {
+                write!(f, "change type from {} to {}", from, to)
+            }
+            ColumnChange::SetNotNull { .. } => write!(f, "set not null on a table with rows"),
+            change => write!(f, "{:?}", change),
+        }
+    }
+}
+
+/// Compares an enum from the schema with the same enum in the database.
+///
+/// Returns `None` if the database already has every value of the enum.
+pub fn diff_enum(
+    database_enum: &DatabaseEnum,
+    db_enum: Option<&DatabaseEnum>,
+) -> Option<EnumChange> {
+    let db_enum = match db_enum {
+        Some(db_enum) => db_enum,
+        None => return Some(EnumChange::Create(database_enum.clone())),
+    };
+
+    let values: Vec<_> = database_enum
+        .values
+        .iter()
+        .filter(|value| !db_enum.values.contains(value))
+        .cloned()
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(EnumChange::AddValues {
+        name: database_enum.name.clone(),
+        values,
+    })
+}
+
+/// Compares a table from the schema with the same table in the database.
+///
+/// Returns `None` if the table is already in sync.
+pub fn diff_table(table: &DatabaseTable, db_table: Option<&DatabaseTable>) -> Option<TableChange> {
+    let db_table = match db_table {
+        Some(db_table) => db_table,
+        None => return Some(TableChange::Create(table.clone())),
+    };
+
+    let columns = diff_columns(table, db_table);
+    let indexes = diff_indexes(table, db_table);
+    let constraints = diff_constraints(table, db_table);
+    if columns.is_empty() && indexes.is_empty() && constraints.is_empty() {
+        return None;
+    }
+
+    Some(TableChange::Alter {
+        table: table.name.clone(),
+        columns,
+        indexes,
+        constraints,
+    })
+}
+
+/// Compares the columns of a table from the schema with the same table in the database.
+pub fn diff_columns(table: &DatabaseTable, db_table: &DatabaseTable) -> Vec<ColumnChange> {
+    let mut changes = Vec::new();
+
+    let mut renamed_columns = Vec::new();
+
+    for schema_col in &table.columns {
+        let db_col = match db_table
+            .columns
+            .iter()
+            .find(|db_col| db_col.name == schema_col.name)
+        {
+            Some(db_col) => db_col,
+            None => match find_renamed_column(table, db_table, &schema_col.renamed_from) {
+                Some(db_col) => {
+                    // Column exists in DB under a previous name.
+                    // Its constraints and on update trigger are named after the previous name,
+                    // so they're dropped and added again
+                    if db_col.unique {
+                        changes.push(ColumnChange::DropUnique {
+                            column: db_col.name.clone(),
+                        });
+                    }
+                    if let Some(references) = &db_col.references {
+                        changes.push(ColumnChange::DropForeignKey {
+                            column: db_col.name.clone(),
+                            references: references.clone(),
+                        });
+                    }
+                    if let Some(check) = &db_col.constraint {
+                        changes.push(ColumnChange::DropCheck {
+                            column: db_col.name.clone(),
+                            check: check.clone(),
+                        });
+                    }
+                    if db_col.on_update_now {
+                        changes.push(ColumnChange::DropOnUpdateNow {
+                            column: db_col.name.clone(),
+                        });
+                    }
+                    changes.push(ColumnChange::Rename {
+                        from: db_col.name.clone(),
+                        to: schema_col.name.clone(),
+                    });
+                    renamed_columns.push(db_col.name.as_str());
+                    db_col
+                }
+                None => {
+                    // Column does not exist in DB
+                    changes.push(ColumnChange::Add(schema_col.clone()));
+                    continue;
+                }
+            },
+        };
+
+        let column = schema_col.name.clone();
+        let renamed = db_col.name != schema_col.name;
+
+        // Check for type mismatch
+        if schema_col.ty != db_col.ty {
+            changes.push(ColumnChange::ChangeType {
+                column: column.clone(),
+                from: db_col.ty.clone(),
+                to: schema_col.ty.clone(),
+            });
+        }
+
+        // Check for nullable mismatch
+        if schema_col.nullable != db_col.nullable {
+            if db_col.nullable {
+                changes.push(ColumnChange::SetNotNull {
+                    column: column.clone(),
+                });
+            } else {
+                changes.push(ColumnChange::DropNotNull {
+                    column: column.clone(),
+                });
+            }
+        }
+
+        // Check for default mismatch
+        if schema_col.default != db_col.default {
+            match (&schema_col.default, &db_col.default) {
+                (Some(default), _) => changes.push(ColumnChange::SetDefault {
+                    column: column.clone(),
+                    from: db_col.default.clone(),
+                    to: default.clone(),
+                }),
+                (None, Some(db_default)) => changes.push(ColumnChange::DropDefault {
+                    column: column.clone(),
+                    from: db_default.clone(),
+                }),
+                (None, None) => {}
+            }
+        }
+
+        // Check for on update mismatch
+        let db_on_update_now = db_col.on_update_now && !renamed;
+        if schema_col.on_update_now != db_on_update_now {
+            if db_on_update_now {
+                changes.push(ColumnChange::DropOnUpdateNow {
+                    column: column.clone(),
+                });
+            } else {
+                changes.push(ColumnChange::SetOnUpdateNow {
+                    column: column.clone(),
+                });
+            }
+        }
+
+        // Check for unique mismatch
+        let db_unique = db_col.unique && !renamed;
+        if schema_col.unique != db_unique {
+            if db_unique {
+                changes.push(ColumnChange::DropUnique {
+                    column: column.clone(),
+                });
+            } else {
+                changes.push(ColumnChange::AddUnique {
+                    column: column.clone(),
+                });
+            }
+        }
+
+        // Check for check constraint mismatch
+        let db_check = if renamed {
+            None
+        } else {
+            db_col.constraint.as_ref()
+        };
+        if schema_col.constraint.as_ref() != db_check {
+            if let Some(check) = db_check {
+                changes.push(ColumnChange::DropCheck {
+                    column: column.clone(),
+                    check: check.clone(),
+                });
+            }
+            if let Some(check) = &schema_col.constraint {
+                changes.push(ColumnChange::AddCheck {
+                    column: column.clone(),
+                    check: check.clone(),
+                });
+            }
+        }
+
+        // Check for references mismatch
+        let db_references = if renamed {
+            None
+        } else {
+            db_col.references.as_ref()
+        };
+        if schema_col.references.as_ref() != db_references {
+            if let Some(references) = db_references {
+                changes.push(ColumnChange::DropForeignKey {
+                    column: column.clone(),
+                    references: references.clone(),
+                });
+            }
+            if let Some(references) = &schema_col.references {
+                changes.push(ColumnChange::AddForeignKey {
+                    column,
+                    references: references.clone(),
+                });
+            }
+        }
+    }
+
+    // Drop columns that exist in db but don't exist in schema
+    changes.extend(
+        db_table
+            .columns
+            .iter()
+            .filter(|db_col| {
+                !renamed_columns.contains(&db_col.name.as_str())
+                    && table
+                        .columns
+                        .iter()
+                        .all(|schema_col| schema_col.name != db_col.name)
+            })
+            .map(|db_col| ColumnChange::Drop(db_col.clone())),
+    );
+
+    changes
+}
+
+/// Compares the indexes of a table from the schema with the same table in the database.
+///
+/// Indexes are matched by name, and an index which differs from the schema is dropped and created again.
+pub fn diff_indexes(table: &DatabaseTable, db_table: &DatabaseTable) -> Vec<IndexChange> {
+    let mut changes = Vec::new();
+
+    for db_index in &db_table.indexes {
+        if !table.indexes.contains(db_index) {
+            changes.push(IndexChange::Drop(db_index.clone()));
+        }
+    }
+
+    for index in &table.indexes {
+        if !db_table.indexes.contains(index) {
+            changes.push(IndexChange::Create(index.clone()));
+        }
+    }
+
+    changes
+}
+
+/// Compares the table level constraints of a table from the schema with the same table in the database.
+///
+/// Constraints are matched by name, and a constraint which differs from the schema is dropped and added again.
+/// The primary key is compared by its columns, following renamed columns, and is dropped and added again when they differ.
+pub fn diff_constraints(table: &DatabaseTable, db_table: &DatabaseTable) -> Vec<ConstraintChange> {
+    let mut changes = Vec::new();
+
+    let is_primary_key =
+        |constraint: &DatabaseConstraint| matches!(constraint.kind, ConstraintKind::PrimaryKey(_));
+
+    for db_constraint in &db_table.constraints {
+        if !is_primary_key(db_constraint) && !table.constraints.contains(db_constraint) {
+            changes.push(ConstraintChange::Drop(db_constraint.clone()));
+        }
+    }
+
+    for constraint in &table.constraints {
+        if !is_primary_key(constraint) && !db_table.constraints.contains(constraint) {
+            changes.push(ConstraintChange::Add(constraint.clone()));
+        }
+    }
+
+    let mut primary_key = table.primary_key();
+    primary_key.sort_unstable();
+    let db_primary_key = db_table.primary_key();
+    let mut renamed_db_primary_key: Vec<_> = db_primary_key
+        .iter()
+        .map(|db_column| {
+            table
+                .columns
+                .iter()
+                .find(|column| {
+                    column.name == *db_column
+                        || (column.renamed_from.iter().any(|name| name == db_column)
+                            && table.columns.iter().all(|other| other.name != *db_column))
+                })
+                .map(|column| column.name.as_str())
+                .unwrap_or(db_column)
+        })
+        .collect();
+    renamed_db_primary_key.sort_unstable();
+
+    if primary_key != renamed_db_primary_key {
+        if !db_primary_key.is_empty() {
+            let name = db_table
+                .constraints
+                .iter()
+                .find(|constraint| is_primary_key(constraint))
+                .map(|constraint| constraint.name.clone())
+                .unwrap_or_else(|| DatabaseConstraint::primary_key_name(&db_table.name));
+            changes.push(ConstraintChange::Drop(DatabaseConstraint {
+                name,
+                kind: ConstraintKind::PrimaryKey(
+                    db_primary_key.iter().map(ToString::to_string).collect(),
+                ),
+            }));
+        }
+        if !primary_key.is_empty() {
+            changes.push(ConstraintChange::Add(DatabaseConstraint {
+                name: DatabaseConstraint::primary_key_name(&table.name),
+                kind: ConstraintKind::PrimaryKey(
+                    table
+                        .primary_key()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                ),
+            }));
+        }
+    }
+
+    changes
+}
+
+/// Finds the database column a schema column was renamed from.
+///
+/// Previous names which are still used by a column in the schema are ignored.
+fn find_renamed_column<'a>(
+    table: &DatabaseTable,
+    db_table: &'a DatabaseTable,
+    renamed_from: &[String],
+) -> Option<&'a DatabaseColumn> {
+    renamed_from
+        .iter()
+        .filter(|previous_name| {
+            table
+                .columns
+                .iter()
+                .all(|schema_col| &schema_col.name != *previous_name)
+        })
+        .find_map(|previous_name| {
+            db_table
+                .columns
+                .iter()
+                .find(|db_col| &db_col.name == previous_name)
+        })
+}
+
+/// Returns the destructive changes in a diff.
+///
+/// Setting a column to not null is considered destructive when its table is in `populated_tables`.
+pub fn destructive_changes(
+    diff: &SchemaDiff,
+    populated_tables: &[String],
+) -> Vec<DestructiveChange> {
+    let mut destructive_changes = Vec::new();
+
+    for table_change in &diff.tables {
+        let (table, columns) = match table_change {
+            TableChange::Create(_) | TableChange::Rename { .. } | TableChange::Drop(_) => continue,
+            TableChange::Alter { table, columns, .. } => (table, columns),
+        };
+
+        for change in columns {
+            let destructive = match change {
+                ColumnChange::SetNotNull { .. } => populated_tables.contains(table),
+                change => change.is_destructive(),
+            };
+            if destructive {
+                destructive_changes.push(DestructiveChange {
+                    table: table.clone(),
+                    change: change.clone(),
+                });
+            }
+        }
+    }
+
+    destructive_changes
+}
+
+/// Returns a description of each change in a diff which can't be undone without losing data.
+pub fn irreversible_changes(diff: &SchemaDiff) -> Vec<String> {
+    let mut irreversible_changes = Vec::new();
+
+    for table_change in &diff.tables {
+        match table_change {
+            TableChange::Drop(table) => {
+                irreversible_changes.push(format!("{}: drop table", table.name))
+            }
+            TableChange::Alter { table, columns, .. } => irreversible_changes.extend(
+                columns
+                    .iter()
+                    .filter(|change| !change.is_reversible())
+                    .map(|change| {
+                        DestructiveChange {
+                            table: table.clone(),
+                            change: change.clone(),
+                        }
+                        .to_string()
+                    }),
+            ),
+            TableChange::Create(_) | TableChange::Rename { .. } => {}
+        }
+    }
+
+    irreversible_changes
+}
+
+/// Returns a table with the changes of a [`TableChange::Alter`] applied.
+pub fn altered_table(
+    table: &DatabaseTable,
+    columns: &[ColumnChange],
+    indexes: &[IndexChange],
+    constraints: &[ConstraintChange],
+) -> DatabaseTable {
+    let mut table = table.clone();
+
+    for column_change in columns {
+        match column_change {
+            ColumnChange::Add(added) => {
+                table.columns.push(added.clone());
+                continue;
+            }
+            ColumnChange::Drop(dropped) => {
+                table.columns.retain(|column| column.name != dropped.name);
+                continue;
+            }
+            _ => {}
+        }
+
+        let column = match column_change {
+            ColumnChange::Rename { from, .. } => {
+                table.columns.iter_mut().find(|column| &column.name == from)
+            }
+            _ => table
+                .columns
+                .iter_mut()
+                .find(|column| column.name == column_change.column_name()),
+        };
+        let column = match column {
+            Some(column) => column,
+            None => continue,
+        };
+
+        match column_change {
+            ColumnChange::Add(_) | ColumnChange::Drop(_) => {}
+            ColumnChange::Rename { to, .. } => column.name = to.clone(),
+            ColumnChange::ChangeType { to, .. } => column.ty = to.clone(),
+            ColumnChange::SetDefault { to, .. } => column.default = Some(to.clone()),
+            ColumnChange::DropDefault { .. } => column.default = None,
+            ColumnChange::SetNotNull { .. } => column.nullable = false,
+            ColumnChange::DropNotNull { .. } => column.nullable = true,
+            ColumnChange::SetOnUpdateNow { .. } => column.on_update_now = true,
+            ColumnChange::DropOnUpdateNow { .. } => column.on_update_now = false,
+            ColumnChange::AddUnique { .. } => column.unique = true,
+            ColumnChange::DropUnique { .. } => column.unique = false,
+            ColumnChange::AddForeignKey { references, .. } => {
+                column.references = Some(references.clone())
+            }
+            ColumnChange::DropForeignKey { .. } => column.references = None,
+            ColumnChange::AddCheck { check, .. } => column.constraint = Some(check.clone()),
+            ColumnChange::DropCheck { .. } => column.constraint = None,
+        }
+    }
+
+    for index_change in indexes {
+        match index_change {
+            IndexChange::Create(index) => table.indexes.push(index.clone()),
+            IndexChange::Drop(index) => table.indexes.retain(|other| other.name != index.name),
+        }
+    }
+
+    // Primary keys are kept as flags on their columns
+    for constraint_change in constraints {
+        match constraint_change {
+            ConstraintChange::Add(DatabaseConstraint {
+                kind: ConstraintKind::PrimaryKey(primary_key),
+                ..
+            }) => {
+                for column in &mut table.columns {
+                    column.primary_key = primary_key.contains(&column.name);
+                }
+            }
+            ConstraintChange::Drop(
+                constraint @ DatabaseConstraint {
+                    kind: ConstraintKind::PrimaryKey(_),
+                    ..
+                },
+            ) => {
+                for column in &mut table.columns {
+                    column.primary_key = false;
+                }
+                table
+                    .constraints
+                    .retain(|other| other.name != constraint.name);
+            }
+            ConstraintChange::Add(constraint) => table.constraints.push(constraint.clone()),
+            ConstraintChange::Drop(constraint) => table
+                .constraints
+                .retain(|other| other.name != constraint.name),
+        }
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use awto::database::{
+        DatabaseDefault, DatabaseIndex, DatabaseType, IndexMethod, IntoDatabaseEnum,
+        IntoDatabaseTable,
+    };
+    use awto::tests_cfg::{Product, ProductTag, Review, ReviewStatus};
+
+    use super::*;
+
+    #[test]
+    fn enum_changes() {
+        let database_enum = ReviewStatus::database_enum();
+        assert_eq!(
+            diff_enum(&database_enum, None),
+            Some(EnumChange::Create(database_enum.clone()))
+        );
+        assert_eq!(diff_enum(&database_enum, Some(&database_enum)), None);
+
+        // `hidden` is missing from the database, which also has a value removed from the schema
+        let db_enum = DatabaseEnum {
+            name: "review_status".to_string(),
+            values: vec![
+                "pending".to_string(),
+                "archived".to_string(),
+                "published".to_string(),
+            ],
+        };
+        assert_eq!(
+            diff_enum(&database_enum, Some(&db_enum)),
+            Some(EnumChange::AddValues {
+                name: "review_status".to_string(),
+                values: vec!["hidden".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn create_missing_table() {
+        let table = Product::database_table();
+        assert_eq!(
+            diff_table(&table, None),
+            Some(TableChange::Create(table.clone()))
+        );
+    }
+
+    #[test]
+    fn table_in_sync() {
+        let table = Product::database_table();
+        assert_eq!(diff_table(&table, Some(&table)), None);
+    }
+
+    #[test]
+    fn column_changes() {
+        let table = Product::database_table();
+        let mut db_table = table.clone();
+
+        // `description` is missing from the database
+        db_table
+            .columns
+            .retain(|column| column.name != "description");
+
+        // `price` has drifted in the database
+        let price = db_table
+            .columns
+            .iter_mut()
+            .find(|column| column.name == "price")
+            .unwrap();
+        price.ty = DatabaseType::Integer;
+        price.nullable = true;
+        price.default = None;
+        price.unique = true;
+
+        // `legacy` only exists in the database
+        let mut legacy = price.clone();
+        legacy.name = "legacy".to_string();
+        db_table.columns.push(legacy.clone());
+
+        let description = table
+            .columns
+            .iter()
+            .find(|column| column.name == "description")
+            .unwrap()
+            .clone();
+
+        assert_eq!(
+            diff_columns(&table, &db_table),
+            vec![
+                ColumnChange::ChangeType {
+                    column: "price".to_string(),
+                    from: DatabaseType::Integer,
+                    to: DatabaseType::BigInt,
+                },
+                ColumnChange::SetNotNull {
+                    column: "price".to_string(),
+                },
+                ColumnChange::SetDefault {
+                    column: "price".to_string(),
+                    from: None,
+                    to: DatabaseDefault::Int(0),
+                },
+                ColumnChange::DropUnique {
+                    column: "price".to_string(),
+                },
+                ColumnChange::Add(description),
+                ColumnChange::Drop(legacy),
+            ]
+        );
+    }
+
+    #[test]
+    fn array_changes() {
+        let table = Review::database_table();
+        let mut db_table = table.clone();
+        for column in &mut db_table.columns {
+            match column.name.as_str() {
+                // Widening the elements of `helpful_votes` is safe
+                "helpful_votes" => column.ty = DatabaseType::Array(Box::new(DatabaseType::Integer)),
+                // `images` can't hold arrays of text in the database
+                "images" => column.ty = DatabaseType::Text(None),
+                _ => {}
+            }
+        }
+
+        let diff = SchemaDiff {
+            tables: vec![diff_table(&table, Some(&db_table)).unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(
+            diff.tables,
+            vec![TableChange::Alter {
+                table: "product_reviews".to_string(),
+                columns: vec![
+                    ColumnChange::ChangeType {
+                        column: "images".to_string(),
+                        from: DatabaseType::Text(None),
+                        to: DatabaseType::Array(Box::new(DatabaseType::Text(None))),
+                    },
+                    ColumnChange::ChangeType {
+                        column: "helpful_votes".to_string(),
+                        from: DatabaseType::Array(Box::new(DatabaseType::Integer)),
+                        to: DatabaseType::Array(Box::new(DatabaseType::BigInt)),
+                    },
+                ],
+                indexes: vec![],
+                constraints: vec![],
+            }]
+        );
+        assert_eq!(
+            destructive_changes(&diff, &[])
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "product_reviews.images: change type from character varying to character varying[]"
+            ]
+        );
+    }
+
+    #[test]
+    fn index_changes() {
+        let table = Product::database_table();
+        let mut db_table = table.clone();
+
+        // `product_name_idx` uses a different method in the database
+        let mut name_idx = db_table.indexes[0].clone();
+        name_idx.method = IndexMethod::Hash;
+        db_table.indexes = vec![name_idx.clone()];
+
+        // `product_legacy_idx` only exists in the database
+        let legacy_idx = DatabaseIndex {
+            name: "product_legacy_idx".to_string(),
+            columns: vec!["legacy".to_string()],
+            unique: false,
+            method: IndexMethod::BTree,
+        };
+        db_table.indexes.push(legacy_idx.clone());
+
+        assert_eq!(
+            diff_table(&table, Some(&db_table)),
+            Some(TableChange::Alter {
+                table: "product".to_string(),
+                columns: vec![],
+                indexes: vec![
+                    IndexChange::Drop(name_idx),
+                    IndexChange::Drop(legacy_idx),
+                    IndexChange::Create(table.indexes[0].clone()),
+                ],
+                constraints: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn constraint_changes() {
+        let table = Review::database_table();
+        let mut db_table = table.clone();
+
+        // The unique constraint covers different columns in the database
+        let author_key = &mut db_table.constraints[0];
+        author_key.kind = ConstraintKind::Unique(vec!["author".to_string(), "rating".to_string()]);
+        let author_key = author_key.clone();
+
+        // The check constraint only exists in the schema
+        db_table.constraints.remove(1);
+
+        assert_eq!(
+            diff_table(&table, Some(&db_table)),
+            Some(TableChange::Alter {
+                table: "product_reviews".to_string(),
+                columns: vec![],
+                indexes: vec![],
+                constraints: vec![
+                    ConstraintChange::Drop(author_key),
+                    ConstraintChange::Add(table.constraints[0].clone()),
+                    ConstraintChange::Add(table.constraints[1].clone()),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn primary_key_changes() {
+        let table = ProductTag::database_table();
+
+        // The primary key only covers one column in the database
+        let mut db_table = table.clone();
+        for column in &mut db_table.columns {
+            column.primary_key = column.name == "product_id";
+        }
+        let db_primary_key = DatabaseConstraint {
+            name: "product_tag_pkey".to_string(),
+            kind: ConstraintKind::PrimaryKey(vec!["product_id".to_string()]),
+        };
+        db_table.constraints.push(db_primary_key.clone());
+
+        let primary_key = DatabaseConstraint {
+            name: "product_tag_pkey".to_string(),
+            kind: ConstraintKind::PrimaryKey(vec!["product_id".to_string(), "tag_id".to_string()]),
+        };
+        let constraints = vec![
+            ConstraintChange::Drop(db_primary_key),
+            ConstraintChange::Add(primary_key.clone()),
+        ];
+        assert_eq!(
+            diff_table(&table, Some(&db_table)),
+            Some(TableChange::Alter {
+                table: "product_tag".to_string(),
+                columns: vec![],
+                indexes: vec![],
+                constraints: constraints.clone(),
+            })
+        );
+
+        // The altered table keeps the primary key as column flags
+        let altered = altered_table(&db_table, &[], &[], &constraints);
+        assert_eq!(altered.primary_key(), vec!["product_id", "tag_id"]);
+        assert!(altered.constraints.is_empty());
+
+        // Renaming a primary key column doesn't change the primary key
+        let mut renamed_table = table.clone();
+        let tag_id = &mut renamed_table.columns[1];
+        tag_id.name = "label_id".to_string();
+        tag_id.renamed_from = vec!["tag_id".to_string()];
+        assert!(diff_constraints(&renamed_table, &table).is_empty());
+    }
+
+    #[test]
+    fn on_update_changes() {
+        let table = Product::database_table();
+
+        // The database doesn't set `updated_at` on update
+        let mut db_table = table.clone();
+        for column in &mut db_table.columns {
+            column.on_update_now = false;
+        }
+        assert_eq!(
+            diff_columns(&table, &db_table),
+            vec![ColumnChange::SetOnUpdateNow {
+                column: "updated_at".to_string(),
+            }]
+        );
+
+        // The trigger of a renamed column is named after its previous name
+        let mut renamed_table = table.clone();
+        let modified_at = &mut renamed_table.columns[2];
+        modified_at.name = "modified_at".to_string();
+        modified_at.renamed_from = vec!["updated_at".to_string()];
+        assert_eq!(
+            diff_columns(&renamed_table, &table),
+            vec![
+                ColumnChange::DropOnUpdateNow {
+                    column: "updated_at".to_string(),
+                },
+                ColumnChange::Rename {
+                    from: "updated_at".to_string(),
+                    to: "modified_at".to_string(),
+                },
+                ColumnChange::SetOnUpdateNow {
+                    column: "modified_at".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_column() {
+        let mut table = Product::database_table();
+        let db_table = table.clone();
+
+        // `price` was renamed to `cost`, and its default and check were changed
+        let cost = table
+            .columns
+            .iter_mut()
+            .find(|column| column.name == "price")
+            .unwrap();
+        cost.name = "cost".to_string();
+        cost.default = None;
+        cost.constraint = Some("cost >= 0".to_string());
+        cost.renamed_from = vec!["amount".to_string(), "price".to_string()];
+
+        assert_eq!(
+            diff_columns(&table, &db_table),
+            vec![
+                ColumnChange::DropCheck {
+                    column: "price".to_string(),
+                    check: "price >= 0".to_string(),
+                },
+                ColumnChange::Rename {
+                    from: "price".to_string(),
+                    to: "cost".to_string(),
+                },
+                ColumnChange::DropDefault {
+                    column: "cost".to_string(),
+                    from: DatabaseDefault::Int(0),
+                },
+                ColumnChange::AddCheck {
+                    column: "cost".to_string(),
+                    check: "cost >= 0".to_string(),
+                },
+            ]
+        );
+
+        // Unique and foreign key constraints are named after the previous name too
+        let mut db_review = Review::database_table();
+        for column in &mut db_review.columns {
+            if column.name == "product_id" {
+                column.unique = true;
+            }
+        }
+        let mut review = db_review.clone();
+        for column in &mut review.columns {
+            if column.name == "product_id" {
+                column.name = "item_id".to_string();
+                column.renamed_from = vec!["product_id".to_string()];
+            }
+        }
+        let references = ("product".to_string(), "id".to_string());
+        assert_eq!(
+            diff_columns(&review, &db_review),
+            vec![
+                ColumnChange::DropUnique {
+                    column: "product_id".to_string(),
+                },
+                ColumnChange::DropForeignKey {
+                    column: "product_id".to_string(),
+                    references: references.clone(),
+                },
+                ColumnChange::Rename {
+                    from: "product_id".to_string(),
+                    to: "item_id".to_string(),
+                },
+                ColumnChange::AddUnique {
+                    column: "item_id".to_string(),
+                },
+                ColumnChange::AddForeignKey {
+                    column: "item_id".to_string(),
+                    references,
+                },
+            ]
+        );
+
+        // Once renamed, the column is in sync
+        let mut db_table = table.clone();
+        db_table
+            .columns
+            .iter_mut()
+            .for_each(|column| column.renamed_from.clear());
+        assert_eq!(diff_columns(&table, &db_table), vec![]);
+    }
+
+    #[test]
+    fn classify_destructive_changes() {
+        let table = Product::database_table();
+        let mut db_table = table.clone();
+        for column in &mut db_table.columns {
+            match column.name.as_str() {
+                // Widening `description` is safe
+                "description" => column.ty = DatabaseType::Text(Some(60)),
+                // Narrowing `price` is destructive
+                "price" => column.ty = DatabaseType::Numeric(None),
+                // Setting `name` to not null is destructive on a populated table
+                "name" => column.nullable = true,
+                _ => {}
+            }
+        }
+        let mut legacy = db_table.columns[0].clone();
+        legacy.name = "legacy".to_string();
+        db_table.columns.push(legacy.clone());
+
+        let diff = SchemaDiff {
+            tables: vec![diff_table(&table, Some(&db_table)).unwrap()],
+            ..Default::default()
+        };
+
+        let price_change = ColumnChange::ChangeType {
+            column: "price".to_string(),
+            from: DatabaseType::Numeric(None),
+            to: DatabaseType::BigInt,
+        };
+        assert_eq!(
+            destructive_changes(&diff, &[]),
+            vec![
+                DestructiveChange {
+                    table: "product".to_string(),
+                    change: price_change.clone(),
+                },
+                DestructiveChange {
+                    table: "product".to_string(),
+                    change: ColumnChange::Drop(legacy.clone()),
+                },
+            ]
+        );
+
+        assert_eq!(
+            irreversible_changes(&diff),
+            vec![
+                "product.price: change type from numeric to bigint",
+                "product.legacy: drop column",
+            ]
+        );
+
+        let changes = destructive_changes(&diff, &["product".to_string()]);
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "product.name: set not null on a table with rows",
+                "product.price: change type from numeric to bigint",
+                "product.legacy: drop column",
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_alter() {
+        let column = |name: &str, ty| DatabaseColumn {
+            name: name.to_string(),
+            ty,
+            nullable: true,
+            default: None,
+            on_update_now: false,
+            unique: false,
+            constraint: None,
+            primary_key: false,
+            references: None,
+            renamed_from: vec![],
+        };
+        let table = DatabaseTable {
+            name: "product".to_string(),
+            columns: vec![
+                column("name", DatabaseType::Text(None)),
+                column("summary", DatabaseType::Text(Some(40))),
+                column("legacy", DatabaseType::Bool),
+            ],
+            indexes: vec![],
+            constraints: vec![],
+            renamed_from: vec![],
+        };
+
+        let altered = altered_table(
+            &table,
+            &[
+                ColumnChange::Rename {
+                    from: "summary".to_string(),
+                    to: "description".to_string(),
+                },
+                ColumnChange::ChangeType {
+                    column: "description".to_string(),
+                    from: DatabaseType::Text(Some(40)),
+                    to: DatabaseType::Text(Some(120)),
+                },
+                ColumnChange::SetNotNull {
+                    column: "name".to_string(),
+                },
+                ColumnChange::Drop(column("legacy", DatabaseType::Bool)),
+                ColumnChange::Add(column("price", DatabaseType::BigInt)),
+            ],
+            &[],
+            &[],
+        );
+
+        assert_eq!(
+            altered.columns,
+            vec![
+                DatabaseColumn {
+                    nullable: false,
+                    ..column("name", DatabaseType::Text(None))
+                },
+                column("description", DatabaseType::Text(Some(120))),
+                column("price", DatabaseType::BigInt),
+            ]
+        );
+    }
+}
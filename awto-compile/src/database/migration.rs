@@ -0,0 +1,554 @@
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use awto::database::DatabaseType;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{any::AnyQueryResult, AnyConnection, Executor};
+use tokio_stream::StreamExt;
+
+use super::backend::Backend;
+use crate::error::Error;
+
+/// Table used to record which migrations have been applied to a database.
+pub const HISTORY_TABLE: &str = "_awto_migrations";
+
+/// Prefix of lines in a down migration which mark a change that can't be undone without losing data.
+pub const IRREVERSIBLE_PREFIX: &str = "-- irreversible: ";
+
+const MIGRATION_FILE_EXTENSION: &str = "sql";
+/// Postgres error code of creating an object which already exists.
+const DUPLICATE_OBJECT: &str = "42710";
+const DOWN_MIGRATION_SUFFIX: &str = ".down";
+/// Prefix of the name of a migration reverting an earlier migration, followed by the earlier migration's version.
+const REVERT_NAME_PREFIX: &str = "revert_";
+
+/// A versioned SQL migration stored as a `.sql` file.
+///
+/// Migration files are named `{version}_{timestamp}_{name}.sql`, for example
+/// `0001_20211018093000_sync.sql`, where the version is a sequential number used to order them.
+/// The sql undoing a migration is stored next to it in a `.down.sql` file.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Migration {
+    pub version: u32,
+    pub timestamp: String,
+    pub name: String,
+    pub sql: String,
+    /// Sql undoing the migration.
+    ///
+    /// Changes which can't be undone without losing data are marked by lines starting with [`IRREVERSIBLE_PREFIX`].
+    pub down_sql: Option<String>,
+}
+
+impl Migration {
+    /// Creates a new migration timestamped with the current time.
+    pub fn new(version: u32, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Migration {
+            version,
+            timestamp: Utc::now().format("%Y%m%d%H%M%S").to_string(),
+            name: name.into(),
+            sql: sql.into(),
+            down_sql: None,
+        }
+    }
+
+    /// Returns the file name of the migration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto_compile::database::migration::Migration;
+    /// let migration = Migration {
+    ///     version: 1,
+    ///     timestamp: "20211018093000".to_string(),
+    ///     name: "sync".to_string(),
+    ///     sql: String::new(),
+    ///     down_sql: None,
+    /// };
+    ///
+    /// assert_eq!(migration.file_name(), "0001_20211018093000_sync.sql");
+    /// assert_eq!(migration.down_file_name(), "0001_20211018093000_sync.down.sql");
+    /// ```
+    pub fn file_name(&self) -> String {
+        format!(
+            "{:04}_{}_{}.{}",
+            self.version, self.timestamp, self.name, MIGRATION_FILE_EXTENSION
+        )
+    }
+
+    /// Returns the file name of the down migration.
+    pub fn down_file_name(&self) -> String {
+        format!(
+            "{:04}_{}_{}{}.{}",
+            self.version,
+            self.timestamp,
+            self.name,
+            DOWN_MIGRATION_SUFFIX,
+            MIGRATION_FILE_EXTENSION
+        )
+    }
+
+    /// Returns a hex encoded sha256 checksum of the migration sql.
+    ///
+    /// Leading and trailing whitespace is ignored.
+    pub fn checksum(&self) -> String {
+        let digest = Sha256::digest(self.sql.trim().as_bytes());
+        digest.iter().fold(String::new(), |mut checksum, byte| {
+            write!(checksum, "{:02x}", byte).unwrap();
+            checksum
+        })
+    }
+
+    /// Parses the version, timestamp and name from a migration file name.
+    pub fn parse_file_name(file_name: &str) -> Option<(u32, String, String)> {
+        let stem = file_name.strip_suffix(&format!(".{}", MIGRATION_FILE_EXTENSION))?;
+        if stem.ends_with(DOWN_MIGRATION_SUFFIX) {
+            return None;
+        }
+        let mut parts = stem.splitn(3, '_');
+        let version = parts.next()?.parse().ok()?;
+        let timestamp = parts.next()?;
+        let name = parts.next()?;
+        if timestamp.is_empty() || !timestamp.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        Some((version, timestamp.to_string(), name.to_string()))
+    }
+}
+
+/// Reads all migrations from a directory, ordered by version.
+///
+/// Files which don't follow the migration file naming are ignored.
+/// A missing directory is treated as having no migrations.
+pub fn read_migrations(dir: impl AsRef<Path>) -> io::Result<Vec<Migration>> {
+    let dir = dir.as_ref();
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut migrations = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = match path.file_name().and_then(|file_name| file_name.to_str()) {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+
+        if let Some((version, timestamp, name)) = Migration::parse_file_name(file_name) {
+            let mut migration = Migration {
+                version,
+                timestamp,
+                name,
+                sql: fs::read_to_string(&path)?,
+                down_sql: None,
+            };
+
+            let down_path = dir.join(migration.down_file_name());
+            if down_path.is_file() {
+                migration.down_sql = Some(fs::read_to_string(down_path)?);
+            }
+
+            migrations.push(migration);
+        }
+    }
+
+    migrations.sort();
+
+    Ok(migrations)
+}
+
+/// Writes a migration file into a directory, creating the directory if needed.
+///
+/// The down migration is written alongside it, if there is one.
+pub fn write_migration(dir: impl AsRef<Path>, migration: &Migration) -> io::Result<PathBuf> {
+    let dir = dir.as_ref();
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(migration.file_name());
+    fs::write(&path, &migration.sql)?;
+
+    if let Some(down_sql) = &migration.down_sql {
+        fs::write(dir.join(migration.down_file_name()), down_sql)?;
+    }
+
+    Ok(path)
+}
+
+/// A migration which has been applied to the database, as recorded in the [`HISTORY_TABLE`].
+#[derive(Clone, Debug, PartialEq, Eq, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: DateTime<Utc>,
+    pub down_sql: Option<String>,
+}
+
+impl AppliedMigration {
+    /// Returns the changes marked as irreversible in the down migration.
+    pub fn irreversible_changes(&self) -> Vec<&str> {
+        self.down_sql
+            .as_deref()
+            .map(|down_sql| {
+                down_sql
+                    .lines()
+                    .filter_map(|line| line.strip_prefix(IRREVERSIBLE_PREFIX))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the version of the migration reverted by this migration, if it reverts one.
+    pub fn reverted_version(&self) -> Option<i64> {
+        self.name.strip_prefix(REVERT_NAME_PREFIX)?.parse().ok()
+    }
+}
+
+/// Creates the [`HISTORY_TABLE`] in a schema if it does not yet exist.
+pub async fn ensure_history_table(conn: &mut AnyConnection, schema: &str) -> Result<(), Error> {
+    let backend = Backend::of(conn);
+    let table = backend.qualify(schema, HISTORY_TABLE);
+    let mut sql = format!(
+        "CREATE TABLE IF NOT EXISTS {table} (
+  version bigint NOT NULL PRIMARY KEY,
+  name {text} NOT NULL,
+  checksum {text} NOT NULL,
+  applied_at {timestamp} NOT NULL DEFAULT {now},
+  down_sql text
+);",
+        table = table,
+        text = backend.write_type(schema, &DatabaseType::Text(None)),
+        timestamp = backend.write_type(schema, &DatabaseType::Timestamptz),
+        now = backend.now(),
+    );
+    // History tables created before down migrations were recorded lack their column
+    if let Backend::Postgres = backend {
+        write!(
+            sql,
+            "\nALTER TABLE {} ADD COLUMN IF NOT EXISTS down_sql text;",
+            table
+        )
+        .unwrap();
+    }
+
+    conn.execute(sql.as_str()).await.map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Returns `true` if the [`HISTORY_TABLE`] exists in a schema.
+pub async fn history_table_exists(conn: &mut AnyConnection, schema: &str) -> Result<bool, Error> {
+    let backend = Backend::of(conn);
+    let (exists,): (bool,) = sqlx::query_as(backend.table_exists_query())
+        .bind(backend.qualify(schema, HISTORY_TABLE))
+        .fetch_one(conn)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    Ok(exists)
+}
+
+/// Fetches all applied migrations from the [`HISTORY_TABLE`], ordered by version.
+pub async fn fetch_applied_migrations(
+    conn: &mut AnyConnection,
+    schema: &str,
+) -> Result<Vec<AppliedMigration>, Error> {
+    sqlx::query_as(&format!(
+        "SELECT version, name, checksum, applied_at, down_sql FROM {} ORDER BY version",
+        Backend::of(conn).qualify(schema, HISTORY_TABLE)
+    ))
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)
+}
+
+/// Verifies that none of the migrations which have already been applied have been modified since.
+pub fn verify_migrations(
+    migrations: &[Migration],
+    applied_migrations: &[AppliedMigration],
+) -> Result<(), Error> {
+    for migration in migrations {
+        let applied = applied_migrations
+            .iter()
+            .find(|applied| applied.version == i64::from(migration.version));
+        if let Some(applied) = applied {
+            if applied.checksum != migration.checksum() {
+                return Err(Error::MigrationChecksumMismatch(
+                    migration.version,
+                    migration.name.clone(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the version following the latest of the migration files and applied migrations.
+pub fn next_version(migrations: &[Migration], applied_migrations: &[AppliedMigration]) -> u32 {
+    migrations
+        .iter()
+        .map(|migration| migration.version)
+        .chain(
+            applied_migrations
+                .iter()
+                .map(|applied| applied.version as u32),
+        )
+        .max()
+        .map_or(1, |version| version + 1)
+}
+
+/// Returns the statements of a migration adding a value to an enum type.
+///
+/// Postgres can't use an enum value within the transaction adding it, and before version 12
+/// can't add one within a transaction at all, so these statements are run on their own by [`add_enum_values`].
+/// Each statement is expected on a line of its own.
+pub fn enum_value_statements(sql: &str) -> Vec<&str> {
+    sql.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("ALTER TYPE ") && line.contains(" ADD VALUE "))
+        .collect()
+}
+
+/// Adds the enum values of a migration, running each statement on its own.
+///
+/// Values which already exist are skipped, as the values are added before the rest of
+/// the migration is applied, which may fail.
+pub async fn add_enum_values(
+    conn: &mut AnyConnection,
+    migration: &Migration,
+) -> Result<Vec<AnyQueryResult>, Error> {
+    let mut results = Vec::new();
+    for statement in enum_value_statements(&migration.sql) {
+        match (&mut *conn).execute(statement).await {
+            Ok(result) => results.push(result),
+            Err(sqlx::Error::Database(err)) if err.code().as_deref() == Some(DUPLICATE_OBJECT) => {}
+            Err(err) => return Err(Error::Sqlx(err)),
+        }
+    }
+
+    Ok(results)
+}
+
+/// Applies a migration and records it in the [`HISTORY_TABLE`].
+///
+/// The migration should be applied within a transaction, so it is not recorded if it fails.
+/// MySQL implicitly commits schema changes, so a failing migration can be left partly applied there.
+/// Statements adding enum values are skipped, and should be run by [`add_enum_values`] first.
+pub async fn apply_migration(
+    conn: &mut AnyConnection,
+    schema: &str,
+    migration: &Migration,
+) -> Result<Vec<AnyQueryResult>, Error> {
+    let enum_value_statements = enum_value_statements(&migration.sql);
+    let sql = migration
+        .sql
+        .lines()
+        .filter(|line| !enum_value_statements.contains(&line.trim()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let results = (&mut *conn)
+        .execute_many(sql.as_str())
+        .collect::<Result<Vec<_>, _>>()
+        .await
+        .map_err(Error::Sqlx)?;
+
+    let backend = Backend::of(conn);
+    sqlx::query(&format!(
+        "INSERT INTO {} (version, name, checksum, down_sql) VALUES ({}, {}, {}, {})",
+        backend.qualify(schema, HISTORY_TABLE),
+        backend.param(1),
+        backend.param(2),
+        backend.param(3),
+        backend.param(4)
+    ))
+    .bind(i64::from(migration.version))
+    .bind(&migration.name)
+    .bind(migration.checksum())
+    .bind(&migration.down_sql)
+    .execute(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(results)
+}
+
+/// Returns the migration reverting an applied migration, which runs its down migration as a migration of its own.
+///
+/// Reverting a migration rather than removing it from the [`HISTORY_TABLE`] keeps its file applied,
+/// so the files of reviewed migrations are never removed or reused.
+/// Fails if the migration has no down migration, or if it has irreversible changes which aren't allowed.
+pub fn revert_migration(
+    version: u32,
+    migration: &AppliedMigration,
+    allow_irreversible: bool,
+) -> Result<Migration, Error> {
+    let reverted_version = migration.version as u32;
+    let down_sql = migration
+        .down_sql
+        .as_ref()
+        .ok_or_else(|| Error::MissingDownMigration(reverted_version, migration.name.clone()))?;
+
+    let irreversible_changes = migration.irreversible_changes();
+    if !allow_irreversible && !irreversible_changes.is_empty() {
+        return Err(Error::IrreversibleMigration(
+            reverted_version,
+            migration.name.clone(),
+            irreversible_changes.join(", "),
+        ));
+    }
+
+    Ok(Migration::new(
+        version,
+        format!("{}{:04}", REVERT_NAME_PREFIX, reverted_version),
+        down_sql.as_str(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        env::temp_dir().join(format!("awto-migrations-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn parse_file_name() {
+        assert_eq!(
+            Migration::parse_file_name("0012_20211018093000_create_product.sql"),
+            Some((
+                12,
+                "20211018093000".to_string(),
+                "create_product".to_string()
+            ))
+        );
+        assert_eq!(Migration::parse_file_name("README.md"), None);
+        assert_eq!(
+            Migration::parse_file_name("0012_20211018093000_create_product.down.sql"),
+            None
+        );
+        assert_eq!(Migration::parse_file_name("0001_sync.sql"), None);
+    }
+
+    #[test]
+    fn write_and_read_migrations() {
+        let dir = temp_dir();
+
+        let first = Migration {
+            down_sql: Some("ALTER TABLE product DROP COLUMN a;\n".to_string()),
+            ..Migration::new(1, "sync", "ALTER TABLE product ADD COLUMN a bigint;\n")
+        };
+        let second = Migration::new(2, "sync", "ALTER TABLE product DROP COLUMN a;\n");
+
+        let path = write_migration(&dir, &second).unwrap();
+        assert!(path.is_file());
+        write_migration(&dir, &first).unwrap();
+
+        assert_eq!(read_migrations(&dir).unwrap(), vec![first, second]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn verify_checksums() {
+        let migrations = [Migration::new(
+            1,
+            "sync",
+            "ALTER TABLE product ADD COLUMN a bigint;\n",
+        )];
+        let mut applied_migrations = [AppliedMigration {
+            version: 1,
+            name: "sync".to_string(),
+            checksum: migrations[0].checksum(),
+            applied_at: Utc::now(),
+            down_sql: None,
+        }];
+        assert!(verify_migrations(&migrations, &applied_migrations).is_ok());
+
+        applied_migrations[0].checksum =
+            Migration::new(1, "sync", "ALTER TABLE product ADD COLUMN b bigint;").checksum();
+        assert!(matches!(
+            verify_migrations(&migrations, &applied_migrations),
+            Err(Error::MigrationChecksumMismatch(1, _))
+        ));
+    }
+
+    #[test]
+    fn find_enum_value_statements() {
+        let sql = "ALTER TYPE product_status ADD VALUE IF NOT EXISTS 'archived';
+ALTER TABLE product ADD COLUMN status product_status NOT NULL DEFAULT 'archived';
+ALTER TYPE product_status RENAME TO status;
+";
+
+        assert_eq!(
+            enum_value_statements(sql),
+            vec!["ALTER TYPE product_status ADD VALUE IF NOT EXISTS 'archived';"]
+        );
+    }
+
+    #[test]
+    fn revert_migrations() {
+        let mut applied_migration = AppliedMigration {
+            version: 3,
+            name: "sync".to_string(),
+            checksum: String::new(),
+            applied_at: Utc::now(),
+            down_sql: Some(format!(
+                "{}product.legacy: drop column\nALTER TABLE product ADD COLUMN legacy integer;\n",
+                IRREVERSIBLE_PREFIX
+            )),
+        };
+        assert!(matches!(
+            revert_migration(5, &applied_migration, false),
+            Err(Error::IrreversibleMigration(3, _, _))
+        ));
+
+        let revert = revert_migration(5, &applied_migration, true).unwrap();
+        assert_eq!(revert.version, 5);
+        assert_eq!(revert.name, "revert_0003");
+        assert_eq!(revert.sql, applied_migration.down_sql.clone().unwrap());
+        assert_eq!(revert.down_sql, None);
+
+        let applied_revert = AppliedMigration {
+            version: 5,
+            checksum: revert.checksum(),
+            name: revert.name,
+            applied_at: Utc::now(),
+            down_sql: None,
+        };
+        assert_eq!(applied_revert.reverted_version(), Some(3));
+        assert_eq!(applied_migration.reverted_version(), None);
+
+        applied_migration.down_sql = None;
+        assert!(matches!(
+            revert_migration(5, &applied_migration, true),
+            Err(Error::MissingDownMigration(3, _))
+        ));
+    }
+
+    #[test]
+    fn irreversible_changes() {
+        let applied_migration = AppliedMigration {
+            version: 1,
+            name: "sync".to_string(),
+            checksum: String::new(),
+            applied_at: Utc::now(),
+            down_sql: Some(format!(
+                "{}product.legacy: drop column\nALTER TABLE product ADD COLUMN legacy integer;\n",
+                IRREVERSIBLE_PREFIX
+            )),
+        };
+
+        assert_eq!(
+            applied_migration.irreversible_changes(),
+            vec!["product.legacy: drop column"]
+        );
+    }
+}
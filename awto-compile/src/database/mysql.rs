@@ -0,0 +1,288 @@
+use awto::database::{
+    ConstraintKind, DatabaseColumn, DatabaseConstraint, DatabaseDefault, DatabaseIndex,
+    DatabaseTable, DatabaseType, IndexMethod,
+};
+use sqlx::AnyConnection;
+
+use super::{backend::Backend, sqlite::parenthesized};
+use crate::error::Error;
+
+/// Fetches a table from the connection's MySQL database.
+///
+/// MySQL backs unique constraints with unique indexes, so unique indexes named in `index_names` are fetched as indexes,
+/// and other unique indexes as constraints.
+pub async fn fetch_table(
+    conn: &mut AnyConnection,
+    table_name: &str,
+    index_names: &[&str],
+) -> Result<Option<DatabaseTable>, Error> {
+    #[derive(Debug, sqlx::FromRow)]
+    struct ColumnsQuery {
+        name: String,
+        ty: String,
+        nullable: bool,
+        column_default: Option<String>,
+        is_generated: bool,
+        is_identity: bool,
+        on_update_now: bool,
+        primary_key: bool,
+    }
+
+    #[derive(Debug, sqlx::FromRow)]
+    struct IndexesQuery {
+        name: String,
+        is_unique: bool,
+        columns: String,
+    }
+
+    #[derive(Debug, sqlx::FromRow)]
+    struct ForeignKeysQuery {
+        name: String,
+        column_name: String,
+        referenced_table: String,
+        referenced_column: String,
+    }
+
+    let raw_columns: Vec<ColumnsQuery> = sqlx::query_as(FETCH_COLUMNS_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    if raw_columns.is_empty() {
+        return Ok(None);
+    }
+
+    let foreign_keys: Vec<ForeignKeysQuery> = sqlx::query_as(FETCH_FOREIGN_KEYS_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    let checks: Vec<(String, String)> = sqlx::query_as(FETCH_CHECKS_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    let raw_indexes: Vec<IndexesQuery> = sqlx::query_as(FETCH_INDEXES_QUERY)
+        .bind(table_name)
+        .fetch_all(conn)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    let mut columns = raw_columns
+        .into_iter()
+        .map(|col| {
+            let ty = Backend::MySql
+                .parse_type(&col.ty, &[])
+                .ok_or_else(|| Error::UnsupportedType(table_name.to_string(), col.name.clone()))?;
+            let is_generated = col.is_generated;
+
+            Ok(DatabaseColumn {
+                references: foreign_keys
+                    .iter()
+                    .find(|foreign_key| foreign_key.column_name == col.name)
+                    .map(|foreign_key| {
+                        (
+                            foreign_key.referenced_table.clone(),
+                            foreign_key.referenced_column.clone(),
+                        )
+                    }),
+                default: if col.is_identity {
+                    Some(DatabaseDefault::Identity)
+                } else {
+                    col.column_default
+                        // MariaDB reports a missing default as `NULL`
+                        .filter(|default| default != "NULL")
+                        .map(|default| parse_default(default, &ty, is_generated))
+                },
+                ty,
+                nullable: col.nullable,
+                on_update_now: col.on_update_now,
+                unique: false,
+                constraint: None,
+                primary_key: col.primary_key,
+                renamed_from: Vec::new(),
+                name: col.name,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut indexes = Vec::new();
+    let mut constraints = Vec::new();
+    for index in raw_indexes {
+        // MySQL creates an index for each foreign key, named after it
+        if foreign_keys
+            .iter()
+            .any(|foreign_key| foreign_key.name == index.name)
+        {
+            continue;
+        }
+
+        let index_columns: Vec<_> = index
+            .columns
+            .split(',')
+            .filter(|column| !column.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        if !index.is_unique || index_names.contains(&index.name.as_str()) {
+            indexes.push(DatabaseIndex {
+                name: index.name,
+                columns: index_columns,
+                unique: index.is_unique,
+                method: IndexMethod::default(),
+            });
+        } else if let [column_name] = index_columns.as_slice() {
+            if let Some(column) = columns
+                .iter_mut()
+                .find(|column| &column.name == column_name)
+            {
+                column.unique = true;
+            }
+        } else {
+            constraints.push(DatabaseConstraint {
+                name: index.name,
+                kind: ConstraintKind::Unique(index_columns),
+            });
+        }
+    }
+
+    for (name, check) in checks {
+        let check = normalize_check(&check);
+        match columns
+            .iter_mut()
+            .find(|column| DatabaseConstraint::column_check_name(table_name, &column.name) == name)
+        {
+            Some(column) => column.constraint = Some(check),
+            None => constraints.push(DatabaseConstraint {
+                name,
+                kind: ConstraintKind::Check(check),
+            }),
+        }
+    }
+
+    Ok(Some(DatabaseTable {
+        name: table_name.to_string(),
+        columns,
+        indexes,
+        constraints,
+        renamed_from: Vec::new(),
+    }))
+}
+
+/// Parses a column default as MySQL reports it, which depends on the type of the column.
+///
+/// MySQL reports string defaults without quotes, and booleans as integers.
+fn parse_default(default: String, ty: &DatabaseType, is_generated: bool) -> DatabaseDefault {
+    match ty {
+        _ if is_generated => Backend::MySql.parse_default(default),
+        DatabaseType::Bool => DatabaseDefault::Bool(default != "0"),
+        DatabaseType::Text(_) if !default.starts_with('\'') => DatabaseDefault::String(default),
+        _ => Backend::MySql.parse_default(default),
+    }
+}
+
+/// Normalizes a check expression as MySQL reports it, such as ``(`price` >= 0)``.
+///
+/// MySQL quotes identifiers, prefixes strings with their character set and wraps the expression in parentheses,
+/// which are removed to compare the expression with the schema.
+///
+/// # Examples
+///
+/// ```
+/// # use awto_compile::database::mysql::normalize_check;
+/// assert_eq!(normalize_check("(`price` >= 0)"), "price >= 0");
+/// assert_eq!(normalize_check("(`grp` in (_utf8mb4'a',_utf8mb4'b'))"), "grp in ('a','b')");
+/// assert_eq!(normalize_check("(`name_utf8` <> _latin1'_x')"), "name_utf8 <> '_x'");
+/// ```
+pub fn normalize_check(check: &str) -> String {
+    let check = check.replace('`', "");
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut normalized = String::with_capacity(check.len());
+    let mut in_string = false;
+    let mut rest = check.as_str();
+    while let Some(c) = rest.chars().next() {
+        // Character set introducers are an identifier prefixed with `_` right before a string
+        if c == '_' && !in_string && !normalized.ends_with(is_identifier_char) {
+            let introducer_len = rest[1..]
+                .find(|c: char| !is_identifier_char(c))
+                .map(|len| len + 1)
+                .unwrap_or(rest.len());
+            if introducer_len > 1 && rest[introducer_len..].starts_with('\'') {
+                rest = &rest[introducer_len..];
+                continue;
+            }
+        }
+        if c == '\'' {
+            in_string = !in_string;
+        }
+
+        normalized.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    match parenthesized(&normalized) {
+        Some(inner) if inner.len() + 2 == normalized.len() => inner.to_string(),
+        _ => normalized,
+    }
+}
+
+const FETCH_COLUMNS_QUERY: &str = "
+SELECT
+    column_name AS name,
+    column_type AS ty,
+    is_nullable = 'YES' AS nullable,
+    column_default AS column_default,
+    extra LIKE '%DEFAULT_GENERATED%' AS is_generated,
+    extra LIKE '%auto_increment%' AS is_identity,
+    extra LIKE '%on update%' AS on_update_now,
+    column_key = 'PRI' AS primary_key
+FROM information_schema.columns
+WHERE
+    table_schema = DATABASE() AND
+    table_name = ?
+ORDER BY ordinal_position
+";
+
+const FETCH_FOREIGN_KEYS_QUERY: &str = "
+SELECT
+    constraint_name AS name,
+    column_name AS column_name,
+    referenced_table_name AS referenced_table,
+    referenced_column_name AS referenced_column
+FROM information_schema.key_column_usage
+WHERE
+    table_schema = DATABASE() AND
+    table_name = ? AND
+    referenced_table_name IS NOT NULL
+";
+
+const FETCH_CHECKS_QUERY: &str = "
+SELECT
+    tc.constraint_name AS name,
+    cc.check_clause AS check_clause
+FROM information_schema.table_constraints tc
+JOIN information_schema.check_constraints cc
+ON
+    cc.constraint_schema = tc.constraint_schema AND
+    cc.constraint_name = tc.constraint_name
+WHERE
+    tc.table_schema = DATABASE() AND
+    tc.table_name = ? AND
+    tc.constraint_type = 'CHECK'
+ORDER BY tc.constraint_name
+";
+
+const FETCH_INDEXES_QUERY: &str = "
+SELECT
+    index_name AS name,
+    non_unique = 0 AS is_unique,
+    COALESCE(GROUP_CONCAT(column_name ORDER BY seq_in_index SEPARATOR ','), '') AS columns
+FROM information_schema.statistics
+WHERE
+    table_schema = DATABASE() AND
+    table_name = ? AND
+    index_name <> 'PRIMARY'
+GROUP BY index_name, non_unique
+ORDER BY index_name
+";
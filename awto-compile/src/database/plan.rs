@@ -0,0 +1,271 @@
+use std::fmt;
+
+use awto::database::{
+    ColumnChange, ConstraintChange, EnumChange, IndexChange, SchemaDiff, TableChange,
+};
+
+use super::diff::DestructiveChange;
+
+/// Changes the database compiler would make to a database, without applying them.
+///
+/// The [`Display`](fmt::Display) implementation writes a human readable report of the plan.
+///
+/// # Examples
+///
+/// ```
+/// # use awto::database::{
+/// #     ColumnChange, DatabaseColumn, DatabaseIndex, DatabaseType, EnumChange, IndexChange,
+/// #     IndexMethod, SchemaDiff, TableChange,
+/// # };
+/// # use awto_compile::database::plan::DatabasePlan;
+/// let category = DatabaseColumn {
+///     name: "category".to_string(),
+///     ty: DatabaseType::Text(None),
+///     nullable: true,
+///     default: None,
+///     on_update_now: false,
+///     unique: false,
+///     constraint: None,
+///     primary_key: false,
+///     references: None,
+///     renamed_from: vec![],
+/// };
+/// let plan = DatabasePlan {
+///     pending_migrations: vec![],
+///     diff: SchemaDiff {
+///         enums: vec![EnumChange::AddValues {
+///             name: "product_status".to_string(),
+///             values: vec!["archived".to_string()],
+///         }],
+///         tables: vec![TableChange::Alter {
+///             table: "product".to_string(),
+///             columns: vec![
+///                 ColumnChange::Add(category),
+///                 ColumnChange::DropNotNull {
+///                     column: "price".to_string(),
+///                 },
+///             ],
+///             indexes: vec![IndexChange::Create(DatabaseIndex {
+///                 name: "product_category_idx".to_string(),
+///                 columns: vec!["category".to_string()],
+///                 unique: false,
+///                 method: IndexMethod::BTree,
+///             })],
+///             constraints: vec![],
+///         }],
+///     },
+///     destructive_changes: vec![],
+///     removed_tables: vec![],
+///     sql: "ALTER TYPE product_status ADD VALUE IF NOT EXISTS 'archived';\nALTER TABLE product ADD COLUMN category character varying;\nALTER TABLE product ALTER COLUMN price DROP NOT NULL;\nCREATE INDEX product_category_idx ON product (category);".to_string(),
+///     down_sql: "DROP INDEX product_category_idx;\nALTER TABLE product ALTER COLUMN price SET NOT NULL;\nALTER TABLE product DROP COLUMN category;".to_string(),
+///     irreversible_changes: vec![],
+/// };
+///
+/// assert_eq!(plan.to_string(), "\
+/// product_status: 1 values added
+///   + archived
+/// product: 1 added, 1 altered, 0 dropped
+///   + category
+///   ~ price
+///   + index product_category_idx (category)
+///
+/// ALTER TYPE product_status ADD VALUE IF NOT EXISTS 'archived';
+/// ALTER TABLE product ADD COLUMN category character varying;
+/// ALTER TABLE product ALTER COLUMN price DROP NOT NULL;
+/// CREATE INDEX product_category_idx ON product (category);
+/// ");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DatabasePlan {
+    /// File names of migrations which have not been applied to the database yet.
+    pub pending_migrations: Vec<String>,
+    /// Changes needed to sync the database with the schema.
+    pub diff: SchemaDiff,
+    /// Changes in the diff which can lose data.
+    pub destructive_changes: Vec<DestructiveChange>,
+    /// Awto managed tables which no longer exist in the schema.
+    pub removed_tables: Vec<String>,
+    /// Sql statements which would be executed.
+    pub sql: String,
+    /// Sql statements which would undo the changes.
+    pub down_sql: String,
+    /// Changes which can't be undone without losing data.
+    pub irreversible_changes: Vec<String>,
+}
+
+impl DatabasePlan {
+    /// Returns `true` if there are no changes to apply.
+    pub fn is_empty(&self) -> bool {
+        self.pending_migrations.is_empty() && self.sql.is_empty()
+    }
+}
+
+impl fmt::Display for DatabasePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() && self.removed_tables.is_empty() {
+            return writeln!(f, "database is up to date");
+        }
+
+        if !self.pending_migrations.is_empty() {
+            writeln!(f, "pending migrations:")?;
+            for migration in &self.pending_migrations {
+                writeln!(f, "  {}", migration)?;
+            }
+            writeln!(f)?;
+        }
+
+        for enum_change in &self.diff.enums {
+            match enum_change {
+                EnumChange::Create(database_enum) => {
+                    writeln!(
+                        f,
+                        "{}: enum created with {} values",
+                        database_enum.name,
+                        database_enum.values.len()
+                    )?;
+                    for value in &database_enum.values {
+                        writeln!(f, "  + {}", value)?;
+                    }
+                }
+                EnumChange::Drop(database_enum) => {
+                    writeln!(f, "{}: enum dropped", database_enum.name)?;
+                }
+                EnumChange::AddValues { name, values } => {
+                    writeln!(f, "{}: {} values added", name, values.len())?;
+                    for value in values {
+                        writeln!(f, "  + {}", value)?;
+                    }
+                }
+            }
+        }
+
+        for table_change in &self.diff.tables {
+            match table_change {
+                TableChange::Create(table) => {
+                    writeln!(
+                        f,
+                        "{}: created with {} columns",
+                        table.name,
+                        table.columns.len()
+                    )?;
+                    for column in &table.columns {
+                        writeln!(f, "  + {}", column.name)?;
+                    }
+                }
+                TableChange::Rename { from, to } => {
+                    writeln!(f, "{}: renamed from {}", to, from)?;
+                }
+                TableChange::Drop(table) => {
+                    writeln!(f, "{}: dropped", table.name)?;
+                }
+                TableChange::Alter {
+                    table,
+                    columns,
+                    indexes,
+                    constraints,
+                } => {
+                    // Changes made to a column before it is renamed are listed under its new name
+                    let renamed_columns: Vec<_> = columns
+                        .iter()
+                        .filter_map(|column_change| match column_change {
+                            ColumnChange::Rename { from, .. } => Some(from.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+
+                    let mut added = Vec::new();
+                    let mut altered = Vec::new();
+                    let mut dropped = Vec::new();
+                    for column_change in columns {
+                        match column_change {
+                            ColumnChange::Add(column) => added.push(column.name.as_str()),
+                            ColumnChange::Drop(column) => dropped.push(column.name.as_str()),
+                            _ => {
+                                let column = column_change.column_name();
+                                if !altered.contains(&column) && !renamed_columns.contains(&column)
+                                {
+                                    altered.push(column);
+                                }
+                            }
+                        }
+                    }
+
+                    writeln!(
+                        f,
+                        "{}: {} added, {} altered, {} dropped",
+                        table,
+                        added.len(),
+                        altered.len(),
+                        dropped.len()
+                    )?;
+                    for column in added {
+                        writeln!(f, "  + {}", column)?;
+                    }
+                    for column in altered {
+                        let renamed_from =
+                            columns
+                                .iter()
+                                .find_map(|column_change| match column_change {
+                                    ColumnChange::Rename { from, to } if to == column => Some(from),
+                                    _ => None,
+                                });
+                        match renamed_from {
+                            Some(from) => writeln!(f, "  ~ {} (renamed from {})", column, from)?,
+                            None => writeln!(f, "  ~ {}", column)?,
+                        }
+                    }
+                    for column in dropped {
+                        writeln!(f, "  - {}", column)?;
+                    }
+                    for index_change in indexes {
+                        let (prefix, index) = match index_change {
+                            IndexChange::Create(index) => ('+', index),
+                            IndexChange::Drop(index) => ('-', index),
+                        };
+                        writeln!(
+                            f,
+                            "  {} index {} ({})",
+                            prefix,
+                            index.name,
+                            index.columns.join(", ")
+                        )?;
+                    }
+                    for constraint_change in constraints {
+                        let (prefix, constraint) = match constraint_change {
+                            ConstraintChange::Add(constraint) => ('+', constraint),
+                            ConstraintChange::Drop(constraint) => ('-', constraint),
+                        };
+                        writeln!(
+                            f,
+                            "  {} constraint {} {}",
+                            prefix, constraint.name, constraint.kind
+                        )?;
+                    }
+                }
+            }
+        }
+
+        if !self.removed_tables.is_empty() {
+            writeln!(f, "removed from schema:")?;
+            for table in &self.removed_tables {
+                writeln!(f, "  {}", table)?;
+            }
+        }
+
+        if !self.destructive_changes.is_empty() {
+            writeln!(f, "destructive changes:")?;
+            for destructive_change in &self.destructive_changes {
+                writeln!(f, "  ! {}", destructive_change)?;
+            }
+        }
+
+        if !self.sql.is_empty() {
+            if !self.diff.is_empty() {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", self.sql)?;
+        }
+
+        Ok(())
+    }
+}
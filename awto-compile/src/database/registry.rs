@@ -0,0 +1,194 @@
+use std::{fmt, str};
+
+use awto::database::DatabaseType;
+use sqlx::{AnyConnection, Executor};
+
+use super::backend::{quote_identifier, quote_literal, Backend};
+use crate::error::Error;
+
+/// Table used to record which tables are managed by awto.
+pub const REGISTRY_TABLE: &str = "_awto_tables";
+
+/// Prefix added to the name of tables archived by [`RemovedTables::Archive`].
+pub const ARCHIVED_TABLE_PREFIX: &str = "_archived_";
+
+/// What to do with awto managed tables which no longer exist in the schema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemovedTables {
+    /// Leaves the tables in the database, only reporting them.
+    Keep,
+    /// Drops the tables along with their data.
+    Drop,
+    /// Renames the tables with the [`ARCHIVED_TABLE_PREFIX`].
+    Archive,
+}
+
+// Deriving `Default` for enums requires Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl Default for RemovedTables {
+    fn default() -> Self {
+        Self::Keep
+    }
+}
+
+#[derive(Debug)]
+pub struct RemovedTablesFromStrError;
+
+impl str::FromStr for RemovedTables {
+    type Err = RemovedTablesFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let removed_tables = match s {
+            "keep" => Self::Keep,
+            "drop" => Self::Drop,
+            "archive" => Self::Archive,
+            _ => return Err(RemovedTablesFromStrError),
+        };
+        Ok(removed_tables)
+    }
+}
+
+impl fmt::Display for RemovedTables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keep => write!(f, "keep"),
+            Self::Drop => write!(f, "drop"),
+            Self::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+/// Returns the name a removed table is archived as.
+pub fn archived_table_name(table: &str) -> String {
+    format!("{}{}", ARCHIVED_TABLE_PREFIX, table)
+}
+
+/// Creates the [`REGISTRY_TABLE`] in a schema if it does not yet exist.
+pub async fn ensure_registry_table(conn: &mut AnyConnection, schema: &str) -> Result<(), Error> {
+    let backend = Backend::of(conn);
+    // MySQL only keys text columns with a maximum length, and its table names are at most 64 characters
+    let name_type = match backend {
+        Backend::MySql => DatabaseType::Text(Some(64)),
+        Backend::Postgres | Backend::Sqlite => DatabaseType::Text(None),
+    };
+    conn.execute(
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+  name {} NOT NULL PRIMARY KEY,
+  registered_at {} NOT NULL DEFAULT {}
+);",
+            backend.qualify(schema, REGISTRY_TABLE),
+            backend.write_type(schema, &name_type),
+            backend.write_type(schema, &DatabaseType::Timestamptz),
+            backend.now(),
+        )
+        .as_str(),
+    )
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(())
+}
+
+/// Fetches the names of registered tables which still exist in a schema.
+///
+/// Returns no tables if the [`REGISTRY_TABLE`] does not exist.
+pub async fn fetch_registered_tables(
+    conn: &mut AnyConnection,
+    schema: &str,
+) -> Result<Vec<String>, Error> {
+    let backend = Backend::of(conn);
+    let (exists,): (bool,) = sqlx::query_as(backend.table_exists_query())
+        .bind(backend.qualify(schema, REGISTRY_TABLE))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let tables: Vec<(String,)> = sqlx::query_as(&format!(
+        "SELECT name FROM {} WHERE {} ORDER BY name",
+        backend.qualify(schema, REGISTRY_TABLE),
+        registered_table_exists(backend, schema)
+    ))
+    .fetch_all(conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    Ok(tables.into_iter().map(|(name,)| name).collect())
+}
+
+/// Registers the tables in the schema, and unregisters tables which no longer exist in the database schema.
+pub async fn update_registered_tables(
+    conn: &mut AnyConnection,
+    schema: &str,
+    tables: &[&str],
+) -> Result<(), Error> {
+    let backend = Backend::of(conn);
+    sqlx::query(&format!(
+        "DELETE FROM {} WHERE NOT ({})",
+        backend.qualify(schema, REGISTRY_TABLE),
+        registered_table_exists(backend, schema)
+    ))
+    .execute(&mut *conn)
+    .await
+    .map_err(Error::Sqlx)?;
+
+    let insert_sql = match backend {
+        Backend::MySql => format!(
+            "INSERT IGNORE INTO {} (name) VALUES (?)",
+            backend.qualify(schema, REGISTRY_TABLE)
+        ),
+        Backend::Postgres | Backend::Sqlite => format!(
+            "INSERT INTO {} (name) VALUES ($1) ON CONFLICT DO NOTHING",
+            backend.qualify(schema, REGISTRY_TABLE)
+        ),
+    };
+    for table in tables {
+        sqlx::query(&insert_sql)
+            .bind(table)
+            .execute(&mut *conn)
+            .await
+            .map_err(Error::Sqlx)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the condition selecting rows of the [`REGISTRY_TABLE`] whose table still exists.
+fn registered_table_exists(backend: Backend, schema: &str) -> String {
+    match backend {
+        Backend::Postgres => format!(
+            "to_regclass({} || name) IS NOT NULL",
+            quote_literal(&format!("{}.", quote_identifier(schema)))
+        ),
+        Backend::Sqlite => {
+            "name IN (SELECT name FROM sqlite_master WHERE type = 'table')".to_string()
+        }
+        Backend::MySql => {
+            "name IN (SELECT table_name FROM information_schema.tables WHERE table_schema = DATABASE())"
+                .to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_removed_tables() {
+        for removed_tables in [
+            RemovedTables::Keep,
+            RemovedTables::Drop,
+            RemovedTables::Archive,
+        ] {
+            assert_eq!(
+                removed_tables.to_string().parse::<RemovedTables>().unwrap(),
+                removed_tables
+            );
+        }
+        assert!("delete".parse::<RemovedTables>().is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use awto::database::{
+    ConstraintKind, DatabaseColumn, DatabaseConstraint, DatabaseDefault, DatabaseIndex,
+    DatabaseTable, IndexMethod,
+};
+use sqlx::AnyConnection;
+
+use super::backend::Backend;
+use crate::error::Error;
+
+/// Prefix of the table a table is rebuilt into before replacing it.
+pub const REBUILT_TABLE_PREFIX: &str = "_awto_new_";
+
+/// Fetches a table from a SQLite database.
+///
+/// Columns, indexes and foreign keys are read through pragmas.
+/// Check constraints, the names of table constraints and `AUTOINCREMENT` are only kept in the table's sql,
+/// so they are parsed from the `CREATE TABLE` statement written by awto.
+pub async fn fetch_table(
+    conn: &mut AnyConnection,
+    table_name: &str,
+    enum_names: &[&str],
+) -> Result<Option<DatabaseTable>, Error> {
+    #[derive(Debug, sqlx::FromRow)]
+    struct ColumnsQuery {
+        name: String,
+        ty: String,
+        notnull: bool,
+        dflt_value: Option<String>,
+        pk: bool,
+    }
+
+    #[derive(Debug, sqlx::FromRow)]
+    struct IndexesQuery {
+        name: String,
+        is_unique: bool,
+        columns: String,
+    }
+
+    let raw_columns: Vec<ColumnsQuery> = sqlx::query_as(FETCH_COLUMNS_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+
+    if raw_columns.is_empty() {
+        return Ok(None);
+    }
+
+    let unique_columns: Vec<(String,)> = sqlx::query_as(FETCH_UNIQUE_COLUMNS_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    let references: Vec<(String, String, String)> = sqlx::query_as(FETCH_REFERENCES_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    let triggers: Vec<(String,)> = sqlx::query_as(FETCH_TRIGGERS_QUERY)
+        .bind(table_name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    let (sql,): (String,) =
+        sqlx::query_as("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = $1")
+            .bind(table_name)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(Error::Sqlx)?;
+    let CreateTableSql {
+        column_checks,
+        identity_columns,
+        constraints,
+    } = parse_create_table_sql(&sql);
+
+    let columns = raw_columns
+        .into_iter()
+        .map(|col| {
+            Ok(DatabaseColumn {
+                unique: unique_columns.iter().any(|(column,)| column == &col.name),
+                constraint: column_checks
+                    .iter()
+                    .find(|(column, _)| column == &col.name)
+                    .map(|(_, check)| check.clone()),
+                references: references
+                    .iter()
+                    .find(|(column, _, _)| column == &col.name)
+                    .map(|(_, table, column)| (table.clone(), column.clone())),
+                ty: Backend::Sqlite
+                    .parse_type(&col.ty, enum_names)
+                    .ok_or_else(|| {
+                        Error::UnsupportedType(table_name.to_string(), col.name.clone())
+                    })?,
+                nullable: !col.notnull && !col.pk,
+                on_update_now: triggers.iter().any(|(trigger,)| {
+                    trigger == &Backend::Sqlite.on_update_trigger_name(table_name, &col.name)
+                }),
+                default: if identity_columns.contains(&col.name) {
+                    Some(DatabaseDefault::Identity)
+                } else {
+                    col.dflt_value
+                        .map(|default| Backend::Sqlite.parse_default(default))
+                },
+                primary_key: col.pk,
+                renamed_from: Vec::new(),
+                name: col.name,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let raw_indexes: Vec<IndexesQuery> = sqlx::query_as(FETCH_INDEXES_QUERY)
+        .bind(table_name)
+        .fetch_all(conn)
+        .await
+        .map_err(Error::Sqlx)?;
+    let indexes = raw_indexes
+        .into_iter()
+        .map(|index| DatabaseIndex {
+            name: index.name,
+            columns: index.columns.split(',').map(ToString::to_string).collect(),
+            unique: index.is_unique,
+            method: IndexMethod::default(),
+        })
+        .collect();
+
+    Ok(Some(DatabaseTable {
+        name: table_name.to_string(),
+        columns,
+        indexes,
+        constraints,
+        renamed_from: Vec::new(),
+    }))
+}
+
+/// What a `CREATE TABLE` statement holds beyond what pragmas report.
+#[derive(Debug, Default, PartialEq)]
+struct CreateTableSql {
+    /// Check expressions of columns, by column name.
+    column_checks: Vec<(String, String)>,
+    /// Names of the columns declared with `AUTOINCREMENT`.
+    identity_columns: Vec<String>,
+    constraints: Vec<DatabaseConstraint>,
+}
+
+/// Parses the column checks, identity columns and table constraints from a `CREATE TABLE` statement written by awto,
+/// which writes each column and constraint on its own line.
+fn parse_create_table_sql(sql: &str) -> CreateTableSql {
+    let mut parsed = CreateTableSql::default();
+
+    // The first line holds the table name, and the last closes the statement
+    for line in sql.lines().skip(1) {
+        let line = line.trim().trim_end_matches(',');
+
+        if let Some(constraint) = line.strip_prefix("CONSTRAINT ") {
+            let (name, kind) = match constraint.split_once(' ') {
+                Some(name_kind) => name_kind,
+                None => continue,
+            };
+            let kind = if let Some(columns) = kind.strip_prefix("UNIQUE ") {
+                match parenthesized(columns) {
+                    Some(columns) => ConstraintKind::Unique(
+                        columns
+                            .split(',')
+                            .map(|column| column.trim().to_string())
+                            .collect(),
+                    ),
+                    None => continue,
+                }
+            } else if let Some(check) = kind.strip_prefix("CHECK ") {
+                match parenthesized(check) {
+                    Some(check) => ConstraintKind::Check(check.to_string()),
+                    None => continue,
+                }
+            } else {
+                continue;
+            };
+
+            parsed.constraints.push(DatabaseConstraint {
+                name: name.to_string(),
+                kind,
+            });
+        } else if let Some((column, definition)) = line.split_once(' ') {
+            if let Some(check) = definition
+                .find(" CHECK (")
+                .and_then(|i| parenthesized(&definition[i + " CHECK ".len()..]))
+            {
+                parsed
+                    .column_checks
+                    .push((column.to_string(), check.to_string()));
+            }
+            if definition.contains(" PRIMARY KEY AUTOINCREMENT") {
+                parsed.identity_columns.push(column.to_string());
+            }
+        }
+    }
+
+    parsed
+}
+
+/// Returns the contents of the parentheses `s` starts with.
+pub(super) fn parenthesized(s: &str) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&s[1..i]);
+                }
+            }
+            _ if depth == 0 => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+const FETCH_COLUMNS_QUERY: &str = "
+SELECT name, type AS ty, \"notnull\", dflt_value, pk > 0 AS pk
+FROM pragma_table_info($1)
+ORDER BY cid
+";
+
+const FETCH_UNIQUE_COLUMNS_QUERY: &str = "
+SELECT ii.name
+FROM pragma_index_list($1) il
+JOIN pragma_index_info(il.name) ii
+WHERE
+    il.origin = 'u' AND
+    (SELECT COUNT(*) FROM pragma_index_info(il.name)) = 1
+";
+
+const FETCH_REFERENCES_QUERY: &str = "
+SELECT \"from\", \"table\", \"to\"
+FROM pragma_foreign_key_list($1)
+";
+
+const FETCH_TRIGGERS_QUERY: &str = "
+SELECT name
+FROM sqlite_master
+WHERE type = 'trigger' AND tbl_name = $1
+";
+
+const FETCH_INDEXES_QUERY: &str = "
+SELECT
+    il.name,
+    il.\"unique\" AS is_unique,
+    (
+        SELECT group_concat(ii.name, ',')
+        FROM (SELECT name FROM pragma_index_info(il.name) ORDER BY seqno) ii
+    ) AS columns
+FROM pragma_index_list($1) il
+WHERE il.origin = 'c'
+ORDER BY il.name
+";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_checks_and_constraints() {
+        let sql = "\
+CREATE TABLE \"product\" (
+  id integer NOT NULL PRIMARY KEY AUTOINCREMENT,
+  price bigint NOT NULL DEFAULT 0 CHECK (price >= 0 AND (price < 100)),
+  name text NOT NULL UNIQUE,
+  CONSTRAINT product_name_price_key UNIQUE (name, price),
+  CONSTRAINT product_name_not_empty CHECK (length(name) > 0)
+)";
+
+        let parsed = parse_create_table_sql(sql);
+        assert_eq!(
+            parsed.column_checks,
+            vec![(
+                "price".to_string(),
+                "price >= 0 AND (price < 100)".to_string()
+            )]
+        );
+        assert_eq!(parsed.identity_columns, vec!["id".to_string()]);
+        assert_eq!(
+            parsed.constraints,
+            vec![
+                DatabaseConstraint {
+                    name: "product_name_price_key".to_string(),
+                    kind: ConstraintKind::Unique(vec!["name".to_string(), "price".to_string()]),
+                },
+                DatabaseConstraint {
+                    name: "product_name_not_empty".to_string(),
+                    kind: ConstraintKind::Check("length(name) > 0".to_string()),
+                },
+            ]
+        );
+    }
+}
@@ -1,7 +1,35 @@
+use crate::database::{backend::Backend, diff::DestructiveChange};
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("database has unsupported type in {0}.{0}")]
     UnsupportedType(String, String),
+    #[error("{0} databases don't support the type of {1}.{2}")]
+    UnsupportedBackendType(Backend, String, String),
+    #[error("migration {0} '{1}' has been modified since it was applied")]
+    MigrationChecksumMismatch(u32, String),
+    #[error("migration {0} '{1}' has no down migration")]
+    MissingDownMigration(u32, String),
+    #[error("migration {0} '{1}' has irreversible changes which would lose data: {2}")]
+    IrreversibleMigration(u32, String, String),
+    #[error(
+        "refusing to roll back before the pending migrations are applied: {}",
+        .0.join(", ")
+    )]
+    PendingMigrations(Vec<String>),
+    #[error(
+        "refusing to apply destructive changes without allowing them: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    DestructiveChanges(Vec<DestructiveChange>),
+    #[error(
+        "{0} databases can't plan changes after pending migrations, apply the migrations first"
+    )]
+    DryRunPendingMigrations(Backend),
+    #[error("database is being synced by another build, try again once it has finished")]
+    Locked,
+    #[error("rows of table '{0}' violate its foreign keys")]
+    ForeignKeyViolation(String),
     #[error("database error: {0}")]
     Sqlx(sqlx::Error),
 }
@@ -1,18 +1,32 @@
 use std::{env, fmt::Write};
 
 use awto::{
-    protobuf::{ProtobufField, ProtobufMessage, ProtobufMethod, ProtobufService},
+    protobuf::{
+        ProtobufEnum, ProtobufField, ProtobufMessage, ProtobufMethod, ProtobufOneof,
+        ProtobufService, ProtobufType,
+    },
     schema::{Model, Role},
 };
-use heck::SnakeCase;
+use heck::{CamelCase, ShoutySnakeCase, SnakeCase};
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
-use crate::util::{is_ty_vec, strip_ty_option};
+use crate::util::{is_ty_option, is_ty_vec, strip_ty_option, strip_ty_vec};
 
 const COMPILED_PROTO_FILE: &str = "app.proto";
 const COMPILED_RUST_FILE: &str = "app.rs";
 
+const DECIMAL_TYPES: [&str; 2] = ["rust_decimal::Decimal", "Decimal"];
+
+const TIMESTAMP_TYPES: [&str; 6] = [
+    "chrono::NaiveDateTime",
+    "NaiveDateTime",
+    "chrono::DateTime<chrono::FixedOffset>",
+    "chrono::DateTime<FixedOffset>",
+    "DateTime<chrono::FixedOffset>",
+    "DateTime<FixedOffset>",
+];
+
 #[cfg(feature = "async")]
 pub fn compile_protobuf(
     models: Vec<Model>,
@@ -98,6 +112,25 @@ pub fn compile_protobuf(
 ///
 /// import "google/protobuf/timestamp.proto";
 ///
+/// enum ReviewStatus {
+///   REVIEW_STATUS_UNSPECIFIED = 0;
+///   REVIEW_STATUS_PENDING = 1;
+///   REVIEW_STATUS_PUBLISHED = 2;
+///   REVIEW_STATUS_HIDDEN = 3;
+/// }
+///
+/// message ProductLookup {
+///   message Price {
+///     int64 min = 1;
+///     optional int64 max = 2;
+///   }
+///   oneof product_lookup {
+///     string id = 1;
+///     string name = 2;
+///     Price price = 3;
+///   }
+/// }
+///
 /// message Product {
 ///   string id = 1;
 ///   google.protobuf.Timestamp created_at = 2;
@@ -115,6 +148,16 @@ pub fn compile_protobuf(
 ///   repeated Product products = 1;
 /// }
 ///
+/// message ReviewQuery {
+///   string product_id = 1;
+///   optional ReviewStatus status = 2;
+///   optional string tags = 3;
+/// }
+///
+/// message ProductSearch {
+///   ProductLookup lookup = 1;
+/// }
+///
 /// message NewProduct {
 ///   string name = 1;
 ///   optional int64 price = 2;
@@ -143,6 +186,14 @@ impl ProtobufCompiler {
         write!(proto, "{}", self.write_protobuf_header()).unwrap();
         writeln!(proto).unwrap();
 
+        for (_, protobuf_enum) in self.protobuf_enums() {
+            writeln!(proto, "{}", self.write_protobuf_enum(protobuf_enum)).unwrap();
+        }
+
+        for (_, protobuf_oneof) in self.protobuf_oneofs() {
+            writeln!(proto, "{}", self.write_protobuf_oneof(protobuf_oneof)).unwrap();
+        }
+
         for message in self.all_protobuf_messages() {
             writeln!(proto, "{}", self.write_protobuf_message(message)).unwrap();
         }
@@ -162,15 +213,23 @@ impl ProtobufCompiler {
             code,
             r#"
 pub enum TryFromProtoError {{
+    InvalidDecimal,
+    InvalidEnumValue(String),
     InvalidUuid,
+    InvalidJson(String),
     MissingField(String),
+    UnsetOneof(String),
 }}
 
 impl ::std::fmt::Display for TryFromProtoError {{
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> Result<(), ::std::fmt::Error> {{
         match self {{
+            Self::InvalidDecimal => write!(f, "invalid decimal"),
+            Self::InvalidEnumValue(name) => write!(f, "invalid value for enum '{{}}'", name),
             Self::InvalidUuid => write!(f, "invalid uuid"),
+            Self::InvalidJson(field) => write!(f, "invalid json in field '{{}}'", field),
             Self::MissingField(field) => write!(f, "missing field '{{}}'", field),
+            Self::UnsetOneof(name) => write!(f, "no value set for oneof '{{}}'", name),
         }}
     }}
 }}
@@ -178,62 +237,169 @@ impl ::std::fmt::Display for TryFromProtoError {{
         )
         .unwrap();
 
-        for (model, _) in self.protobuf_messages() {
+        for (model, protobuf_enum) in self.protobuf_enums() {
             let ident = format_ident!("{}", model.name);
+            let enum_name = &model.name;
+            let variant_idents: Vec<_> = model
+                .variants
+                .iter()
+                .map(|variant| format_ident!("{}", variant.name))
+                .collect();
+            // Prost strips the enum name from the prefixed values when naming variants
+            let proto_variant_idents: Vec<_> = protobuf_enum
+                .values
+                .iter()
+                .map(|value| format_ident!("{}", value.to_camel_case()))
+                .collect();
 
-            let mut from_rust_fields = Vec::new();
-            let mut from_proto_fields = Vec::new();
+            let expanded = quote!(
+                impl ::std::convert::TryFrom<#ident> for ::schema::#ident {
+                    type Error = TryFromProtoError;
 
-            for field in &model.fields {
-                let field_ident = format_ident!("{}", field.name);
-                let field_ident_string = &field.name;
-
-                let ty = strip_ty_option(&field.ty);
-
-                match ty {
-                    "chrono::NaiveDateTime"
-                    | "NaiveDateTime"
-                    | "chrono::DateTime<chrono::FixedOffset>"
-                    | "chrono::DateTime<FixedOffset>"
-                    | "DateTime<chrono::FixedOffset>"
-                    | "DateTime<FixedOffset>" => {
-                        from_rust_fields.push(quote!(
-                            #field_ident: Some(::prost_types::Timestamp {
-                                nanos: val.#field_ident.timestamp_nanos() as i32,
-                                seconds: val.#field_ident.timestamp(),
-                            })
-                        ));
-                        from_proto_fields.push(quote!(
-                        #field_ident: {
-                            let unwrapped_value = val.#field_ident.ok_or_else(|| TryFromProtoError::MissingField(#field_ident_string.to_string()))?;
-                            ::chrono::DateTime::from_utc(
-                                ::chrono::naive::NaiveDateTime::from_timestamp(
-                                    unwrapped_value.seconds,
-                                    unwrapped_value.nanos as u32
-                                ),
-                                ::chrono::FixedOffset::east(0),
+                    fn try_from(val: #ident) -> Result<Self, Self::Error> {
+                        match val {
+                            #ident::Unspecified => Err(TryFromProtoError::InvalidEnumValue(#enum_name.to_string())),
+                            #( #ident::#proto_variant_idents => Ok(Self::#variant_idents), )*
+                        }
+                    }
+                }
+
+                impl ::std::convert::From<::schema::#ident> for #ident {
+                    fn from(val: ::schema::#ident) -> Self {
+                        match val {
+                            #( ::schema::#ident::#variant_idents => Self::#proto_variant_idents, )*
+                        }
+                    }
+                }
+            );
+
+            write!(code, "{}", expanded).unwrap();
+        }
+
+        for (model, protobuf_oneof) in self.protobuf_oneofs() {
+            let ident = format_ident!("{}", model.name);
+            let oneof_name = &model.name;
+            // Prost generates a module named after the message, holding its nested messages and
+            // an enum of the oneof, which is named after the message too
+            let module_ident = format_ident!("{}", model.name.to_snake_case());
+            let oneof_ident = format_ident!("{}", model.name.to_snake_case().to_camel_case());
+
+            let mut from_rust_arms = Vec::new();
+            let mut from_proto_arms = Vec::new();
+
+            for (variant, oneof_variant) in model.variants.iter().zip(&protobuf_oneof.variants) {
+                let variant_ident = format_ident!("{}", variant.name);
+                let proto_variant_ident = format_ident!("{}", oneof_variant.name.to_camel_case());
+
+                if let (true, ProtobufType::Custom(message)) =
+                    (oneof_variant.nested, &oneof_variant.ty)
+                {
+                    let message_ident = format_ident!("{}", variant.name.to_camel_case());
+                    let field_idents: Vec<_> = variant
+                        .fields
+                        .iter()
+                        .map(|field| format_ident!("{}", field.name))
+                        .collect();
+                    let (from_rust_fields, from_proto_fields): (Vec<_>, Vec<_>) = variant
+                        .fields
+                        .iter()
+                        .zip(&message.fields)
+                        .zip(&field_idents)
+                        .map(|((field, protobuf_field), field_ident)| {
+                            let (from_rust, from_proto) = self.field_conversions(
+                                &field.name,
+                                &field.ty,
+                                &protobuf_field.ty,
+                                &quote!(#field_ident),
+                                &quote!(v.#field_ident),
+                            );
+
+                            (
+                                quote!(#field_ident: #from_rust),
+                                quote!(#field_ident: #from_proto),
                             )
+                        })
+                        .unzip();
+
+                    from_rust_arms.push(quote!(
+                        ::schema::#ident::#variant_ident { #( #field_idents ),* } =>
+                            #module_ident::#oneof_ident::#proto_variant_ident(#module_ident::#message_ident {
+                                #( #from_rust_fields, )*
+                            })
+                    ));
+                    from_proto_arms.push(quote!(
+                        #module_ident::#oneof_ident::#proto_variant_ident(v) => Self::#variant_ident {
+                            #( #from_proto_fields, )*
                         }
                     ));
-                    }
-                    "uuid::Uuid" | "Uuid" => {
-                        from_rust_fields.push(quote!(
-                            #field_ident: val.#field_ident.to_string()
-                        ));
-                        from_proto_fields.push(quote!(
-                        #field_ident: ::uuid::Uuid::parse_str(&val.#field_ident).map_err(|_| TryFromProtoError::InvalidUuid)?
+                } else {
+                    let (from_rust, from_proto) = self.oneof_value_conversions(
+                        &oneof_variant.name,
+                        &variant.fields[0].ty,
+                        &oneof_variant.ty,
+                        &quote!(v),
+                        &quote!(v),
+                    );
+
+                    from_rust_arms.push(quote!(
+                        ::schema::#ident::#variant_ident(v) => #module_ident::#oneof_ident::#proto_variant_ident(#from_rust)
                     ));
+                    from_proto_arms.push(quote!(
+                        #module_ident::#oneof_ident::#proto_variant_ident(v) => Self::#variant_ident(#from_proto)
+                    ));
+                }
+            }
+
+            let expanded = quote!(
+                impl ::std::convert::TryFrom<#ident> for ::schema::#ident {
+                    type Error = TryFromProtoError;
+
+                    #[allow(unused_variables)]
+                    fn try_from(val: #ident) -> Result<Self, Self::Error> {
+                        let value = val
+                            .#module_ident
+                            .ok_or_else(|| TryFromProtoError::UnsetOneof(#oneof_name.to_string()))?;
+
+                        Ok(match value {
+                            #( #from_proto_arms, )*
+                        })
                     }
-                    _ => {
-                        if is_ty_vec(ty) {
-                            from_rust_fields.push(quote!(#field_ident: val.#field_ident.into_iter().map(|v| v.into()).collect()));
-                            from_proto_fields.push(quote!(#field_ident: val.#field_ident.into_iter().map(|v| ::std::convert::TryFrom::try_from(v)).collect::<Result<_, _>>()?));
-                        } else {
-                            from_rust_fields.push(quote!(#field_ident: val.#field_ident.into()));
-                            from_proto_fields.push(quote!(#field_ident: val.#field_ident.into()));
+                }
+
+                impl ::std::convert::From<::schema::#ident> for #ident {
+                    fn from(val: ::schema::#ident) -> Self {
+                        let value = match val {
+                            #( #from_rust_arms, )*
+                        };
+
+                        Self {
+                            #module_ident: Some(value),
                         }
                     }
                 }
+            );
+
+            write!(code, "{}", expanded).unwrap();
+        }
+
+        for (model, protobuf_message) in self.protobuf_messages() {
+            let ident = format_ident!("{}", model.name);
+
+            let mut from_rust_fields = Vec::new();
+            let mut from_proto_fields = Vec::new();
+
+            for (field, protobuf_field) in model.fields.iter().zip(&protobuf_message.fields) {
+                let field_ident = format_ident!("{}", field.name);
+                let (from_rust, from_proto) = self.field_conversions(
+                    &field.name,
+                    &field.ty,
+                    &protobuf_field.ty,
+                    &quote!(val.#field_ident),
+                    &quote!(val.#field_ident),
+                );
+
+                from_rust_fields.push(quote!(#field_ident: #from_rust));
+                from_proto_fields.push(quote!(#field_ident: #from_proto));
             }
 
             let expanded = quote!(
@@ -258,7 +424,7 @@ impl ::std::fmt::Display for TryFromProtoError {{
                 }
             );
 
-            write!(code, "{}", expanded.to_string()).unwrap();
+            write!(code, "{}", expanded).unwrap();
         }
 
         for service in &self.services {
@@ -304,7 +470,7 @@ impl ::std::fmt::Display for TryFromProtoError {{
                 }
             );
 
-            write!(code, "{}", expanded.to_string()).unwrap();
+            write!(code, "{}", expanded).unwrap();
         }
 
         code.trim().to_string()
@@ -327,6 +493,262 @@ impl ::std::fmt::Display for TryFromProtoError {{
         })
     }
 
+    fn protobuf_enums(&self) -> Vec<(&Model, &ProtobufEnum)> {
+        self.models.iter().fold(Vec::new(), |mut acc, model| {
+            let roles = model
+                .roles
+                .iter()
+                .filter_map(|role| match role {
+                    Role::ProtobufEnum(protobuf_enum) => Some((model, protobuf_enum)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            acc.extend(roles);
+
+            acc
+        })
+    }
+
+    fn protobuf_oneofs(&self) -> Vec<(&Model, &ProtobufOneof)> {
+        self.models.iter().fold(Vec::new(), |mut acc, model| {
+            let roles = model
+                .roles
+                .iter()
+                .filter_map(|role| match role {
+                    Role::ProtobufOneof(protobuf_oneof) => Some((model, protobuf_oneof)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            acc.extend(roles);
+
+            acc
+        })
+    }
+
+    fn is_protobuf_enum(&self, ty: &str) -> bool {
+        self.protobuf_enums()
+            .iter()
+            .any(|(model, _)| model.name == ty)
+    }
+
+    fn is_protobuf_message(&self, ty: &str) -> bool {
+        self.protobuf_messages()
+            .iter()
+            .any(|(model, _)| model.name == ty)
+    }
+
+    fn is_protobuf_oneof(&self, ty: &str) -> bool {
+        self.protobuf_oneofs()
+            .iter()
+            .any(|(model, _)| model.name == ty)
+    }
+
+    /// Returns the expressions converting a message field from its schema value into its prost value,
+    /// and from its prost value into its schema value.
+    fn field_conversions(
+        &self,
+        name: &str,
+        ty: &str,
+        protobuf_type: &ProtobufType,
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        let inner_ty = strip_ty_option(ty);
+
+        if *protobuf_type == ProtobufType::Json {
+            let (from_rust, from_proto) = Self::json_conversions(name, &quote!(v), &quote!(v));
+
+            if is_ty_option(ty) {
+                (
+                    quote!(#rust_value.map(|v| #from_rust)),
+                    quote!(#proto_value.map(|v| #from_proto).transpose()?),
+                )
+            } else {
+                (
+                    quote!({ let v = #rust_value; #from_rust }),
+                    quote!({ let v = #proto_value; #from_proto? }),
+                )
+            }
+        } else if TIMESTAMP_TYPES.contains(&inner_ty) {
+            let (from_rust, from_proto) =
+                Self::timestamp_conversions(rust_value, &quote!(unwrapped_value));
+            (
+                quote!(Some(#from_rust)),
+                quote!({
+                    let unwrapped_value = #proto_value.ok_or_else(|| TryFromProtoError::MissingField(#name.to_string()))?;
+                    #from_proto
+                }),
+            )
+        } else if matches!(inner_ty, "uuid::Uuid" | "Uuid") {
+            (
+                quote!(#rust_value.to_string()),
+                quote!(::uuid::Uuid::parse_str(&#proto_value).map_err(|_| TryFromProtoError::InvalidUuid)?),
+            )
+        } else if self.is_protobuf_enum(strip_ty_vec(inner_ty)) {
+            // Enum fields are sent as their `i32` values
+            let (from_rust, from_proto) =
+                Self::enum_conversions(strip_ty_vec(inner_ty), &quote!(v), &quote!(v));
+            Self::fallible_field_conversions(ty, rust_value, proto_value, from_rust, from_proto)
+        } else if DECIMAL_TYPES.contains(&strip_ty_vec(inner_ty)) {
+            let (from_rust, from_proto) = Self::decimal_conversions(&quote!(v), &quote!(v));
+            Self::fallible_field_conversions(ty, rust_value, proto_value, from_rust, from_proto)
+        } else if is_ty_vec(inner_ty) {
+            (
+                quote!(#rust_value.into_iter().map(|v| v.into()).collect()),
+                quote!(#proto_value.into_iter().map(|v| ::std::convert::TryFrom::try_from(v)).collect::<Result<_, _>>()?),
+            )
+        } else if self.is_protobuf_oneof(inner_ty) {
+            // Message fields are always optional in prost
+            if is_ty_option(ty) {
+                (
+                    quote!(#rust_value.map(Into::into)),
+                    quote!(#proto_value.map(::std::convert::TryFrom::try_from).transpose()?),
+                )
+            } else {
+                (
+                    quote!(Some(#rust_value.into())),
+                    quote!(::std::convert::TryFrom::try_from(
+                        #proto_value.ok_or_else(|| TryFromProtoError::MissingField(#name.to_string()))?
+                    )?),
+                )
+            }
+        } else {
+            (quote!(#rust_value.into()), quote!(#proto_value.into()))
+        }
+    }
+
+    /// Returns the expressions converting the value of a tuple variant of a oneof into its prost value,
+    /// and from its prost value into its schema value.
+    ///
+    /// Unlike message fields, messages in a oneof aren't optional.
+    fn oneof_value_conversions(
+        &self,
+        name: &str,
+        ty: &str,
+        protobuf_type: &ProtobufType,
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        if *protobuf_type == ProtobufType::Json {
+            let (from_rust, from_proto) = Self::json_conversions(name, rust_value, proto_value);
+            (from_rust, quote!(#from_proto?))
+        } else if TIMESTAMP_TYPES.contains(&ty) {
+            Self::timestamp_conversions(rust_value, proto_value)
+        } else if matches!(ty, "uuid::Uuid" | "Uuid") {
+            (
+                quote!(#rust_value.to_string()),
+                quote!(::uuid::Uuid::parse_str(&#proto_value).map_err(|_| TryFromProtoError::InvalidUuid)?),
+            )
+        } else if self.is_protobuf_enum(ty) {
+            let (from_rust, from_proto) = Self::enum_conversions(ty, rust_value, proto_value);
+            (from_rust, quote!(#from_proto?))
+        } else if DECIMAL_TYPES.contains(&ty) {
+            let (from_rust, from_proto) = Self::decimal_conversions(rust_value, proto_value);
+            (from_rust, quote!(#from_proto?))
+        } else if self.is_protobuf_message(ty) || self.is_protobuf_oneof(ty) {
+            (
+                quote!(#rust_value.into()),
+                quote!(::std::convert::TryFrom::try_from(#proto_value)?),
+            )
+        } else {
+            (quote!(#rust_value.into()), quote!(#proto_value.into()))
+        }
+    }
+
+    fn timestamp_conversions(
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        (
+            quote!(::prost_types::Timestamp {
+                nanos: #rust_value.timestamp_subsec_nanos() as i32,
+                seconds: #rust_value.timestamp(),
+            }),
+            quote!(::chrono::DateTime::from_utc(
+                ::chrono::naive::NaiveDateTime::from_timestamp(
+                    #proto_value.seconds,
+                    #proto_value.nanos as u32
+                ),
+                ::chrono::FixedOffset::east(0),
+            )),
+        )
+    }
+
+    /// Wraps the conversions of a value `v` of a field, where the conversion from the prost value returns a `Result`,
+    /// for required, optional and repeated fields.
+    fn fallible_field_conversions(
+        ty: &str,
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+        from_rust: TokenStream,
+        from_proto: TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        if is_ty_vec(strip_ty_option(ty)) {
+            (
+                quote!(#rust_value.into_iter().map(|v| #from_rust).collect()),
+                quote!(#proto_value.into_iter().map(|v| #from_proto).collect::<Result<_, _>>()?),
+            )
+        } else if is_ty_option(ty) {
+            (
+                quote!(#rust_value.map(|v| #from_rust)),
+                quote!(#proto_value.map(|v| #from_proto).transpose()?),
+            )
+        } else {
+            (
+                quote!({ let v = #rust_value; #from_rust }),
+                quote!({ let v = #proto_value; #from_proto? }),
+            )
+        }
+    }
+
+    /// Decimals are sent as strings to keep their precision. The conversion from the prost value returns a `Result`.
+    fn decimal_conversions(
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        (
+            quote!(#rust_value.to_string()),
+            quote!(#proto_value.parse().map_err(|_| TryFromProtoError::InvalidDecimal)),
+        )
+    }
+
+    /// Json values are sent as strings. The conversion from the prost value returns a `Result`.
+    fn json_conversions(
+        name: &str,
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        let to_json_message = format!("failed to serialize field '{}' to json", name);
+
+        (
+            quote!(::serde_json::to_string(&#rust_value).expect(#to_json_message)),
+            quote!(
+                ::serde_json::from_str(&#proto_value)
+                    .map_err(|_| TryFromProtoError::InvalidJson(#name.to_string()))
+            ),
+        )
+    }
+
+    /// The conversion from the prost value returns a `Result`.
+    fn enum_conversions(
+        enum_ty: &str,
+        rust_value: &TokenStream,
+        proto_value: &TokenStream,
+    ) -> (TokenStream, TokenStream) {
+        let enum_ident = format_ident!("{}", enum_ty);
+
+        (
+            quote!(#enum_ident::from(#rust_value) as i32),
+            quote!(
+                #enum_ident::from_i32(#proto_value)
+                    .ok_or_else(|| TryFromProtoError::InvalidEnumValue(#enum_ty.to_string()))
+                    .and_then(::std::convert::TryFrom::try_from)
+            ),
+        )
+    }
+
     fn all_protobuf_messages(&self) -> Vec<&ProtobufMessage> {
         self.protobuf_messages()
             .iter()
@@ -346,11 +768,60 @@ impl ::std::fmt::Display for TryFromProtoError {{
         proto
     }
 
+    fn write_protobuf_enum(&self, protobuf_enum: &ProtobufEnum) -> String {
+        let mut proto = String::new();
+
+        // Values share a namespace with the enum's siblings, so they're prefixed with its name
+        let prefix = protobuf_enum.name.to_shouty_snake_case();
+
+        writeln!(proto, "enum {} {{", protobuf_enum.name).unwrap();
+        writeln!(proto, "  {}_UNSPECIFIED = 0;", prefix).unwrap();
+        for (i, value) in protobuf_enum.values.iter().enumerate() {
+            writeln!(proto, "  {}_{} = {};", prefix, value, i + 1).unwrap();
+        }
+        writeln!(proto, "}}").unwrap();
+
+        proto
+    }
+
+    fn write_protobuf_oneof(&self, oneof: &ProtobufOneof) -> String {
+        let mut proto = String::new();
+
+        writeln!(proto, "message {} {{", oneof.name).unwrap();
+
+        // Unit and struct variants are sent as messages nested in the oneof's message
+        for variant in oneof.variants.iter().filter(|variant| variant.nested) {
+            if let ProtobufType::Custom(message) = &variant.ty {
+                for line in self.write_protobuf_message(message).lines() {
+                    writeln!(proto, "  {}", line).unwrap();
+                }
+            }
+        }
+
+        writeln!(proto, "  oneof {} {{", oneof.name.to_snake_case()).unwrap();
+        for (i, variant) in oneof.variants.iter().enumerate() {
+            writeln!(
+                proto,
+                "    {ty} {name} = {num};",
+                ty = variant.ty,
+                name = variant.name,
+                num = i + 1
+            )
+            .unwrap();
+        }
+        writeln!(proto, "  }}").unwrap();
+
+        writeln!(proto, "}}").unwrap();
+
+        proto
+    }
+
     fn write_protobuf_message(&self, message: &ProtobufMessage) -> String {
         let mut proto = String::new();
 
         writeln!(proto, "message {} {{", message.name).unwrap();
 
+        // Field numbers follow the field order, so renaming a field keeps its number
         for (i, field) in message.fields.iter().enumerate() {
             writeln!(proto, "  {}", self.write_protobuf_field(field, i)).unwrap();
         }
@@ -408,3 +879,20 @@ impl ::std::fmt::Display for TryFromProtoError {{
         proto
     }
 }
+
+#[cfg(test)]
+mod test {
+    use awto::tests_cfg::*;
+
+    use super::*;
+
+    #[test]
+    fn timestamp_nanos() {
+        let compiler = ProtobufCompiler::new(MODELS.to_vec(), vec![]);
+        let code = compiler.compile_generated_code();
+
+        // Protobuf timestamps carry the seconds and the nanoseconds within that second
+        assert!(code.contains("nanos : val . created_at . timestamp_subsec_nanos () as i32"));
+        assert!(!code.contains("timestamp_nanos ()"));
+    }
+}
@@ -11,6 +11,16 @@ pub fn strip_ty_option(ty: &str) -> &str {
     ty
 }
 
+pub fn strip_ty_vec(ty: &str) -> &str {
+    for prefix in VEC_PREFIXES {
+        if ty.starts_with(prefix) {
+            return &ty[prefix.len()..(ty.len() - 1)];
+        }
+    }
+
+    ty
+}
+
 pub fn is_ty_option(ty: &str) -> bool {
     OPTION_PREFIXES.iter().any(|prefix| ty.starts_with(prefix))
 }
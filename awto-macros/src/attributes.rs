@@ -1,23 +1,175 @@
-use better_bae::FromAttributes;
+use better_bae::{FromAttributes, TryFromAttributes};
 use quote::ToTokens;
 use syn::spanned::Spanned;
 
-#[derive(Default, FromAttributes)]
-#[bae("awto")]
-pub struct RootAttrs {}
+/// Struct level `#[awto(...)]` attributes.
+///
+/// Unlike field attributes, `check(...)`, `index(...)` and `unique(...)` can be repeated, so these are parsed by hand
+/// and every `#[awto(...)]` attribute on the struct is merged.
+#[derive(Default)]
+pub struct RootAttrs {
+    pub checks: Vec<CheckAttrs>,
+    pub created_at: Option<syn::LitStr>,
+    pub indexes: Vec<IndexAttrs>,
+    pub renamed_from: Option<LitStrs>,
+    pub table: Option<syn::LitStr>,
+    pub timestamps: Option<syn::LitBool>,
+    pub uniques: Vec<UniqueAttrs>,
+    pub updated_at: Option<syn::LitStr>,
+}
+
+impl syn::parse::Parse for RootAttrs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut attrs = RootAttrs::default();
+
+        let content;
+        syn::parenthesized!(content in input);
+
+        while !content.is_empty() {
+            let ident = content.parse::<syn::Ident>()?;
+
+            match &*ident.to_string() {
+                "check" => attrs.checks.push(content.parse()?),
+                "created_at" => {
+                    content.parse::<syn::Token![=]>()?;
+                    attrs.created_at = Some(content.parse()?);
+                }
+                "index" => attrs.indexes.push(content.parse()?),
+                "renamed_from" => {
+                    content.parse::<syn::Token![=]>()?;
+                    attrs.renamed_from = Some(content.parse()?);
+                }
+                "table" => {
+                    content.parse::<syn::Token![=]>()?;
+                    attrs.table = Some(content.parse()?);
+                }
+                "timestamps" => {
+                    content.parse::<syn::Token![=]>()?;
+                    attrs.timestamps = Some(content.parse()?);
+                }
+                "unique" => attrs.uniques.push(content.parse()?),
+                "updated_at" => {
+                    content.parse::<syn::Token![=]>()?;
+                    attrs.updated_at = Some(content.parse()?);
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!(
+                            "`#[awto]` got unknown `{}` argument. Supported arguments are `check`, `created_at`, `index`, `renamed_from`, `table`, `timestamps`, `unique`, `updated_at`",
+                            other
+                        ),
+                    ));
+                }
+            }
+
+            content.parse::<syn::Token![,]>().ok();
+        }
+
+        Ok(attrs)
+    }
+}
+
+impl TryFromAttributes for RootAttrs {
+    fn attr_name() -> &'static str {
+        "awto"
+    }
+
+    fn try_from_attributes(attrs: &[syn::Attribute]) -> syn::Result<Option<Self>> {
+        let mut root_attrs: Option<RootAttrs> = None;
+
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("awto")) {
+            let parsed = syn::parse2::<RootAttrs>(attr.tokens.clone())?;
+            root_attrs = Some(match root_attrs {
+                Some(mut root_attrs) => {
+                    root_attrs.checks.extend(parsed.checks);
+                    root_attrs.created_at = parsed.created_at.or(root_attrs.created_at);
+                    root_attrs.indexes.extend(parsed.indexes);
+                    root_attrs.renamed_from = parsed.renamed_from.or(root_attrs.renamed_from);
+                    root_attrs.table = parsed.table.or(root_attrs.table);
+                    root_attrs.timestamps = parsed.timestamps.or(root_attrs.timestamps);
+                    root_attrs.uniques.extend(parsed.uniques);
+                    root_attrs.updated_at = parsed.updated_at.or(root_attrs.updated_at);
+                    root_attrs
+                }
+                None => parsed,
+            });
+        }
+
+        Ok(root_attrs)
+    }
+}
+
+/// A struct level check constraint, written as `check(name = "...", expr = "...")`.
+#[derive(FromAttributes)]
+#[bae("check")]
+pub struct CheckAttrs {
+    pub expr: syn::LitStr,
+    pub name: syn::LitStr,
+}
+
+/// A struct level index, written as `index(columns = ["a", "b"], ...)`.
+#[derive(FromAttributes)]
+#[bae("index")]
+pub struct IndexAttrs {
+    pub columns: LitStrs,
+    pub method: Option<syn::LitStr>,
+    pub name: Option<syn::LitStr>,
+    pub unique: Option<()>,
+}
+
+/// A struct level unique constraint across columns, written as `unique(columns = ["a", "b"], ...)`.
+#[derive(FromAttributes)]
+#[bae("unique")]
+pub struct UniqueAttrs {
+    pub columns: LitStrs,
+    pub name: Option<syn::LitStr>,
+}
 
 #[derive(Default, FromAttributes)]
 #[bae("awto")]
 pub struct ItemAttrs {
+    pub check: Option<syn::LitStr>,
     pub db_type: Option<syn::LitStr>,
     pub default: Option<syn::Lit>,
     pub default_raw: Option<syn::LitStr>,
+    pub identity: Option<()>,
+    pub index: Option<()>,
+    pub json: Option<()>,
     pub max_len: Option<syn::LitInt>,
+    pub precision: Option<syn::LitInt>,
+    pub primary_key: Option<()>,
     pub proto_type: Option<syn::LitStr>,
     pub references: Option<KeyVal<syn::Ident, syn::LitStr>>,
+    pub renamed_from: Option<LitStrs>,
+    pub scale: Option<syn::LitInt>,
+    pub serial: Option<()>,
     pub unique: Option<()>,
 }
 
+/// One or more string literals, written as either `"a"` or `["a", "b"]`.
+#[derive(Debug)]
+pub struct LitStrs(pub Vec<syn::LitStr>);
+
+impl syn::parse::Parse for LitStrs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(syn::LitStr) {
+            return Ok(LitStrs(vec![input.parse()?]));
+        }
+        if !lookahead.peek(syn::token::Bracket) {
+            return Err(lookahead.error());
+        }
+
+        let content;
+        syn::bracketed!(content in input);
+        let lits = content
+            .parse_terminated::<_, syn::Token![,]>(<syn::LitStr as syn::parse::Parse>::parse)?;
+
+        Ok(LitStrs(lits.into_iter().collect()))
+    }
+}
+
 #[derive(Debug)]
 pub struct KeyVal<K, V>(pub K, pub V);
 
@@ -49,7 +49,7 @@ impl ProtobufService {
                         format_ident!("{}_{}_ParamTypeValidator", ident, name);
                     validators.push(quote_spanned!(
                             param.span()=>
-                                #[allow(non_camel_case_types)]
+                                #[allow(dead_code, non_camel_case_types)]
                                 trait #param_type_validator_ident: awto::protobuf::IntoProtobufMessage {}
                                 impl #param_type_validator_ident for #param {}
                         ));
@@ -59,7 +59,7 @@ impl ProtobufService {
                         format_ident!("{}_{}_ReturnTypeValidator", ident, name);
                     validators.push(quote_spanned!(
                         returns.span()=>
-                            #[allow(non_camel_case_types)]
+                            #[allow(dead_code, non_camel_case_types)]
                             trait #return_type_validator_ident: awto::protobuf::IntoProtobufMessage {}
                             impl #return_type_validator_ident for #returns {}
                     ));
@@ -185,7 +185,7 @@ impl ProtobufService {
                                 let return_type_result_validator_ident = format_ident!("{}_{}_ReturnTypeResultValidator", self.ident, name);
                                 validators.push(quote_spanned!(
                                     second.span()=>
-                                        #[allow(non_camel_case_types)]
+                                        #[allow(dead_code, non_camel_case_types)]
                                         trait #return_type_result_validator_ident: ::std::convert::Into<::tonic::Status> {}
                                         impl #return_type_result_validator_ident for #second {}
                                 ));
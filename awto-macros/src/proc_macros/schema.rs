@@ -7,43 +7,130 @@ use syn::spanned::Spanned;
 use crate::{
     error::Error,
     proc_macros::schema::{
-        database_table::DatabaseTableModel, protobuf_message::ProtobufMessageModel,
+        database_enum::DatabaseEnumModel,
+        database_table::DatabaseTableModel,
+        protobuf_enum::ProtobufEnumModel,
+        protobuf_message::{ProtobufMessageModel, ProtobufTypes},
+        protobuf_oneof::ProtobufOneofModel,
     },
     util::ProcMacro,
 };
 
+mod database_enum;
 mod database_table;
+mod protobuf_enum;
 mod protobuf_message;
+mod protobuf_oneof;
 
-pub struct Structs(pub Vec<syn::ItemStruct>);
+pub struct SchemaItems {
+    pub structs: Vec<syn::ItemStruct>,
+    pub enums: Vec<syn::ItemEnum>,
+}
 
-impl syn::parse::Parse for Structs {
+impl syn::parse::Parse for SchemaItems {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut structs: Vec<syn::ItemStruct> = Vec::new();
+        let mut enums: Vec<syn::ItemEnum> = Vec::new();
         while !input.is_empty() {
-            structs.push(input.parse()?);
+            match input.parse()? {
+                syn::Item::Struct(item) => structs.push(item),
+                syn::Item::Enum(item) => enums.push(item),
+                item => return Err(syn::Error::new(item.span(), "expected a struct or enum")),
+            }
         }
 
-        Ok(Structs(structs))
+        Ok(SchemaItems { structs, enums })
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum Role {
+    DatabaseEnum,
     DatabaseTable,
     DatabaseSubTable(syn::Ident),
+    ProtobufEnum,
     ProtobufMessage,
+    ProtobufOneof,
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-struct RoleFromStrError;
+impl Role {
+    fn parse_roles(attrs: &[syn::Attribute]) -> syn::Result<Vec<Role>> {
+        attrs
+            .iter()
+            .filter_map(|attr| {
+                attr.parse_meta()
+                    .map(|meta| match &meta {
+                        syn::Meta::Path(path) => path.get_ident().and_then(|ident| {
+                            if ident == "database_enum" {
+                                Some(Ok(Role::DatabaseEnum))
+                            } else if ident == "database_table" {
+                                Some(Ok(Role::DatabaseTable))
+                            } else if ident == "protobuf_enum" {
+                                Some(Ok(Role::ProtobufEnum))
+                            } else if ident == "protobuf_message" {
+                                Some(Ok(Role::ProtobufMessage))
+                            } else if ident == "protobuf_oneof" {
+                                Some(Ok(Role::ProtobufOneof))
+                            } else {
+                                None
+                            }
+                        }),
+                        syn::Meta::List(list) => list.path.get_ident().and_then(|ident| {
+                            if ident == "database_sub_table" {
+                                Some(attr.parse_args::<syn::Ident>().map(Role::DatabaseSubTable))
+                            } else {
+                                None
+                            }
+                        }),
+                        _ => None,
+                    })
+                    .ok()
+                    .flatten()
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the role can only be used on enums.
+    fn is_enum_role(&self) -> bool {
+        matches!(
+            self,
+            Role::DatabaseEnum | Role::ProtobufEnum | Role::ProtobufOneof
+        )
+    }
+
+    fn is_role_attribute(attr: &syn::Attribute) -> bool {
+        attr.parse_meta()
+            .map(|meta| match meta {
+                syn::Meta::Path(path) => path
+                    .get_ident()
+                    .map(|ident| {
+                        ident == "database_enum"
+                            || ident == "database_table"
+                            || ident == "protobuf_enum"
+                            || ident == "protobuf_message"
+                            || ident == "protobuf_oneof"
+                    })
+                    .unwrap_or(false),
+                syn::Meta::List(list) => list
+                    .path
+                    .get_ident()
+                    .map(|ident| ident == "database_sub_table")
+                    .unwrap_or(false),
+                _ => false,
+            })
+            .unwrap_or(false)
+    }
+}
 
 impl fmt::Display for Role {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::DatabaseEnum => write!(f, "database_enum"),
             Self::DatabaseTable => write!(f, "database_table"),
             Self::DatabaseSubTable(_) => write!(f, "database_sub_table"),
+            Self::ProtobufEnum => write!(f, "protobuf_enum"),
             Self::ProtobufMessage => write!(f, "protobuf_message"),
+            Self::ProtobufOneof => write!(f, "protobuf_oneof"),
         }
     }
 }
@@ -53,48 +140,22 @@ struct Item {
     item: syn::ItemStruct,
 }
 
+struct EnumItem {
+    roles: Vec<Role>,
+    item: syn::ItemEnum,
+}
+
 pub struct Schema {
     items: Vec<Item>,
+    enums: Vec<EnumItem>,
 }
 
 impl Schema {
-    fn parse_input(input: Structs) -> syn::Result<Vec<Item>> {
+    fn parse_input(input: Vec<syn::ItemStruct>) -> syn::Result<Vec<Item>> {
         input
-            .0
             .into_iter()
             .map(|item| {
-                let roles: Vec<_> = item
-                    .attrs
-                    .iter()
-                    .filter_map(|attr| {
-                        attr.parse_meta()
-                            .map(|meta| match &meta {
-                                syn::Meta::Path(path) => {
-                                    path.get_ident().and_then(|ident| {
-                                        if ident == "database_table" {
-                                            Some(Ok(Role::DatabaseTable))
-                                        } else if ident == "protobuf_message" {
-                                            Some(Ok(Role::ProtobufMessage))
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                },
-                                syn::Meta::List(list) => {
-                                    list.path.get_ident().and_then(|ident|
-                                        if ident == "database_sub_table" {
-                                            Some(attr.parse_args::<syn::Ident>().map(Role::DatabaseSubTable))
-                                        } else {
-                                            None
-                                        }
-                                    )
-                                }
-                                _ => None,
-                            })
-                            .ok()
-                            .flatten()
-                    })
-                    .collect::<Result<_, _>>()?;
+                let roles = Role::parse_roles(&item.attrs)?;
                 if roles.is_empty() {
                     return Err(
                         syn::Error::new(
@@ -103,6 +164,12 @@ impl Schema {
                         )
                     );
                 }
+                if let Some(role) = roles.iter().find(|role| role.is_enum_role()) {
+                    return Err(syn::Error::new(
+                        item.ident.span(),
+                        format!("#[{}] can only be used on enums", role),
+                    ));
+                }
 
                 // Ensure all structs are public
                 if !matches!(item.vis, syn::Visibility::Public(_)) {
@@ -114,8 +181,64 @@ impl Schema {
             .collect()
     }
 
+    fn parse_enums(input: Vec<syn::ItemEnum>) -> syn::Result<Vec<EnumItem>> {
+        input
+            .into_iter()
+            .map(|item| {
+                let roles = Role::parse_roles(&item.attrs)?;
+                if roles.is_empty() {
+                    return Err(syn::Error::new(
+                        item.ident.span(),
+                        "enum must be marked with a role attribute\n\navailable attributes are #[database_enum], #[protobuf_enum], #[protobuf_oneof]",
+                    ));
+                }
+                if let Some(role) = roles.iter().find(|role| !role.is_enum_role()) {
+                    return Err(syn::Error::new(
+                        item.ident.span(),
+                        format!("#[{}] can only be used on structs", role),
+                    ));
+                }
+
+                // Ensure all enums are public
+                if !matches!(item.vis, syn::Visibility::Public(_)) {
+                    return Err(syn::Error::new(item.ident.span(), "enum must be public"));
+                }
+
+                Ok(EnumItem { roles, item })
+            })
+            .collect()
+    }
+
+    /// Returns the enums which can be used as the type of a database column.
+    fn database_enum_idents(&self) -> Vec<syn::Ident> {
+        self.enums
+            .iter()
+            .filter(|item| item.roles.contains(&Role::DatabaseEnum))
+            .map(|item| item.item.ident.clone())
+            .collect()
+    }
+
+    /// Returns the enums and oneofs which can be used as the type of a protobuf field.
+    fn protobuf_types(&self) -> ProtobufTypes {
+        let idents = |role| {
+            self.enums
+                .iter()
+                .filter(|item| item.roles.contains(&role))
+                .map(|item| item.item.ident.clone())
+                .collect()
+        };
+
+        ProtobufTypes {
+            enums: idents(Role::ProtobufEnum),
+            oneofs: idents(Role::ProtobufOneof),
+        }
+    }
+
     fn parse_models(&self) -> syn::Result<TokenStream> {
-        let models: Vec<_> = self
+        let database_enums = self.database_enum_idents();
+        let protobuf_types = self.protobuf_types();
+
+        let mut models: Vec<_> = self
             .items
             .iter()
             .map(|item| {
@@ -127,10 +250,13 @@ impl Schema {
                     .map(|role| {
                         let expanded = match role {
                             Role::DatabaseTable => {
-                                let database_table =
-                                    DatabaseTableModel::new(item.item.clone(), false)
-                                        .map_err(|err| err.into_syn_error(item.item.span()))?
-                                        .expand()?;
+                                let database_table = DatabaseTableModel::new(
+                                    item.item.clone(),
+                                    false,
+                                    database_enums.clone(),
+                                )
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
 
                                 quote!(awto::schema::Role::DatabaseTable(#database_table))
                             }
@@ -143,20 +269,29 @@ impl Schema {
                                         syn::Error::new(parent_ident.span(), "parent not found")
                                     })?;
 
-                                let database_table =
-                                    DatabaseTableModel::new(parent.item.clone(), false)
-                                        .map_err(|err| err.into_syn_error(item.item.span()))?
-                                        .expand()?;
+                                let database_table = DatabaseTableModel::new(
+                                    parent.item.clone(),
+                                    false,
+                                    database_enums.clone(),
+                                )
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
 
                                 quote!(awto::schema::Role::DatabaseSubTable(#database_table))
                             }
                             Role::ProtobufMessage => {
-                                let protobuf_message = ProtobufMessageModel::new(item.item.clone())
-                                    .map_err(|err| err.into_syn_error(item.item.span()))?
-                                    .expand()?;
+                                let protobuf_message = ProtobufMessageModel::new(
+                                    item.item.clone(),
+                                    protobuf_types.clone(),
+                                )
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
 
                                 quote!(awto::schema::Role::ProtobufMessage(#protobuf_message) )
                             }
+                            Role::DatabaseEnum | Role::ProtobufEnum | Role::ProtobufOneof => {
+                                unreachable!("structs can't have enum roles")
+                            }
                         };
 
                         Result::<_, syn::Error>::Ok(expanded)
@@ -181,12 +316,83 @@ impl Schema {
                         fields: vec![ #( #rust_fields ),* ],
                         name: #item_ident.to_string(),
                         roles: vec![ #( #roles ),* ],
+                        variants: vec![],
                     }
                 ))
             })
             .collect::<Result<_, _>>()?;
 
-        let item_count = self.items.len();
+        for item in &self.enums {
+            let item_ident = item.item.ident.to_string();
+
+            let roles = item
+                .roles
+                .iter()
+                .map(|role| match role {
+                    Role::DatabaseEnum => {
+                        let database_enum = DatabaseEnumModel::new(item.item.clone())
+                            .map_err(|err| err.into_syn_error(item.item.span()))?
+                            .expand()?;
+
+                        Ok(quote!(awto::schema::Role::DatabaseEnum(#database_enum)))
+                    }
+                    Role::ProtobufEnum => {
+                        let protobuf_enum = ProtobufEnumModel::new(item.item.clone())
+                            .map_err(|err| err.into_syn_error(item.item.span()))?
+                            .expand()?;
+
+                        Ok(quote!(awto::schema::Role::ProtobufEnum(#protobuf_enum)))
+                    }
+                    Role::ProtobufOneof => {
+                        let protobuf_oneof =
+                            ProtobufOneofModel::new(item.item.clone(), protobuf_types.clone())
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
+
+                        Ok(quote!(awto::schema::Role::ProtobufOneof(#protobuf_oneof)))
+                    }
+                    _ => unreachable!("enums can only have enum roles"),
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            let rust_variants = item.item.variants.iter().map(|variant| {
+                let variant_ident_string = variant.ident.to_string();
+                let rust_fields = variant.fields.iter().enumerate().map(|(i, field)| {
+                    let field_ident_string = field
+                        .ident
+                        .as_ref()
+                        .map(|ident| ident.to_string())
+                        .unwrap_or_else(|| i.to_string());
+                    let mut field_ty_string = field.ty.to_token_stream().to_string();
+                    field_ty_string.retain(|c| c != ' ');
+
+                    quote!(
+                        awto::schema::RustField {
+                            name: #field_ident_string.to_string(),
+                            ty: #field_ty_string.to_string(),
+                        }
+                    )
+                });
+
+                quote!(
+                    awto::schema::RustVariant {
+                        name: #variant_ident_string.to_string(),
+                        fields: vec![ #( #rust_fields ),* ],
+                    }
+                )
+            });
+
+            models.push(quote!(
+                awto::schema::Model {
+                    fields: vec![],
+                    name: #item_ident.to_string(),
+                    roles: vec![ #( #roles ),* ],
+                    variants: vec![ #( #rust_variants ),* ],
+                }
+            ));
+        }
+
+        let item_count = models.len();
 
         Ok(quote!(awto::lazy_static::lazy_static! {
             pub static ref MODELS: [awto::schema::Model; #item_count] = [
@@ -196,7 +402,10 @@ impl Schema {
     }
 
     fn impl_models(&self) -> syn::Result<TokenStream> {
-        let model_impls: Vec<_> = self
+        let database_enums = self.database_enum_idents();
+        let protobuf_types = self.protobuf_types();
+
+        let mut model_impls: Vec<_> = self
             .items
             .iter()
             .map(|item| {
@@ -208,10 +417,13 @@ impl Schema {
                     .map(|role| {
                         let expanded = match role {
                             Role::DatabaseTable => {
-                                let database_table =
-                                    DatabaseTableModel::new(item.item.clone(), false)
-                                        .map_err(|err| err.into_syn_error(item.item.span()))?
-                                        .expand()?;
+                                let database_table = DatabaseTableModel::new(
+                                    item.item.clone(),
+                                    false,
+                                    database_enums.clone(),
+                                )
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
 
                                 quote!(
                                     impl awto::database::IntoDatabaseTable for #item_ident {
@@ -221,13 +433,17 @@ impl Schema {
                                     }
                                 )
                             }
-                            Role::DatabaseSubTable(_) => {
-                                quote!()
-                            }
+                            Role::DatabaseEnum
+                            | Role::DatabaseSubTable(_)
+                            | Role::ProtobufEnum
+                            | Role::ProtobufOneof => quote!(),
                             Role::ProtobufMessage => {
-                                let protobuf_message = ProtobufMessageModel::new(item.item.clone())
-                                    .map_err(|err| err.into_syn_error(item.item.span()))?
-                                    .expand()?;
+                                let protobuf_message = ProtobufMessageModel::new(
+                                    item.item.clone(),
+                                    protobuf_types.clone(),
+                                )
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
 
                                 quote!(
                                     impl awto::protobuf::IntoProtobufMessage for #item_ident {
@@ -249,27 +465,64 @@ impl Schema {
             })
             .collect::<Result<_, _>>()?;
 
+        for item in &self.enums {
+            let item_ident = &item.item.ident;
+
+            for role in &item.roles {
+                match role {
+                    Role::DatabaseEnum => {
+                        let database_enum = DatabaseEnumModel::new(item.item.clone())
+                            .map_err(|err| err.into_syn_error(item.item.span()))?
+                            .expand()?;
+
+                        model_impls.push(quote!(
+                            impl awto::database::IntoDatabaseEnum for #item_ident {
+                                fn database_enum() -> awto::database::DatabaseEnum {
+                                    #database_enum
+                                }
+                            }
+                        ));
+                    }
+                    Role::ProtobufEnum => {
+                        let protobuf_enum = ProtobufEnumModel::new(item.item.clone())
+                            .map_err(|err| err.into_syn_error(item.item.span()))?
+                            .expand()?;
+
+                        model_impls.push(quote!(
+                            impl awto::protobuf::IntoProtobufEnum for #item_ident {
+                                fn protobuf_enum() -> awto::protobuf::ProtobufEnum {
+                                    #protobuf_enum
+                                }
+                            }
+                        ));
+                    }
+                    Role::ProtobufOneof => {
+                        let protobuf_oneof =
+                            ProtobufOneofModel::new(item.item.clone(), protobuf_types.clone())
+                                .map_err(|err| err.into_syn_error(item.item.span()))?
+                                .expand()?;
+
+                        model_impls.push(quote!(
+                            impl awto::protobuf::IntoProtobufOneof for #item_ident {
+                                fn protobuf_oneof() -> awto::protobuf::ProtobufOneof {
+                                    #protobuf_oneof
+                                }
+                            }
+                        ));
+                    }
+                    _ => unreachable!("enums can only have enum roles"),
+                }
+            }
+        }
+
         Ok(quote!(#( #model_impls )*))
     }
 
     fn strip_attributes(&mut self) {
         for item in &mut self.items {
             item.item.attrs.retain(|attr| {
-                !attr
-                    .parse_meta()
-                    .map(|meta| match meta {
-                        syn::Meta::Path(path) => path
-                            .get_ident()
-                            .map(|ident| ident == "database_table" || ident == "protobuf_message")
-                            .unwrap_or(false),
-                        syn::Meta::List(list) => list
-                            .path
-                            .get_ident()
-                            .map(|ident| ident == "database_sub_table")
-                            .unwrap_or(false),
-                        _ => false,
-                    })
-                    .unwrap_or(false)
+                // `#[awto(...)]` arguments aren't always valid meta, such as `columns = ["a", "b"]`
+                !attr.path.is_ident("awto") && !Role::is_role_attribute(attr)
             });
 
             for field in &mut item.item.fields {
@@ -283,16 +536,31 @@ impl Schema {
                 })
             }
         }
+
+        for item in &mut self.enums {
+            item.item
+                .attrs
+                .retain(|attr| !attr.path.is_ident("awto") && !Role::is_role_attribute(attr));
+
+            for variant in &mut item.item.variants {
+                variant.attrs.retain(|attr| !attr.path.is_ident("awto"));
+
+                for field in &mut variant.fields {
+                    field.attrs.retain(|attr| !attr.path.is_ident("awto"));
+                }
+            }
+        }
     }
 }
 
 impl ProcMacro for Schema {
-    type Input = Structs;
+    type Input = SchemaItems;
 
     fn new(input: Self::Input) -> Result<Self, Error> {
-        let items = Self::parse_input(input).map_err(Error::Syn)?;
+        let items = Self::parse_input(input.structs).map_err(Error::Syn)?;
+        let enums = Self::parse_enums(input.enums).map_err(Error::Syn)?;
 
-        Ok(Schema { items })
+        Ok(Schema { items, enums })
     }
 
     fn expand(mut self) -> syn::Result<TokenStream> {
@@ -302,7 +570,8 @@ impl ProcMacro for Schema {
         self.strip_attributes();
 
         let items = self.items.into_iter().map(|item| item.item);
-        let expanded_input = quote!(#( #items )*);
+        let enums = self.enums.into_iter().map(|item| item.item);
+        let expanded_input = quote!(#( #items )* #( #enums )*);
 
         Ok(TokenStream::from_iter([
             models_cosnt,
@@ -0,0 +1,57 @@
+use heck::SnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+use crate::error::Error;
+
+pub struct DatabaseEnumModel {
+    ident: syn::Ident,
+    variants: Vec<syn::Ident>,
+}
+
+impl DatabaseEnumModel {
+    pub fn new(item: syn::ItemEnum) -> Result<Self, Error> {
+        if item.variants.is_empty() {
+            return Err(Error::Syn(syn::Error::new(
+                item.ident.span(),
+                "database enums must have at least one variant",
+            )));
+        }
+
+        let variants = item
+            .variants
+            .into_iter()
+            .map(|variant| {
+                if !matches!(variant.fields, syn::Fields::Unit) {
+                    return Err(Error::Syn(syn::Error::new(
+                        variant.fields.span(),
+                        "database enum variants cannot have fields",
+                    )));
+                }
+
+                Ok(variant.ident)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(DatabaseEnumModel {
+            ident: item.ident,
+            variants,
+        })
+    }
+
+    pub fn expand(self) -> syn::Result<TokenStream> {
+        let name = self.ident.to_string().to_snake_case();
+        let values = self
+            .variants
+            .iter()
+            .map(|variant| variant.to_string().to_snake_case());
+
+        Ok(quote!(
+            awto::database::DatabaseEnum {
+                name: #name.to_string(),
+                values: vec![ #( #values.to_string(), )* ],
+            }
+        ))
+    }
+}
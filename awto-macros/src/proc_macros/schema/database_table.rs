@@ -1,22 +1,30 @@
+use better_bae::TryFromAttributes;
 use heck::SnakeCase;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::spanned::Spanned;
 
 use crate::{
-    attributes::ItemAttrs,
+    attributes::{ItemAttrs, RootAttrs},
     error::Error,
     util::{parse_fields, Field},
 };
 
 pub struct DatabaseTableModel {
+    attrs: RootAttrs,
+    enums: Vec<syn::Ident>,
     fields: Vec<Field<ItemAttrs>>,
     ident: syn::Ident,
     is_sub_model: bool,
 }
 
 impl DatabaseTableModel {
-    pub fn new(item: syn::ItemStruct, is_sub_model: bool) -> Result<Self, Error> {
+    /// Creates a model of a database table, where `enums` are the database enums which can be used as column types.
+    pub fn new(
+        item: syn::ItemStruct,
+        is_sub_model: bool,
+        enums: Vec<syn::Ident>,
+    ) -> Result<Self, Error> {
         let punctuated_fields = match item.fields {
             syn::Fields::Named(named) => named.named,
             _ => return Err(Error::FieldsNotNamed),
@@ -24,9 +32,15 @@ impl DatabaseTableModel {
 
         let fields = parse_fields::<ItemAttrs>(punctuated_fields)?;
 
+        let attrs = RootAttrs::try_from_attributes(&item.attrs)
+            .map_err(Error::Syn)?
+            .unwrap_or_default();
+
         let ident = item.ident;
 
         Ok(DatabaseTableModel {
+            attrs,
+            enums,
             fields,
             ident,
             is_sub_model,
@@ -42,34 +56,99 @@ impl DatabaseTableModel {
 
 impl DatabaseTableModel {
     fn expand_database_table(&self) -> syn::Result<TokenStream> {
-        let Self { fields, ident, .. } = self;
+        let Self {
+            attrs,
+            fields,
+            ident,
+            ..
+        } = self;
+
+        let table_name = match &attrs.table {
+            Some(table) => table.value(),
+            None => ident.to_string().to_snake_case(),
+        };
+        let table_renamed_from = attrs
+            .renamed_from
+            .as_ref()
+            .map(|renamed_from| renamed_from.0.iter().collect())
+            .unwrap_or_else(Vec::new);
+        if let Some(renamed_from) = table_renamed_from
+            .iter()
+            .find(|renamed_from| renamed_from.value() == table_name)
+        {
+            return Err(syn::Error::new(
+                renamed_from.span(),
+                "table cannot be renamed from its own name",
+            ));
+        }
 
-        let table_name = ident.to_string().to_snake_case();
+        // Timestamp columns default to `created_at` and `updated_at`, and can be renamed or left out
+        let timestamps = attrs
+            .timestamps
+            .as_ref()
+            .map(|timestamps| timestamps.value)
+            .unwrap_or(true);
+        let timestamp_columns = if timestamps {
+            let created_at = attrs
+                .created_at
+                .as_ref()
+                .map(|created_at| created_at.value())
+                .unwrap_or_else(|| "created_at".to_string());
+            let updated_at = attrs
+                .updated_at
+                .as_ref()
+                .map(|updated_at| updated_at.value())
+                .unwrap_or_else(|| "updated_at".to_string());
+            if created_at == updated_at {
+                let column = attrs.updated_at.as_ref().or(attrs.created_at.as_ref());
+                return Err(syn::Error::new(
+                    column
+                        .map(|column| column.span())
+                        .unwrap_or_else(|| ident.span()),
+                    "`created_at` and `updated_at` cannot be the same column",
+                ));
+            }
+            vec![created_at, updated_at]
+        } else {
+            if let Some(column) = attrs.created_at.as_ref().or(attrs.updated_at.as_ref()) {
+                return Err(syn::Error::new(
+                    column.span(),
+                    "timestamp columns cannot be named when `timestamps = false`",
+                ));
+            }
+            vec![]
+        };
+
+        // Fields marked as the primary key make up the primary key, otherwise `id` is the primary key
+        let has_primary_key_attrs = fields.iter().any(|field| field.attrs.primary_key.is_some());
+        let is_primary_key = |field: &Field<ItemAttrs>| {
+            field.attrs.primary_key.is_some()
+                || (!has_primary_key_attrs && field.field.ident.as_ref().unwrap() == "id")
+        };
+        let primary_key_len = fields.iter().filter(|field| is_primary_key(field)).count();
 
         if !self.is_sub_model {
-            macro_rules! check_field_exists {
-                ($field: literal, $ty: literal) => {
-                    if !fields
-                        .iter()
-                        .any(|field| field.field.ident.as_ref().unwrap() == $field)
-                    {
-                        return Err(syn::Error::new(
-                            ident.span(),
-                            concat!(
-                                "database models must have an `",
-                                $field,
-                                ": ",
-                                $ty,
-                                "` column"
-                            ),
-                        ));
-                    }
-                };
+            if primary_key_len == 0 {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "database models must have a primary key, either an `id` column or fields marked with `#[awto(primary_key)]`",
+                ));
             }
 
-            check_field_exists!("id", "Uuid");
-            check_field_exists!("created_at", "DateTime<FixedOffset>");
-            check_field_exists!("updated_at", "DateTime<FixedOffset>");
+            for timestamp_column in &timestamp_columns {
+                if !fields
+                    .iter()
+                    .any(|field| field.field.ident.as_ref().unwrap() == timestamp_column)
+                {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!(
+                            "database models must have a `{}: DateTime<FixedOffset>` column, or `#[awto(timestamps = false)]`",
+                            timestamp_column
+                        ),
+                    ));
+                }
+            }
         }
 
         let columns = fields
@@ -78,24 +157,20 @@ impl DatabaseTableModel {
                 let name = field.field.ident.as_ref().unwrap().to_string();
 
                 let field_str = field.field.ty.to_token_stream().to_string().replace(' ', "");
-                if name == "id" && field_str != "uuid::Uuid" && field_str != "Uuid" {
-                    return Err(syn::Error::new(field.field.ty.span(), "`id` must be of type `Uuid`"));
-                }
-                if name == "created_at"
-                    && field_str != "chrono::DateTime<chrono::FixedOffset>"
-                    && field_str != "chrono::DateTime<FixedOffset>"
-                    && field_str != "DateTime<chrono::FixedOffset>"
-                    && field_str != "DateTime<FixedOffset>"
-                {
-                    return Err(syn::Error::new(field.field.ty.span(), "`created_at` must be of type `DateTime<FixedOffset>`"));
-                }
-                if name == "updated_at"
+                let primary_key = is_primary_key(field);
+                // A sole `id: Uuid` primary key is generated by the database
+                let is_uuid_id = name == "id"
+                    && primary_key
+                    && primary_key_len == 1
+                    && (field_str == "uuid::Uuid" || field_str == "Uuid");
+                let is_timestamp = timestamp_columns.contains(&name);
+                if is_timestamp
                     && field_str != "chrono::DateTime<chrono::FixedOffset>"
                     && field_str != "chrono::DateTime<FixedOffset>"
                     && field_str != "DateTime<chrono::FixedOffset>"
                     && field_str != "DateTime<FixedOffset>"
                 {
-                    return Err(syn::Error::new(field.field.ty.span(), "`updated_at` must be of type `DateTime<FixedOffset>`"));
+                    return Err(syn::Error::new(field.field.ty.span(), format!("`{}` must be of type `DateTime<FixedOffset>`", name)));
                 }
 
                 let mut ty = if let Some(db_type) = &field.attrs.db_type {
@@ -104,8 +179,12 @@ impl DatabaseTableModel {
                     } else {
                         return Err(syn::Error::new(db_type.span(), "invalid db_type"));
                     }
+                } else if field.attrs.json.is_some() {
+                    quote!(awto::database::DatabaseType::Jsonb)
                 } else if let Some(db_type) = Self::rust_to_db_type(&field.field.ty) {
                     db_type
+                } else if let Some(db_type) = self.enum_to_db_type(&field.field.ty) {
+                    db_type
                 } else {
                     return Err(syn::Error::new(
                         field.field.ty.span(),
@@ -125,37 +204,77 @@ impl DatabaseTableModel {
                 } else if db_type_is_text {
                     ty = quote!(#ty(None));
                 }
+                let db_type_is_numeric = ty_string.ends_with("::Numeric") || ty_string.ends_with(":: Numeric");
+                if let Some(precision) = &field.attrs.precision {
+                    if !db_type_is_numeric {
+                        return Err(syn::Error::new(
+                            precision.span(),
+                            "precision can only be used on numeric types",
+                        ));
+                    }
+                    let scale = match &field.attrs.scale {
+                        Some(scale) => {
+                            if scale.base10_parse::<u16>()? > precision.base10_parse::<u16>()? {
+                                return Err(syn::Error::new(scale.span(), "scale cannot be greater than precision"));
+                            }
+                            quote!(#scale)
+                        }
+                        None => quote!(0),
+                    };
+                    ty = quote!(#ty(Some((#precision, #scale))));
+                } else if let Some(scale) = &field.attrs.scale {
+                    return Err(syn::Error::new(scale.span(), "scale can only be used with precision"));
+                } else if db_type_is_numeric {
+                    ty = quote!(#ty(None));
+                }
 
                 let nullable = Self::is_type_option(&field.field.ty);
-                if nullable && name == "id" {
-                    return Err(syn::Error::new(field.field.ty.span(), "`id` cannot be an Option"));
+                if nullable && primary_key {
+                    return Err(syn::Error::new(field.field.ty.span(), "primary key columns cannot be an Option"));
                 }
-                if nullable && name == "created_at" {
-                    return Err(syn::Error::new(field.field.ty.span(), "`created_at` cannot be an Option"));
+                if nullable && is_timestamp {
+                    return Err(syn::Error::new(field.field.ty.span(), format!("`{}` cannot be an Option", name)));
                 }
-                if nullable && name == "updated_at" {
-                    return Err(syn::Error::new(field.field.ty.span(), "`updated_at` cannot be an Option"));
+
+                let generated = match (&field.attrs.serial, &field.attrs.identity) {
+                    (Some(_), Some(_)) => {
+                        return Err(syn::Error::new(field.field.ty.span(), "serial and identity cannot be used together"));
+                    }
+                    (Some(_), None) => Some(quote!(Serial)),
+                    (None, Some(_)) => Some(quote!(Identity)),
+                    (None, None) => None,
+                };
+                if generated.is_some() {
+                    let db_type_is_integer = ["::SmallInt", "::Integer", "::BigInt"]
+                        .iter()
+                        .any(|integer| ty_string.replace(' ', "").ends_with(integer));
+                    if !db_type_is_integer {
+                        return Err(syn::Error::new(field.field.ty.span(), "serial and identity can only be used on integer types"));
+                    }
+                    if nullable {
+                        return Err(syn::Error::new(field.field.ty.span(), "serial and identity columns cannot be an Option"));
+                    }
                 }
 
-                let verify_id_created_at_updated_at_custom_default = || {
-                    if name == "id" {
-                        return Err(syn::Error::new(field.field.ty.span(), "`id` cannot have a custom default"));
+                let verify_no_custom_default = || {
+                    if generated.is_some() {
+                        return Err(syn::Error::new(field.field.ty.span(), "serial and identity columns cannot have a custom default"));
                     }
-                    if name == "created_at" {
-                        return Err(syn::Error::new(field.field.ty.span(), "`created_at` cannot have a custom default"));
+                    if is_uuid_id {
+                        return Err(syn::Error::new(field.field.ty.span(), "`id` cannot have a custom default"));
                     }
-                    if name == "updated_at" {
-                        return Err(syn::Error::new(field.field.ty.span(), "`updated_at` cannot have a custom default"));
+                    if is_timestamp {
+                        return Err(syn::Error::new(field.field.ty.span(), format!("`{}` cannot have a custom default", name)));
                     }
                     Ok(())
                 };
 
                 let mut default = if let Some(default_raw) = &field.attrs.default_raw {
-                    verify_id_created_at_updated_at_custom_default()?;
+                    verify_no_custom_default()?;
 
                     quote!(Some(awto::database::DatabaseDefault::Raw(#default_raw.to_string())))
                 } else if let Some(default) = &field.attrs.default {
-                    verify_id_created_at_updated_at_custom_default()?;
+                    verify_no_custom_default()?;
 
                     if let Some(db_default) = Self::lit_to_db_default(default) {
                         quote!(Some(#db_default))
@@ -168,32 +287,28 @@ impl DatabaseTableModel {
                 } else {
                     quote!(None)
                 };
-                if name == "id" {
+                if let Some(generated) = &generated {
+                    default = quote!(Some(awto::database::DatabaseDefault::#generated))
+                } else if is_uuid_id {
                     default = quote!(Some(awto::database::DatabaseDefault::Raw("uuid_generate_v4()".to_string())))
-                } else if name == "created_at" || name == "updated_at" {
+                } else if is_timestamp {
                     default = quote!(Some(awto::database::DatabaseDefault::Raw("NOW()".to_string())))
                 }
 
                 let unique = field.attrs.unique.is_some();
-                if unique && name == "id" {
+                if unique && is_uuid_id {
                     return Err(syn::Error::new(field.field.ty.span(), "`id` cannot be marked as unique"));
                 }
-                if unique && name == "created_at" {
-                    return Err(syn::Error::new(field.field.ty.span(), "`created_at` cannot be marked as unique"));
-                }
-                if unique && name == "updated_at" {
-                    return Err(syn::Error::new(field.field.ty.span(), "`updated_at` cannot be marked as unique"));
+                if unique && is_timestamp {
+                    return Err(syn::Error::new(field.field.ty.span(), format!("`{}` cannot be marked as unique", name)));
                 }
 
                 let references = if let Some(references) = &field.attrs.references {
-                    if name == "id" {
+                    if is_uuid_id {
                         return Err(syn::Error::new(field.field.ty.span(), "`id` cannot reference another table"));
                     }
-                    if name == "created_at" {
-                        return Err(syn::Error::new(field.field.ty.span(), "`created_at` cannot reference another table"));
-                    }
-                    if name == "updated_at" {
-                        return Err(syn::Error::new(field.field.ty.span(), "`updated_at` cannot reference another table"));
+                    if is_timestamp {
+                        return Err(syn::Error::new(field.field.ty.span(), format!("`{}` cannot reference another table", name)));
                     }
 
                     let references_table = &references.0;
@@ -201,7 +316,7 @@ impl DatabaseTableModel {
                     let references_column = references.1.value();
 
                     quote!({
-                        if !<#references_table as awto::database::IntoDatabaseMessage>::database_message()
+                        if !<#references_table as awto::database::IntoDatabaseTable>::database_table()
                             .columns
                             .iter()
                             .any(|column| column.name == #references_column)
@@ -217,7 +332,7 @@ impl DatabaseTableModel {
                         }
 
                         Some((
-                            <#references_table as awto::database::IntoDatabaseMessage>::database_message().name,
+                            <#references_table as awto::database::IntoDatabaseTable>::database_table().name,
                             #references_column.to_string(),
                         ))
                     })
@@ -225,7 +340,27 @@ impl DatabaseTableModel {
                     quote!(None)
                 };
 
-                let primary_key = name == "id";
+                let renamed_from = field
+                    .attrs
+                    .renamed_from
+                    .as_ref()
+                    .map(|renamed_from| renamed_from.0.iter().collect())
+                    .unwrap_or_else(Vec::new);
+                if let Some(renamed_from) = renamed_from.iter().find(|renamed_from| renamed_from.value() == name) {
+                    return Err(syn::Error::new(renamed_from.span(), "column cannot be renamed from its own name"));
+                }
+
+                if field.attrs.index.is_some() && primary_key && primary_key_len == 1 {
+                    return Err(syn::Error::new(field.field.ty.span(), "the primary key is already indexed"));
+                }
+
+                let constraint = match &field.attrs.check {
+                    Some(check) => quote!(Some(#check.to_string())),
+                    None => quote!(None),
+                };
+
+                // The database sets `updated_at` whenever a row is updated
+                let on_update_now = timestamp_columns.get(1) == Some(&name);
 
                 Ok(quote!(
                     awto::database::DatabaseColumn {
@@ -233,23 +368,179 @@ impl DatabaseTableModel {
                         ty: #ty,
                         nullable: #nullable,
                         default: #default,
+                        on_update_now: #on_update_now,
                         unique: #unique,
-                        constraint: None,
+                        constraint: #constraint,
                         primary_key: #primary_key,
                         references: #references,
+                        renamed_from: vec![ #( #renamed_from.to_string(), )* ],
                     }
                 ))
             })
             .collect::<Result<Vec<_>, _>>()?;
 
+        let column_names: Vec<_> = fields
+            .iter()
+            .map(|field| field.field.ident.as_ref().unwrap().to_string())
+            .collect();
+
+        let mut indexes = Vec::new();
+        for (field, name) in fields.iter().zip(&column_names) {
+            if field.attrs.index.is_some() {
+                let index_columns = [name.clone()];
+                indexes.push(Self::expand_index(
+                    &Self::default_index_name(&table_name, &index_columns),
+                    &index_columns,
+                    false,
+                    quote!(BTree),
+                ));
+            }
+        }
+        for index in &attrs.indexes {
+            let index_columns: Vec<_> = index
+                .columns
+                .0
+                .iter()
+                .map(|column| column.value())
+                .collect();
+            if index_columns.is_empty() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "index must have at least one column",
+                ));
+            }
+            if let Some(column) = index
+                .columns
+                .0
+                .iter()
+                .find(|column| !column_names.contains(&column.value()))
+            {
+                return Err(syn::Error::new(column.span(), "column does not exist"));
+            }
+
+            let index_name = match &index.name {
+                Some(name) => name.value(),
+                None => Self::default_index_name(&table_name, &index_columns),
+            };
+            let method = match &index.method {
+                Some(method) => match Self::index_method(&method.value()) {
+                    Some(method) => method,
+                    None => {
+                        return Err(syn::Error::new(
+                            method.span(),
+                            "unknown index method, expected one of btree, hash, gist, spgist, gin, brin",
+                        ))
+                    }
+                },
+                None => quote!(BTree),
+            };
+
+            indexes.push(Self::expand_index(
+                &index_name,
+                &index_columns,
+                index.unique.is_some(),
+                method,
+            ));
+        }
+
+        let mut constraints = Vec::new();
+        for unique in &attrs.uniques {
+            let unique_columns: Vec<_> = unique
+                .columns
+                .0
+                .iter()
+                .map(|column| column.value())
+                .collect();
+            if unique_columns.len() < 2 {
+                return Err(syn::Error::new(
+                    unique.columns.0.first().map(|column| column.span()).unwrap_or_else(|| ident.span()),
+                    "unique constraint must have at least two columns, use #[awto(unique)] on the field for a single column",
+                ));
+            }
+            if let Some(column) = unique
+                .columns
+                .0
+                .iter()
+                .find(|column| !column_names.contains(&column.value()))
+            {
+                return Err(syn::Error::new(column.span(), "column does not exist"));
+            }
+
+            let constraint_name = match &unique.name {
+                Some(name) => name.value(),
+                None => Self::default_unique_name(&table_name, &unique_columns),
+            };
+
+            constraints.push(quote!(
+                awto::database::DatabaseConstraint {
+                    name: #constraint_name.to_string(),
+                    kind: awto::database::ConstraintKind::Unique(vec![ #( #unique_columns.to_string(), )* ]),
+                }
+            ));
+        }
+
+        for check in &attrs.checks {
+            let check_name = check.name.value();
+            let expr = &check.expr;
+
+            constraints.push(quote!(
+                awto::database::DatabaseConstraint {
+                    name: #check_name.to_string(),
+                    kind: awto::database::ConstraintKind::Check(#expr.to_string()),
+                }
+            ));
+        }
+
         Ok(quote!(
             awto::database::DatabaseTable {
                 name: #table_name.to_string(),
                 columns: vec![ #( #columns, )* ],
+                indexes: vec![ #( #indexes, )* ],
+                constraints: vec![ #( #constraints, )* ],
+                renamed_from: vec![ #( #table_renamed_from.to_string(), )* ],
             }
         ))
     }
 
+    /// Same as `awto::database::DatabaseIndex::default_name`.
+    fn default_index_name(table: &str, columns: &[String]) -> String {
+        format!("{}_{}_idx", table, columns.join("_"))
+    }
+
+    /// Same as `awto::database::DatabaseConstraint::default_unique_name`.
+    fn default_unique_name(table: &str, columns: &[String]) -> String {
+        format!("{}_{}_key", table, columns.join("_"))
+    }
+
+    fn expand_index(
+        name: &str,
+        columns: &[String],
+        unique: bool,
+        method: TokenStream,
+    ) -> TokenStream {
+        quote!(
+            awto::database::DatabaseIndex {
+                name: #name.to_string(),
+                columns: vec![ #( #columns.to_string(), )* ],
+                unique: #unique,
+                method: awto::database::IndexMethod::#method,
+            }
+        )
+    }
+
+    fn index_method(method: &str) -> Option<TokenStream> {
+        let method = match method {
+            "btree" => quote!(BTree),
+            "hash" => quote!(Hash),
+            "gist" => quote!(Gist),
+            "spgist" => quote!(SpGist),
+            "gin" => quote!(Gin),
+            "brin" => quote!(Brin),
+            _ => return None,
+        };
+        Some(method)
+    }
+
     fn is_type_option(ty: &syn::Type) -> bool {
         match ty {
             syn::Type::Path(path) => path
@@ -275,6 +566,22 @@ impl DatabaseTableModel {
         Some(db_default)
     }
 
+    /// Returns the type of a column holding one of the database enums, named by the enum itself.
+    fn enum_to_db_type(&self, ty: &syn::Type) -> Option<TokenStream> {
+        let ty_string = quote!(#ty).to_string().replace(' ', "");
+        let ty_str = if ty_string.starts_with("Option<") {
+            &ty_string[7..(ty_string.len() - 1)]
+        } else {
+            ty_string.as_str()
+        };
+
+        let enum_ident = self.enums.iter().find(|ident| *ident == ty_str)?;
+
+        Some(quote!(awto::database::DatabaseType::Enum(
+            <#enum_ident as awto::database::IntoDatabaseEnum>::database_enum().name
+        )))
+    }
+
     fn rust_to_db_type(ty: &syn::Type) -> Option<TokenStream> {
         let ty_string = match ty {
             syn::Type::Reference(reference) => {
@@ -291,6 +598,11 @@ impl DatabaseTableModel {
             ty_string.as_str()
         };
 
+        Self::rust_str_to_db_type(ty_str)
+            .map(|db_type| quote!(awto::database::DatabaseType::#db_type))
+    }
+
+    fn rust_str_to_db_type(ty_str: &str) -> Option<TokenStream> {
         let db_type = match ty_str {
             // Numeric types
             "i16" => quote!(SmallInt),
@@ -298,6 +610,7 @@ impl DatabaseTableModel {
             "i64" => quote!(BigInt),
             "f32" => quote!(Float),
             "f64" => quote!(Double),
+            "rust_decimal::Decimal" | "Decimal" => quote!(Numeric),
 
             // Character types
             "String" => quote!(Text),
@@ -320,9 +633,26 @@ impl DatabaseTableModel {
             // Uuid type
             "uuid::Uuid" | "Uuid" => quote!(Uuid),
 
-            _ => return None,
+            // Json types
+            "serde_json::Value" => quote!(Jsonb),
+
+            // Array types
+            _ => {
+                let inner_ty = ty_str.strip_prefix("Vec<")?.strip_suffix('>')?;
+                // Postgres doesn't distinguish the dimensions of arrays, so they can't be nested
+                if inner_ty.starts_with("Vec<") && inner_ty != "Vec<u8>" {
+                    return None;
+                }
+
+                let inner_db_type = match Self::rust_str_to_db_type(inner_ty)? {
+                    db_type if db_type.to_string() == "Text" => quote!(Text(None)),
+                    db_type if db_type.to_string() == "Numeric" => quote!(Numeric(None)),
+                    db_type => db_type,
+                };
+                quote!(Array(::std::boxed::Box::new(awto::database::DatabaseType::#inner_db_type)))
+            }
         };
 
-        Some(quote!(awto::database::DatabaseType::#db_type))
+        Some(db_type)
     }
 }
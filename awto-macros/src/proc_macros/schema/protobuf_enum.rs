@@ -0,0 +1,64 @@
+use heck::ShoutySnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+use crate::error::Error;
+
+pub struct ProtobufEnumModel {
+    ident: syn::Ident,
+    variants: Vec<syn::Ident>,
+}
+
+impl ProtobufEnumModel {
+    pub fn new(item: syn::ItemEnum) -> Result<Self, Error> {
+        if item.variants.is_empty() {
+            return Err(Error::Syn(syn::Error::new(
+                item.ident.span(),
+                "protobuf enums must have at least one variant",
+            )));
+        }
+
+        let variants = item
+            .variants
+            .into_iter()
+            .map(|variant| {
+                if !matches!(variant.fields, syn::Fields::Unit) {
+                    return Err(Error::Syn(syn::Error::new(
+                        variant.fields.span(),
+                        "protobuf enum variants cannot have fields",
+                    )));
+                }
+                // The zero value of every protobuf enum is `UNSPECIFIED`
+                if variant.ident.to_string().to_shouty_snake_case() == "UNSPECIFIED" {
+                    return Err(Error::Syn(syn::Error::new(
+                        variant.ident.span(),
+                        "`Unspecified` is reserved for the zero value of protobuf enums",
+                    )));
+                }
+
+                Ok(variant.ident)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ProtobufEnumModel {
+            ident: item.ident,
+            variants,
+        })
+    }
+
+    pub fn expand(self) -> syn::Result<TokenStream> {
+        let name = self.ident.to_string();
+        let values = self
+            .variants
+            .iter()
+            .map(|variant| variant.to_string().to_shouty_snake_case());
+
+        Ok(quote!(
+            awto::protobuf::ProtobufEnum {
+                name: #name.to_string(),
+                values: vec![ #( #values.to_string(), )* ],
+            }
+        ))
+    }
+}
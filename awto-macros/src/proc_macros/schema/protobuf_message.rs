@@ -8,23 +8,42 @@ use crate::{
     util::{parse_fields, Field},
 };
 
+/// Protobuf enums and oneofs of the schema, which can be used as field types.
+#[derive(Clone, Default)]
+pub struct ProtobufTypes {
+    pub enums: Vec<syn::Ident>,
+    pub oneofs: Vec<syn::Ident>,
+}
+
 pub struct ProtobufMessageModel {
     fields: Vec<Field<ItemAttrs>>,
     ident: syn::Ident,
+    types: ProtobufTypes,
 }
 
 impl ProtobufMessageModel {
-    pub fn new(item: syn::ItemStruct) -> Result<Self, Error> {
+    pub fn new(item: syn::ItemStruct, types: ProtobufTypes) -> Result<Self, Error> {
         let punctuated_fields = match item.fields {
             syn::Fields::Named(named) => named.named,
             _ => return Err(Error::FieldsNotNamed),
         };
 
-        let fields = parse_fields::<ItemAttrs>(punctuated_fields)?;
-
-        let ident = item.ident;
+        Self::from_fields(item.ident, punctuated_fields, types)
+    }
 
-        Ok(ProtobufMessageModel { fields, ident })
+    /// Creates a model of a message from named fields, such as the fields of an enum variant.
+    pub fn from_fields(
+        ident: syn::Ident,
+        fields: syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+        types: ProtobufTypes,
+    ) -> Result<Self, Error> {
+        let fields = parse_fields::<ItemAttrs>(fields)?;
+
+        Ok(ProtobufMessageModel {
+            fields,
+            ident,
+            types,
+        })
     }
 
     pub fn expand(self) -> syn::Result<TokenStream> {
@@ -36,7 +55,7 @@ impl ProtobufMessageModel {
 
 impl ProtobufMessageModel {
     fn expand_protobuf_message(&self) -> syn::Result<TokenStream> {
-        let Self { fields, ident } = self;
+        let Self { fields, ident, .. } = self;
 
         let name = ident.to_string();
 
@@ -50,7 +69,9 @@ impl ProtobufMessageModel {
                     } else {
                         return Err(syn::Error::new(proto_type.span(), "invalid proto_type"));
                     }
-                } else if let Some(proto_type) = Self::rust_to_proto_type(&field.field.ty) {
+                } else if field.attrs.json.is_some() {
+                    quote!(awto::protobuf::ProtobufType::Json)
+                } else if let Some(proto_type) = self.types.rust_to_proto_type(&field.field.ty) {
                     proto_type
                 } else {
                     return Err(syn::Error::new(
@@ -58,7 +79,7 @@ impl ProtobufMessageModel {
                         "type is not suppoerted",
                     ));
                 };
-                let required = !Self::is_type_option(&field.field.ty);
+                let required = !is_type_option(&field.field.ty);
 
                 Ok(quote!(
                     awto::protobuf::ProtobufField {
@@ -77,20 +98,22 @@ impl ProtobufMessageModel {
             }
         ))
     }
+}
 
-    fn is_type_option(ty: &syn::Type) -> bool {
-        match ty {
-            syn::Type::Path(path) => path
-                .path
-                .segments
-                .first()
-                .map(|segment| segment.ident == "Option")
-                .unwrap_or(false),
-            _ => false,
-        }
+pub fn is_type_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => path
+            .path
+            .segments
+            .first()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
     }
+}
 
-    fn rust_to_proto_type(ty: &syn::Type) -> Option<TokenStream> {
+impl ProtobufTypes {
+    pub fn rust_to_proto_type(&self, ty: &syn::Type) -> Option<TokenStream> {
         let ty_string = match ty {
             syn::Type::Reference(reference) => {
                 let mut reference = reference.clone();
@@ -106,11 +129,11 @@ impl ProtobufMessageModel {
             ty_string.as_str()
         };
 
-        Self::rust_str_to_proto_type(ty_str)
+        self.rust_str_to_proto_type(ty_str)
             .map(|protobuf_type| quote!(awto::protobuf::ProtobufType::#protobuf_type))
     }
 
-    fn rust_str_to_proto_type(ty_str: &str) -> Option<TokenStream> {
+    fn rust_str_to_proto_type(&self, ty_str: &str) -> Option<TokenStream> {
         let protobuf_type = match ty_str {
             "f64" => quote!(Double),
             "f32" => quote!(Float),
@@ -130,11 +153,18 @@ impl ProtobufMessageModel {
                 quote!(Timestamp)
             }
             "uuid::Uuid" | "Uuid" => quote!(String),
+            "serde_json::Value" => quote!(Json),
+            "rust_decimal::Decimal" | "Decimal" => quote!(String),
             _ => {
                 if ty_str.starts_with("Vec<") {
-                    Self::rust_str_to_proto_type(&ty_str[4..(ty_str.len() - 1)]).map(|inner_ty| {
+                    self.rust_str_to_proto_type(&ty_str[4..(ty_str.len() - 1)]).map(|inner_ty| {
                         quote!(Repeated(::std::boxed::Box::new(awto::protobuf::ProtobufType::#inner_ty)))
                     })?
+                } else if let Some(enum_ident) = self.enums.iter().find(|ident| *ident == ty_str) {
+                    quote!(Enum(<#enum_ident as awto::protobuf::IntoProtobufEnum>::protobuf_enum()))
+                } else if let Some(oneof_ident) = self.oneofs.iter().find(|ident| *ident == ty_str)
+                {
+                    quote!(Oneof(<#oneof_ident as awto::protobuf::IntoProtobufOneof>::protobuf_oneof()))
                 } else {
                     let ty_parsed = format_ident!("{}", ty_str);
                     quote!(Custom(<#ty_parsed as awto::protobuf::IntoProtobufMessage>::protobuf_message()))
@@ -0,0 +1,130 @@
+use better_bae::TryFromAttributes;
+use heck::SnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+use super::protobuf_message::{is_type_option, ProtobufMessageModel, ProtobufTypes};
+use crate::{attributes::ItemAttrs, error::Error};
+
+enum VariantModel {
+    /// A unit or struct variant, sent as a message nested in the oneof's message
+    Message(syn::Ident, ProtobufMessageModel),
+    /// A tuple variant, sent as the protobuf type of its single field
+    Value(syn::Ident, TokenStream),
+}
+
+pub struct ProtobufOneofModel {
+    ident: syn::Ident,
+    variants: Vec<VariantModel>,
+}
+
+impl ProtobufOneofModel {
+    pub fn new(item: syn::ItemEnum, types: ProtobufTypes) -> Result<Self, Error> {
+        if item.variants.is_empty() {
+            return Err(Error::Syn(syn::Error::new(
+                item.ident.span(),
+                "protobuf oneofs must have at least one variant",
+            )));
+        }
+
+        let variants = item
+            .variants
+            .into_iter()
+            .map(|variant| match variant.fields {
+                syn::Fields::Named(named) => Ok(VariantModel::Message(
+                    variant.ident.clone(),
+                    ProtobufMessageModel::from_fields(variant.ident, named.named, types.clone())?,
+                )),
+                syn::Fields::Unit => Ok(VariantModel::Message(
+                    variant.ident.clone(),
+                    ProtobufMessageModel::from_fields(
+                        variant.ident,
+                        syn::punctuated::Punctuated::new(),
+                        types.clone(),
+                    )?,
+                )),
+                syn::Fields::Unnamed(unnamed) => {
+                    if unnamed.unnamed.len() != 1 {
+                        return Err(Error::Syn(syn::Error::new(
+                            unnamed.span(),
+                            "tuple variants of protobuf oneofs must have exactly one field",
+                        )));
+                    }
+
+                    let field = &unnamed.unnamed[0];
+                    let ty = &field.ty;
+                    let attrs = ItemAttrs::try_from_attributes(&field.attrs)
+                        .map_err(Error::Syn)?
+                        .unwrap_or_default();
+                    if attrs.json.is_some() {
+                        return Ok(VariantModel::Value(
+                            variant.ident,
+                            quote!(awto::protobuf::ProtobufType::Json),
+                        ));
+                    }
+
+                    let mut ty_string = quote!(#ty).to_string();
+                    ty_string.retain(|c| c != ' ');
+                    // Fields of a oneof can't be optional or repeated
+                    if is_type_option(ty) || (ty_string.starts_with("Vec<") && ty_string != "Vec<u8>")
+                    {
+                        return Err(Error::Syn(syn::Error::new(
+                            ty.span(),
+                            "tuple variants of protobuf oneofs can't be optional or repeated, use a struct variant instead",
+                        )));
+                    }
+
+                    let proto_type = types.rust_to_proto_type(ty).ok_or_else(|| {
+                        Error::Syn(syn::Error::new(ty.span(), "type is not suppoerted"))
+                    })?;
+
+                    Ok(VariantModel::Value(variant.ident, proto_type))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ProtobufOneofModel {
+            ident: item.ident,
+            variants,
+        })
+    }
+
+    pub fn expand(self) -> syn::Result<TokenStream> {
+        let name = self.ident.to_string();
+
+        let variants = self
+            .variants
+            .into_iter()
+            .map(|variant| {
+                let (ident, ty, nested) = match variant {
+                    VariantModel::Message(ident, message) => {
+                        let message = message.expand()?;
+                        (
+                            ident,
+                            quote!(awto::protobuf::ProtobufType::Custom(#message)),
+                            true,
+                        )
+                    }
+                    VariantModel::Value(ident, ty) => (ident, ty, false),
+                };
+                let name = ident.to_string().to_snake_case();
+
+                Ok(quote!(
+                    awto::protobuf::ProtobufOneofVariant {
+                        name: #name.to_string(),
+                        ty: #ty,
+                        nested: #nested,
+                    }
+                ))
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(quote!(
+            awto::protobuf::ProtobufOneof {
+                name: #name.to_string(),
+                variants: vec![ #( #variants, )* ],
+            }
+        ))
+    }
+}
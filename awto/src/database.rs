@@ -18,6 +18,62 @@ pub enum DatabaseType {
     Timetz,
     Bool,
     Uuid,
+    Json,
+    Jsonb,
+    /// A Postgres enum type, by name.
+    Enum(String),
+    /// An array of a scalar type, such as `bigint[]`.
+    ///
+    /// Postgres doesn't distinguish the dimensions of arrays, so arrays can't be nested.
+    Array(Box<DatabaseType>),
+}
+
+impl DatabaseType {
+    /// Returns `true` if every value of this type can be cast to `to` without losing data.
+    ///
+    /// Casts between `json` and `jsonb` are never lossless, as `jsonb` drops duplicate keys and the order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::DatabaseType;
+    /// assert!(DatabaseType::Integer.casts_losslessly_to(&DatabaseType::BigInt));
+    /// assert!(DatabaseType::Text(Some(40)).casts_losslessly_to(&DatabaseType::Text(Some(120))));
+    /// assert!(!DatabaseType::Text(Some(120)).casts_losslessly_to(&DatabaseType::Text(Some(40))));
+    /// assert!(!DatabaseType::BigInt.casts_losslessly_to(&DatabaseType::Integer));
+    /// assert!(DatabaseType::Numeric(Some((10, 2))).casts_losslessly_to(&DatabaseType::Numeric(Some((12, 4)))));
+    /// assert!(!DatabaseType::Numeric(Some((10, 2))).casts_losslessly_to(&DatabaseType::Numeric(Some((10, 4)))));
+    /// assert!(DatabaseType::Array(Box::new(DatabaseType::Integer))
+    ///     .casts_losslessly_to(&DatabaseType::Array(Box::new(DatabaseType::BigInt))));
+    /// ```
+    pub fn casts_losslessly_to(&self, to: &DatabaseType) -> bool {
+        use DatabaseType::*;
+
+        match (self, to) {
+            (from, to) if from == to => true,
+            // Other types are written differently as text, and can't be cast back as they were
+            (Text(_), Text(None)) => true,
+            (Text(Some(from)), Text(Some(to))) => from <= to,
+            (SmallInt, Integer | BigInt | Numeric(None) | Float | Double) => true,
+            (Integer, BigInt | Numeric(None) | Double) => true,
+            (BigInt, Numeric(None)) => true,
+            (Numeric(Some(_)), Numeric(None)) => true,
+            // Both the integer and fractional digits must fit
+            (
+                Numeric(Some((from_precision, from_scale))),
+                Numeric(Some((to_precision, to_scale))),
+            ) => {
+                // Postgres allows a scale greater than the precision, leaving no integer digits
+                let integer_digits = |precision: u16, scale: u16| precision.saturating_sub(scale);
+                to_scale >= from_scale
+                    && integer_digits(*to_precision, *to_scale)
+                        >= integer_digits(*from_precision, *from_scale)
+            }
+            (Float, Double) => true,
+            (Array(from), Array(to)) => from.casts_losslessly_to(to),
+            _ => false,
+        }
+    }
 }
 
 pub struct DatabaseTypeFromStrError;
@@ -34,7 +90,7 @@ impl str::FromStr for DatabaseType {
             "real" | "float4" => Self::Float,
             "double precision" | "float8" => Self::Double,
             "money" => Self::Money,
-            "character" | "char" | "character varying" | "charvar" => Self::Text(None),
+            "character" | "char" | "character varying" | "charvar" | "varchar" => Self::Text(None),
             "bytea" => Self::Binary,
             "timestamp" => Self::Timestamp,
             "timestamp with time zone" | "timestamptz" => Self::Timestamptz,
@@ -43,6 +99,8 @@ impl str::FromStr for DatabaseType {
             "time with time zone" | "timetz" => Self::Timetz,
             "boolean" | "bool" => Self::Bool,
             "uuid" => Self::Uuid,
+            "json" => Self::Json,
+            "jsonb" => Self::Jsonb,
             _ => return Err(DatabaseTypeFromStrError),
         };
         Ok(database_type)
@@ -70,6 +128,10 @@ impl fmt::Display for DatabaseType {
             Self::Timetz => write!(f, "time with time zone"),
             Self::Bool => write!(f, "boolean"),
             Self::Uuid => write!(f, "uuid"),
+            Self::Json => write!(f, "json"),
+            Self::Jsonb => write!(f, "jsonb"),
+            Self::Enum(name) => write!(f, "{}", name),
+            Self::Array(inner) => write!(f, "{}[]", inner),
         }
     }
 }
@@ -81,6 +143,10 @@ pub enum DatabaseDefault {
     Int(u64),
     Raw(String),
     String(String),
+    /// Integers generated from a sequence owned by the column, as Postgres `serial` columns are.
+    Serial,
+    /// Integers generated by the database, as identity or auto increment columns.
+    Identity,
 }
 
 impl PartialEq for DatabaseDefault {
@@ -106,6 +172,8 @@ impl PartialEq for DatabaseDefault {
                 DatabaseDefault::String(other) => v == other,
                 _ => false,
             },
+            DatabaseDefault::Serial => matches!(other, DatabaseDefault::Serial),
+            DatabaseDefault::Identity => matches!(other, DatabaseDefault::Identity),
         }
     }
 }
@@ -118,6 +186,8 @@ impl fmt::Display for DatabaseDefault {
             DatabaseDefault::Int(val) => write!(f, "{}", val),
             DatabaseDefault::Raw(val) => write!(f, "{}", val),
             DatabaseDefault::String(val) => write!(f, "\"{}\"", val),
+            DatabaseDefault::Serial => write!(f, "serial"),
+            DatabaseDefault::Identity => write!(f, "identity"),
         }
     }
 }
@@ -128,10 +198,170 @@ pub struct DatabaseColumn {
     pub ty: DatabaseType,
     pub nullable: bool,
     pub default: Option<DatabaseDefault>,
+    /// Whether the column is set to the current time whenever its row is updated, such as `updated_at`.
+    pub on_update_now: bool,
     pub unique: bool,
+    /// Expression of a check constraint on the column, such as `price >= 0`.
     pub constraint: Option<String>,
     pub primary_key: bool,
     pub references: Option<(String, String)>,
+    /// Previous names of the column, used to rename it rather than dropping it.
+    pub renamed_from: Vec<String>,
+}
+
+/// Access method used by an index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IndexMethod {
+    BTree,
+    Hash,
+    Gist,
+    SpGist,
+    Gin,
+    Brin,
+}
+
+// Deriving `Default` for enums requires Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl Default for IndexMethod {
+    fn default() -> Self {
+        Self::BTree
+    }
+}
+
+pub struct IndexMethodFromStrError;
+
+impl str::FromStr for IndexMethod {
+    type Err = IndexMethodFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let index_method = match s {
+            "btree" => Self::BTree,
+            "hash" => Self::Hash,
+            "gist" => Self::Gist,
+            "spgist" => Self::SpGist,
+            "gin" => Self::Gin,
+            "brin" => Self::Brin,
+            _ => return Err(IndexMethodFromStrError),
+        };
+        Ok(index_method)
+    }
+}
+
+impl fmt::Display for IndexMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BTree => write!(f, "btree"),
+            Self::Hash => write!(f, "hash"),
+            Self::Gist => write!(f, "gist"),
+            Self::SpGist => write!(f, "spgist"),
+            Self::Gin => write!(f, "gin"),
+            Self::Brin => write!(f, "brin"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct DatabaseIndex {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub method: IndexMethod,
+}
+
+impl DatabaseIndex {
+    /// Returns the default name of an index on columns of a table, such as `product_name_idx`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::DatabaseIndex;
+    /// assert_eq!(
+    ///     DatabaseIndex::default_name("product", &["name", "price"]),
+    ///     "product_name_price_idx"
+    /// );
+    /// ```
+    pub fn default_name(table: &str, columns: &[&str]) -> String {
+        format!("{}_{}_idx", table, columns.join("_"))
+    }
+}
+
+/// Kind of a table level constraint.
+///
+/// The [`Display`](fmt::Display) implementation writes the constraint as sql.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ConstraintKind {
+    /// Values must be unique across several columns.
+    ///
+    /// Single column unique constraints are stored as [`DatabaseColumn::unique`].
+    Unique(Vec<String>),
+    /// Rows must satisfy an expression.
+    ///
+    /// Check constraints on a single column are stored as [`DatabaseColumn::constraint`].
+    Check(String),
+    /// The primary key of the table.
+    ///
+    /// Primary keys are stored as [`DatabaseColumn::primary_key`],
+    /// so this only describes changes to the primary key, and the primary keys of tables fetched from Postgres.
+    PrimaryKey(Vec<String>),
+}
+
+impl fmt::Display for ConstraintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unique(columns) => write!(f, "UNIQUE ({})", columns.join(", ")),
+            Self::Check(expr) => write!(f, "CHECK ({})", expr),
+            Self::PrimaryKey(columns) => write!(f, "PRIMARY KEY ({})", columns.join(", ")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct DatabaseConstraint {
+    pub name: String,
+    pub kind: ConstraintKind,
+}
+
+impl DatabaseConstraint {
+    /// Returns the default name of a unique constraint on columns of a table, such as `product_tenant_id_slug_key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::DatabaseConstraint;
+    /// assert_eq!(
+    ///     DatabaseConstraint::default_unique_name("product", &["tenant_id", "slug"]),
+    ///     "product_tenant_id_slug_key"
+    /// );
+    /// ```
+    pub fn default_unique_name(table: &str, columns: &[&str]) -> String {
+        format!("{}_{}_key", table, columns.join("_"))
+    }
+
+    /// Returns the name of the check constraint on a column, such as `product_price_check`.
+    ///
+    /// This is the name Postgres gives a check constraint declared along with its column.
+    pub fn column_check_name(table: &str, column: &str) -> String {
+        format!("{}_{}_check", table, column)
+    }
+
+    /// Returns the name of the primary key of a table, such as `product_pkey`.
+    ///
+    /// This is the name Postgres gives a primary key, which is kept when the table is renamed.
+    pub fn primary_key_name(table: &str) -> String {
+        format!("{}_pkey", table)
+    }
+}
+
+pub trait IntoDatabaseEnum {
+    fn database_enum() -> DatabaseEnum;
+}
+
+/// A Postgres enum type, created from a fieldless Rust enum.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DatabaseEnum {
+    pub name: String,
+    /// Values of the enum, in the order of their variants.
+    pub values: Vec<String>,
 }
 
 pub trait IntoDatabaseTable {
@@ -142,6 +372,392 @@ pub trait IntoDatabaseTable {
 pub struct DatabaseTable {
     pub name: String,
     pub columns: Vec<DatabaseColumn>,
+    pub indexes: Vec<DatabaseIndex>,
+    pub constraints: Vec<DatabaseConstraint>,
+    /// Previous names of the table, used to rename it rather than creating a new one.
+    pub renamed_from: Vec<String>,
+}
+
+impl DatabaseTable {
+    /// Returns the names of the columns making up the primary key, in the order of the columns.
+    pub fn primary_key(&self) -> Vec<&str> {
+        self.columns
+            .iter()
+            .filter(|column| column.primary_key)
+            .map(|column| column.name.as_str())
+            .collect()
+    }
+}
+
+/// Changes needed to bring a database in sync with the schema.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct SchemaDiff {
+    pub enums: Vec<EnumChange>,
+    pub tables: Vec<TableChange>,
+}
+
+impl SchemaDiff {
+    /// Returns `true` if the database is already in sync with the schema.
+    pub fn is_empty(&self) -> bool {
+        self.enums.is_empty() && self.tables.is_empty()
+    }
+
+    /// Returns the changes which undo this diff.
+    pub fn inverse(&self) -> SchemaDiff {
+        SchemaDiff {
+            enums: self
+                .enums
+                .iter()
+                .rev()
+                .filter_map(EnumChange::inverse)
+                .collect(),
+            tables: self.tables.iter().rev().map(TableChange::inverse).collect(),
+        }
+    }
+
+    /// Returns `true` if every change can be undone without losing data.
+    pub fn is_reversible(&self) -> bool {
+        self.tables.iter().all(TableChange::is_reversible)
+    }
+}
+
+/// A change to a single enum type.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EnumChange {
+    /// The enum does not exist in the database and will be created.
+    Create(DatabaseEnum),
+    /// The enum exists in the database but will be dropped.
+    Drop(DatabaseEnum),
+    /// The enum exists in the database, but is missing values which will be appended.
+    ///
+    /// Values which no longer exist in the schema are left in the database, as Postgres can't remove them.
+    AddValues { name: String, values: Vec<String> },
+}
+
+impl EnumChange {
+    /// Returns the name of the enum being changed.
+    pub fn enum_name(&self) -> &str {
+        match self {
+            EnumChange::Create(database_enum) | EnumChange::Drop(database_enum) => {
+                &database_enum.name
+            }
+            EnumChange::AddValues { name, .. } => name,
+        }
+    }
+
+    /// Returns the change which undoes this change.
+    ///
+    /// Postgres can't remove values from an enum, so added values have no inverse and are left in place.
+    pub fn inverse(&self) -> Option<EnumChange> {
+        match self {
+            EnumChange::Create(database_enum) => Some(EnumChange::Drop(database_enum.clone())),
+            EnumChange::Drop(database_enum) => Some(EnumChange::Create(database_enum.clone())),
+            EnumChange::AddValues { .. } => None,
+        }
+    }
+}
+
+/// A change to a single table.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TableChange {
+    /// The table does not exist in the database and will be created.
+    Create(DatabaseTable),
+    /// The table exists in the database under one of its previous names, and will be renamed.
+    ///
+    /// Changes to its columns follow in a separate [`TableChange::Alter`].
+    /// Constraints and triggers named after the previous name are dropped before it in another,
+    /// and added again under the new name.
+    Rename { from: String, to: String },
+    /// The table exists in the database but not in the schema, and will be dropped.
+    Drop(DatabaseTable),
+    /// The table exists in the database, but its columns, indexes or constraints differ from the schema.
+    Alter {
+        table: String,
+        columns: Vec<ColumnChange>,
+        indexes: Vec<IndexChange>,
+        constraints: Vec<ConstraintChange>,
+    },
+}
+
+impl TableChange {
+    /// Returns the name of the table being changed.
+    pub fn table_name(&self) -> &str {
+        match self {
+            TableChange::Create(table) => &table.name,
+            TableChange::Rename { to, .. } => to,
+            TableChange::Drop(table) => &table.name,
+            TableChange::Alter { table, .. } => table,
+        }
+    }
+
+    /// Returns the change which undoes this change.
+    ///
+    /// The inverse of dropping a table recreates it without its data.
+    pub fn inverse(&self) -> TableChange {
+        match self {
+            TableChange::Create(table) => TableChange::Drop(table.clone()),
+            TableChange::Rename { from, to } => TableChange::Rename {
+                from: to.clone(),
+                to: from.clone(),
+            },
+            TableChange::Drop(table) => TableChange::Create(table.clone()),
+            TableChange::Alter {
+                table,
+                columns,
+                indexes,
+                constraints,
+            } => TableChange::Alter {
+                table: table.clone(),
+                columns: columns.iter().rev().map(ColumnChange::inverse).collect(),
+                indexes: indexes.iter().rev().map(IndexChange::inverse).collect(),
+                constraints: constraints
+                    .iter()
+                    .rev()
+                    .map(ConstraintChange::inverse)
+                    .collect(),
+            },
+        }
+    }
+
+    /// Returns `true` if the change can be undone without losing data.
+    pub fn is_reversible(&self) -> bool {
+        match self {
+            TableChange::Drop(_) => false,
+            TableChange::Alter { columns, .. } => columns.iter().all(ColumnChange::is_reversible),
+            _ => true,
+        }
+    }
+}
+
+/// A change to an index of an existing table.
+///
+/// Indexes which differ from the schema are dropped and created again.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum IndexChange {
+    /// The index does not exist in the database and will be created.
+    Create(DatabaseIndex),
+    /// The index exists in the database but not in the schema, and will be dropped.
+    Drop(DatabaseIndex),
+}
+
+impl IndexChange {
+    /// Returns the change which undoes this change.
+    pub fn inverse(&self) -> IndexChange {
+        match self {
+            IndexChange::Create(index) => IndexChange::Drop(index.clone()),
+            IndexChange::Drop(index) => IndexChange::Create(index.clone()),
+        }
+    }
+
+    /// Returns the name of the index being changed.
+    pub fn index_name(&self) -> &str {
+        match self {
+            IndexChange::Create(index) | IndexChange::Drop(index) => &index.name,
+        }
+    }
+}
+
+/// A change to a table level constraint of an existing table.
+///
+/// Constraints which differ from the schema are dropped and added again.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ConstraintChange {
+    /// The constraint does not exist in the database and will be added.
+    Add(DatabaseConstraint),
+    /// The constraint exists in the database but not in the schema, and will be dropped.
+    Drop(DatabaseConstraint),
+}
+
+impl ConstraintChange {
+    /// Returns the change which undoes this change.
+    pub fn inverse(&self) -> ConstraintChange {
+        match self {
+            ConstraintChange::Add(constraint) => ConstraintChange::Drop(constraint.clone()),
+            ConstraintChange::Drop(constraint) => ConstraintChange::Add(constraint.clone()),
+        }
+    }
+
+    /// Returns the name of the constraint being changed.
+    pub fn constraint_name(&self) -> &str {
+        match self {
+            ConstraintChange::Add(constraint) | ConstraintChange::Drop(constraint) => {
+                &constraint.name
+            }
+        }
+    }
+}
+
+/// A change to a single column of an existing table.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ColumnChange {
+    /// The column does not exist in the database and will be added.
+    Add(DatabaseColumn),
+    /// The column exists in the database but not in the schema, and will be dropped.
+    Drop(DatabaseColumn),
+    /// The column exists in the database under one of its previous names, and will be renamed.
+    Rename {
+        from: String,
+        to: String,
+    },
+    ChangeType {
+        column: String,
+        from: DatabaseType,
+        to: DatabaseType,
+    },
+    SetDefault {
+        column: String,
+        from: Option<DatabaseDefault>,
+        to: DatabaseDefault,
+    },
+    DropDefault {
+        column: String,
+        from: DatabaseDefault,
+    },
+    SetNotNull {
+        column: String,
+    },
+    DropNotNull {
+        column: String,
+    },
+    /// The column will be set to the current time whenever its row is updated.
+    SetOnUpdateNow {
+        column: String,
+    },
+    DropOnUpdateNow {
+        column: String,
+    },
+    AddUnique {
+        column: String,
+    },
+    DropUnique {
+        column: String,
+    },
+    AddForeignKey {
+        column: String,
+        references: (String, String),
+    },
+    DropForeignKey {
+        column: String,
+        references: (String, String),
+    },
+    AddCheck {
+        column: String,
+        check: String,
+    },
+    DropCheck {
+        column: String,
+        check: String,
+    },
+}
+
+impl ColumnChange {
+    /// Returns `true` if applying the change can lose data.
+    ///
+    /// Dropping a column and casting a column to a type which can't hold all of its values are destructive.
+    /// Whether setting a column to not null is destructive depends on the data in the table,
+    /// so it is not considered here.
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            ColumnChange::Drop(_) => true,
+            ColumnChange::ChangeType { from, to, .. } => !from.casts_losslessly_to(to),
+            _ => false,
+        }
+    }
+
+    /// Returns the change which undoes this change.
+    ///
+    /// The inverse of dropping a column adds it back without its data,
+    /// and the inverse of a lossy type change can't restore the lost values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use awto::database::{ColumnChange, DatabaseDefault};
+    /// let change = ColumnChange::SetDefault {
+    ///     column: "price".to_string(),
+    ///     from: None,
+    ///     to: DatabaseDefault::Int(0),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     change.inverse(),
+    ///     ColumnChange::DropDefault {
+    ///         column: "price".to_string(),
+    ///         from: DatabaseDefault::Int(0),
+    ///     }
+    /// );
+    /// ```
+    pub fn inverse(&self) -> ColumnChange {
+        match self.clone() {
+            ColumnChange::Add(column) => ColumnChange::Drop(column),
+            ColumnChange::Drop(column) => ColumnChange::Add(column),
+            ColumnChange::Rename { from, to } => ColumnChange::Rename { from: to, to: from },
+            ColumnChange::ChangeType { column, from, to } => ColumnChange::ChangeType {
+                column,
+                from: to,
+                to: from,
+            },
+            ColumnChange::SetDefault {
+                column,
+                from: Some(from),
+                to,
+            } => ColumnChange::SetDefault {
+                column,
+                from: Some(to),
+                to: from,
+            },
+            ColumnChange::SetDefault {
+                column,
+                from: None,
+                to,
+            } => ColumnChange::DropDefault { column, from: to },
+            ColumnChange::DropDefault { column, from } => ColumnChange::SetDefault {
+                column,
+                from: None,
+                to: from,
+            },
+            ColumnChange::SetNotNull { column } => ColumnChange::DropNotNull { column },
+            ColumnChange::DropNotNull { column } => ColumnChange::SetNotNull { column },
+            ColumnChange::SetOnUpdateNow { column } => ColumnChange::DropOnUpdateNow { column },
+            ColumnChange::DropOnUpdateNow { column } => ColumnChange::SetOnUpdateNow { column },
+            ColumnChange::AddUnique { column } => ColumnChange::DropUnique { column },
+            ColumnChange::DropUnique { column } => ColumnChange::AddUnique { column },
+            ColumnChange::AddForeignKey { column, references } => {
+                ColumnChange::DropForeignKey { column, references }
+            }
+            ColumnChange::DropForeignKey { column, references } => {
+                ColumnChange::AddForeignKey { column, references }
+            }
+            ColumnChange::AddCheck { column, check } => ColumnChange::DropCheck { column, check },
+            ColumnChange::DropCheck { column, check } => ColumnChange::AddCheck { column, check },
+        }
+    }
+
+    /// Returns `true` if the change can be undone without losing data.
+    pub fn is_reversible(&self) -> bool {
+        !self.is_destructive()
+    }
+
+    /// Returns the name of the column being changed.
+    pub fn column_name(&self) -> &str {
+        match self {
+            ColumnChange::Add(column) | ColumnChange::Drop(column) => &column.name,
+            ColumnChange::Rename { to, .. } => to,
+            ColumnChange::ChangeType { column, .. }
+            | ColumnChange::SetDefault { column, .. }
+            | ColumnChange::DropDefault { column, .. }
+            | ColumnChange::SetNotNull { column }
+            | ColumnChange::DropNotNull { column }
+            | ColumnChange::SetOnUpdateNow { column }
+            | ColumnChange::DropOnUpdateNow { column }
+            | ColumnChange::AddUnique { column }
+            | ColumnChange::DropUnique { column }
+            | ColumnChange::AddForeignKey { column, .. }
+            | ColumnChange::DropForeignKey { column, .. }
+            | ColumnChange::AddCheck { column, .. }
+            | ColumnChange::DropCheck { column, .. } => column,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -154,6 +770,252 @@ mod test {
         assert_eq!(Product::database_table().name, "product");
     }
 
+    #[test]
+    fn custom_table_name() {
+        let table = Review::database_table();
+        assert_eq!(table.name, "product_reviews");
+        assert_eq!(table.renamed_from, vec!["review".to_string()]);
+
+        let product_id = table
+            .columns
+            .iter()
+            .find(|column| column.name == "product_id")
+            .unwrap();
+        assert_eq!(
+            product_id.references,
+            Some(("product".to_string(), "id".to_string()))
+        );
+    }
+
+    #[test]
+    fn enums() {
+        assert_eq!(
+            ReviewStatus::database_enum(),
+            DatabaseEnum {
+                name: "review_status".to_string(),
+                values: vec![
+                    "pending".to_string(),
+                    "published".to_string(),
+                    "hidden".to_string()
+                ],
+            }
+        );
+
+        let status = Review::database_table()
+            .columns
+            .into_iter()
+            .find(|column| column.name == "status")
+            .unwrap();
+        assert_eq!(status.ty, DatabaseType::Enum("review_status".to_string()));
+        assert!(!status.nullable);
+    }
+
+    #[test]
+    fn arrays() {
+        let columns = Review::database_table().columns;
+        let images = columns
+            .iter()
+            .find(|column| column.name == "images")
+            .unwrap();
+        assert_eq!(
+            images.ty,
+            DatabaseType::Array(Box::new(DatabaseType::Text(None)))
+        );
+        assert!(images.nullable);
+        assert_eq!(images.ty.to_string(), "character varying[]");
+
+        let helpful_votes = columns
+            .iter()
+            .find(|column| column.name == "helpful_votes")
+            .unwrap();
+        assert_eq!(
+            helpful_votes.ty,
+            DatabaseType::Array(Box::new(DatabaseType::BigInt))
+        );
+        assert_eq!(helpful_votes.ty.to_string(), "bigint[]");
+    }
+
+    #[test]
+    fn json() {
+        let tags = Review::database_table()
+            .columns
+            .into_iter()
+            .find(|column| column.name == "tags")
+            .unwrap();
+        assert_eq!(tags.ty, DatabaseType::Jsonb);
+        assert!(!tags.nullable);
+        assert!(matches!("jsonb".parse(), Ok(DatabaseType::Jsonb)));
+    }
+
+    #[test]
+    fn lossless_casts() {
+        assert!(DatabaseType::Text(Some(40)).casts_losslessly_to(&DatabaseType::Text(None)));
+        assert!(!DatabaseType::Integer.casts_losslessly_to(&DatabaseType::Text(None)));
+        assert!(!DatabaseType::Timestamptz.casts_losslessly_to(&DatabaseType::Text(None)));
+        assert!(!DatabaseType::Json.casts_losslessly_to(&DatabaseType::Jsonb));
+        assert!(!DatabaseType::Jsonb.casts_losslessly_to(&DatabaseType::Json));
+    }
+
+    #[test]
+    fn indexes() {
+        assert_eq!(
+            Product::database_table().indexes,
+            vec![DatabaseIndex {
+                name: "product_name_idx".to_string(),
+                columns: vec!["name".to_string()],
+                unique: false,
+                method: IndexMethod::BTree,
+            }]
+        );
+        assert_eq!(
+            Review::database_table().indexes,
+            vec![DatabaseIndex {
+                name: "product_reviews_product_id_rating_idx".to_string(),
+                columns: vec!["product_id".to_string(), "rating".to_string()],
+                unique: false,
+                method: IndexMethod::BTree,
+            }]
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        assert_eq!(Product::database_table().constraints, vec![]);
+        assert_eq!(
+            Review::database_table().constraints,
+            vec![
+                DatabaseConstraint {
+                    name: "product_reviews_product_id_author_key".to_string(),
+                    kind: ConstraintKind::Unique(vec![
+                        "product_id".to_string(),
+                        "author".to_string()
+                    ]),
+                },
+                DatabaseConstraint {
+                    name: "product_reviews_rating_range".to_string(),
+                    kind: ConstraintKind::Check("rating BETWEEN 1 AND 5".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_keys() {
+        assert_eq!(Product::database_table().primary_key(), vec!["id"]);
+
+        let tag = Tag::database_table();
+        assert_eq!(tag.primary_key(), vec!["id"]);
+        assert_eq!(tag.columns[0].ty, DatabaseType::BigInt);
+        assert_eq!(tag.columns[0].default, Some(DatabaseDefault::Identity));
+
+        let product_tag = ProductTag::database_table();
+        assert_eq!(product_tag.primary_key(), vec!["product_id", "tag_id"]);
+        assert_eq!(product_tag.columns[0].default, None);
+    }
+
+    #[test]
+    fn timestamps() {
+        let now = Some(DatabaseDefault::Raw("NOW()".to_string()));
+
+        let tag = Tag::database_table();
+        let column_names: Vec<_> = tag
+            .columns
+            .iter()
+            .map(|column| column.name.as_str())
+            .collect();
+        assert_eq!(
+            column_names,
+            vec!["id", "inserted_at", "updated_at", "name"]
+        );
+        assert_eq!(tag.columns[1].default, now);
+        assert_eq!(tag.columns[2].default, now);
+
+        let product_tag = ProductTag::database_table();
+        assert!(product_tag
+            .columns
+            .iter()
+            .all(|column| column.default.is_none()));
+    }
+
+    #[test]
+    fn inverse_diff() {
+        let diff = SchemaDiff {
+            enums: vec![EnumChange::Create(ReviewStatus::database_enum())],
+            tables: vec![
+                TableChange::Rename {
+                    from: "product".to_string(),
+                    to: "products".to_string(),
+                },
+                TableChange::Alter {
+                    table: "products".to_string(),
+                    columns: vec![
+                        ColumnChange::Rename {
+                            from: "cost".to_string(),
+                            to: "price".to_string(),
+                        },
+                        ColumnChange::SetNotNull {
+                            column: "price".to_string(),
+                        },
+                    ],
+                    indexes: vec![IndexChange::Create(DatabaseIndex {
+                        name: "products_price_idx".to_string(),
+                        columns: vec!["price".to_string()],
+                        unique: false,
+                        method: IndexMethod::BTree,
+                    })],
+                    constraints: vec![],
+                },
+            ],
+        };
+
+        assert_eq!(
+            diff.inverse(),
+            SchemaDiff {
+                enums: vec![EnumChange::Drop(ReviewStatus::database_enum())],
+                tables: vec![
+                    TableChange::Alter {
+                        table: "products".to_string(),
+                        columns: vec![
+                            ColumnChange::DropNotNull {
+                                column: "price".to_string(),
+                            },
+                            ColumnChange::Rename {
+                                from: "price".to_string(),
+                                to: "cost".to_string(),
+                            },
+                        ],
+                        indexes: vec![IndexChange::Drop(DatabaseIndex {
+                            name: "products_price_idx".to_string(),
+                            columns: vec!["price".to_string()],
+                            unique: false,
+                            method: IndexMethod::BTree,
+                        })],
+                        constraints: vec![],
+                    },
+                    TableChange::Rename {
+                        from: "products".to_string(),
+                        to: "product".to_string(),
+                    },
+                ],
+            }
+        );
+        assert_eq!(diff.inverse().inverse(), diff);
+        assert!(diff.is_reversible());
+
+        let drop_table = TableChange::Drop(Product::database_table());
+        assert!(!drop_table.is_reversible());
+        assert_eq!(
+            drop_table.inverse(),
+            TableChange::Create(Product::database_table())
+        );
+
+        let add_values = EnumChange::AddValues {
+            name: "review_status".to_string(),
+            values: vec!["flagged".to_string()],
+        };
+        assert_eq!(add_values.inverse(), None);
+    }
+
     #[test]
     fn columns() {
         let columns = Product::database_table().columns;
@@ -163,60 +1025,72 @@ mod test {
                 ty: DatabaseType::Uuid,
                 nullable: false,
                 default: Some(DatabaseDefault::Raw("uuid_generate_v4()".to_string())),
+                on_update_now: false,
                 unique: false,
                 constraint: None,
                 primary_key: true,
                 references: None,
+                renamed_from: vec![],
             },
             DatabaseColumn {
                 name: "created_at".to_string(),
                 ty: DatabaseType::Timestamptz,
                 nullable: false,
                 default: Some(DatabaseDefault::Raw("NOW()".to_string())),
+                on_update_now: false,
                 unique: false,
                 constraint: None,
                 primary_key: false,
                 references: None,
+                renamed_from: vec![],
             },
             DatabaseColumn {
                 name: "updated_at".to_string(),
                 ty: DatabaseType::Timestamptz,
                 nullable: false,
                 default: Some(DatabaseDefault::Raw("NOW()".to_string())),
+                on_update_now: true,
                 unique: false,
                 constraint: None,
                 primary_key: false,
                 references: None,
+                renamed_from: vec![],
             },
             DatabaseColumn {
                 name: "name".to_string(),
                 ty: DatabaseType::Text(None),
                 nullable: false,
                 default: None,
+                on_update_now: false,
                 unique: false,
                 constraint: None,
                 primary_key: false,
                 references: None,
+                renamed_from: vec![],
             },
             DatabaseColumn {
                 name: "price".to_string(),
                 ty: DatabaseType::BigInt,
                 nullable: false,
                 default: Some(DatabaseDefault::Int(0)),
+                on_update_now: false,
                 unique: false,
-                constraint: None,
+                constraint: Some("price >= 0".to_string()),
                 primary_key: false,
                 references: None,
+                renamed_from: vec![],
             },
             DatabaseColumn {
                 name: "description".to_string(),
                 ty: DatabaseType::Text(Some(120)),
                 nullable: true,
                 default: None,
+                on_update_now: false,
                 unique: false,
                 constraint: None,
                 primary_key: false,
                 references: None,
+                renamed_from: vec!["summary".to_string()],
             },
         ];
         assert_eq!(columns, expected);
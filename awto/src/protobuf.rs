@@ -22,7 +22,11 @@ pub enum ProtobufType {
     Bytes,
     Repeated(Box<ProtobufType>),
     Timestamp,
+    /// A JSON value, sent as a string.
+    Json,
     Custom(ProtobufMessage),
+    Enum(ProtobufEnum),
+    Oneof(ProtobufOneof),
 }
 
 pub struct ProtobufTypeFromStrError;
@@ -77,7 +81,10 @@ impl fmt::Display for ProtobufType {
             Self::Bytes => write!(f, "bytes"),
             Self::Repeated(inner) => write!(f, "repeated {}", inner),
             Self::Timestamp => write!(f, "google.protobuf.Timestamp"),
+            Self::Json => write!(f, "string"),
             Self::Custom(inner) => write!(f, "{}", inner.name),
+            Self::Enum(inner) => write!(f, "{}", inner.name),
+            Self::Oneof(inner) => write!(f, "{}", inner.name),
         }
     }
 }
@@ -119,6 +126,41 @@ pub struct ProtobufMessage {
     pub fields: Vec<ProtobufField>,
 }
 
+/// A proto3 enum, created from a fieldless Rust enum.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtobufEnum {
+    pub name: String,
+    /// Values of the enum in screaming snake case, in the order of their variants.
+    ///
+    /// Values are written prefixed by the enum name, after the zero `UNSPECIFIED` value.
+    pub values: Vec<String>,
+}
+
+pub trait IntoProtobufEnum {
+    fn protobuf_enum() -> ProtobufEnum;
+}
+
+/// A message holding a single `oneof`, created from a Rust enum with data-carrying variants.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtobufOneof {
+    pub name: String,
+    pub variants: Vec<ProtobufOneofVariant>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtobufOneofVariant {
+    /// Name of the variant's field in the oneof, in snake case.
+    pub name: String,
+    pub ty: ProtobufType,
+    /// Whether `ty` is a [`ProtobufType::Custom`] message nested in the oneof's message,
+    /// created from the fields of a unit or struct variant.
+    pub nested: bool,
+}
+
+pub trait IntoProtobufOneof {
+    fn protobuf_oneof() -> ProtobufOneof;
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ProtobufService {
     pub methods: Vec<ProtobufMethod>,
@@ -137,6 +179,86 @@ mod test {
         assert_eq!(Product::protobuf_message().name, "Product");
     }
 
+    #[test]
+    fn enums() {
+        assert_eq!(
+            ReviewStatus::protobuf_enum(),
+            ProtobufEnum {
+                name: "ReviewStatus".to_string(),
+                values: vec![
+                    "PENDING".to_string(),
+                    "PUBLISHED".to_string(),
+                    "HIDDEN".to_string()
+                ],
+            }
+        );
+        assert_eq!(
+            ReviewQuery::protobuf_message().fields[1],
+            ProtobufField {
+                name: "status".to_string(),
+                ty: ProtobufType::Enum(ReviewStatus::protobuf_enum()),
+                required: false,
+            }
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            ReviewQuery::protobuf_message().fields[2],
+            ProtobufField {
+                name: "tags".to_string(),
+                ty: ProtobufType::Json,
+                required: false,
+            }
+        );
+    }
+
+    #[test]
+    fn oneofs() {
+        assert_eq!(
+            ProductLookup::protobuf_oneof(),
+            ProtobufOneof {
+                name: "ProductLookup".to_string(),
+                variants: vec![
+                    ProtobufOneofVariant {
+                        name: "id".to_string(),
+                        ty: ProtobufType::String,
+                        nested: false,
+                    },
+                    ProtobufOneofVariant {
+                        name: "name".to_string(),
+                        ty: ProtobufType::String,
+                        nested: false,
+                    },
+                    ProtobufOneofVariant {
+                        name: "price".to_string(),
+                        ty: ProtobufType::Custom(ProtobufMessage {
+                            name: "Price".to_string(),
+                            fields: vec![
+                                ProtobufField {
+                                    name: "min".to_string(),
+                                    ty: ProtobufType::Int64,
+                                    required: true,
+                                },
+                                ProtobufField {
+                                    name: "max".to_string(),
+                                    ty: ProtobufType::Int64,
+                                    required: false,
+                                },
+                            ],
+                        }),
+                        nested: true,
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            ProductSearch::protobuf_message().fields[0].ty,
+            ProtobufType::Oneof(ProductLookup::protobuf_oneof())
+        );
+    }
+
     #[test]
     fn columns() {
         let fields = Product::protobuf_message().fields;
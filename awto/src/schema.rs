@@ -1,5 +1,5 @@
-use crate::database::DatabaseTable;
-use crate::protobuf::ProtobufMessage;
+use crate::database::{DatabaseEnum, DatabaseTable};
+use crate::protobuf::{ProtobufEnum, ProtobufMessage, ProtobufOneof};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Role {
@@ -9,8 +9,17 @@ pub enum Role {
     /// An item containing some fields of an existing DatabaseTable item, typically used for inserts or updates
     DatabaseSubTable(DatabaseTable),
 
+    /// An enum which will be used to create a database enum type
+    DatabaseEnum(DatabaseEnum),
+
     /// An item which will be used as a protobuf message
     ProtobufMessage(ProtobufMessage),
+
+    /// An enum which will be used as a protobuf enum
+    ProtobufEnum(ProtobufEnum),
+
+    /// An enum with data-carrying variants which will be used as a protobuf message with a oneof
+    ProtobufOneof(ProtobufOneof),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -19,9 +28,18 @@ pub struct RustField {
     pub ty: String,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct RustVariant {
+    pub name: String,
+    /// Fields of the variant, named by their index in tuple variants.
+    pub fields: Vec<RustField>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Model {
     pub name: String,
     pub roles: Vec<Role>,
     pub fields: Vec<RustField>,
+    /// Variants of an enum, empty for structs.
+    pub variants: Vec<RustVariant>,
 }
@@ -10,13 +10,63 @@ schema! {
         pub id: Uuid,
         pub created_at: DateTime<FixedOffset>,
         pub updated_at: DateTime<FixedOffset>,
+        #[awto(index)]
         pub name: String,
-        #[awto(default = 0)]
+        #[awto(default = 0, check = "price >= 0")]
         pub price: i64,
-        #[awto(max_len = 120)]
+        #[awto(max_len = 120, renamed_from = "summary")]
         pub description: Option<String>,
     }
 
+    #[database_table]
+    #[awto(table = "product_reviews", renamed_from = "review")]
+    #[awto(index(columns = ["product_id", "rating"]))]
+    #[awto(unique(columns = ["product_id", "author"]))]
+    #[awto(check(name = "product_reviews_rating_range", expr = "rating BETWEEN 1 AND 5"))]
+    pub struct Review {
+        pub id: Uuid,
+        pub created_at: DateTime<FixedOffset>,
+        pub updated_at: DateTime<FixedOffset>,
+        #[awto(references = (Product, "id"))]
+        pub product_id: Uuid,
+        pub author: String,
+        pub rating: i32,
+        pub status: ReviewStatus,
+        #[awto(json)]
+        pub tags: Vec<String>,
+        pub images: Option<Vec<String>>,
+        pub helpful_votes: Vec<i64>,
+    }
+
+    #[database_table]
+    #[awto(created_at = "inserted_at")]
+    pub struct Tag {
+        #[awto(primary_key, identity)]
+        pub id: i64,
+        pub inserted_at: DateTime<FixedOffset>,
+        pub updated_at: DateTime<FixedOffset>,
+        #[awto(unique, max_len = 40)]
+        pub name: String,
+    }
+
+    #[database_table]
+    #[awto(timestamps = false)]
+    pub struct ProductTag {
+        #[awto(primary_key, references = (Product, "id"))]
+        pub product_id: Uuid,
+        #[awto(primary_key, references = (Tag, "id"))]
+        pub tag_id: i64,
+    }
+
+    #[database_enum]
+    #[protobuf_enum]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ReviewStatus {
+        Pending,
+        Published,
+        Hidden,
+    }
+
     #[protobuf_message]
     pub struct ProductId {
         pub id: Uuid,
@@ -27,6 +77,27 @@ schema! {
         pub products: Vec<Product>,
     }
 
+    #[protobuf_message]
+    pub struct ReviewQuery {
+        pub product_id: Uuid,
+        pub status: Option<ReviewStatus>,
+        #[awto(json)]
+        pub tags: Option<Vec<String>>,
+    }
+
+    #[protobuf_oneof]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ProductLookup {
+        Id(Uuid),
+        Name(String),
+        Price { min: i64, max: Option<i64> },
+    }
+
+    #[protobuf_message]
+    pub struct ProductSearch {
+        pub lookup: ProductLookup,
+    }
+
     #[protobuf_message]
     #[database_sub_table(Product)]
     pub struct NewProduct {
@@ -39,22 +110,31 @@ schema! {
 #[derive(Default)]
 pub struct ProductService;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProductNotFound;
+
+impl From<ProductNotFound> for Status {
+    fn from(_: ProductNotFound) -> Self {
+        Status::not_found("resouce not found")
+    }
+}
+
 #[protobuf_service]
 impl ProductService {
-    pub fn find_product(&self, request: ProductId) -> Result<ProductList, Status> {
+    pub fn find_product(&self, request: ProductId) -> Result<ProductList, ProductNotFound> {
         if request.id == Uuid::default() {
             Ok(ProductList {
                 products: vec![Product {
                     id: Uuid::default(),
-                    created_at: Local::now().with_timezone(&FixedOffset::east(0)),
-                    updated_at: Local::now().with_timezone(&FixedOffset::east(0)),
+                    created_at: Local::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
+                    updated_at: Local::now().with_timezone(&FixedOffset::east_opt(0).unwrap()),
                     name: "1".to_string(),
                     price: 20,
                     description: None,
                 }],
             })
         } else {
-            Err(Status::not_found("resouce not found"))
+            Err(ProductNotFound)
         }
     }
 }
@@ -1,16 +1,45 @@
 use std::{env, error};
 
 use awto::schema::Role;
-use awto_compile::database::compile_database;
+use awto_compile::database::{compile_database_with_options, CompileDatabaseOptions};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn error::Error>> {
     dotenv::dotenv().ok();
 
+    // Listing any rerun-if lines replaces cargo's default of rerunning when the package changes
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=../../schema/src");
+    println!("cargo:rerun-if-env-changed=AWTO_ALLOW_DESTRUCTIVE");
+    println!("cargo:rerun-if-env-changed=AWTO_DRY_RUN");
+    println!("cargo:rerun-if-env-changed=AWTO_MIGRATIONS_DIR");
+    println!("cargo:rerun-if-env-changed=AWTO_REMOVED_TABLES");
+    println!("cargo:rerun-if-env-changed=DATABASE_SCHEMA");
+    println!("cargo:rerun-if-env-changed=DATABASE_URL");
+    if let Some(migrations_dir) = env::var_os("AWTO_MIGRATIONS_DIR") {
+        println!("cargo:rerun-if-changed={}", migrations_dir.to_string_lossy());
+    }
+
     let pg_schema = env::var("DATABASE_SCHEMA").unwrap_or_else(|_| "public".to_string());
     let uri = env::var("DATABASE_URL").expect("missing env DATABASE_URL");
 
-    compile_database(&uri, schema::MODELS.to_vec()).await?;
+    let result = compile_database_with_options(
+        &uri,
+        schema::MODELS.to_vec(),
+        CompileDatabaseOptions::from_env(),
+    )
+    .await?;
+    // Dry runs leave the database and the generated code untouched
+    if result.plan.is_some() {
+        return Ok(());
+    }
+
+    for table in &result.removed_tables {
+        println!(
+            "cargo:warning=table '{}' has been removed from the schema",
+            table
+        );
+    }
 
     sea_orm_build::generate_models(
         &pg_schema,
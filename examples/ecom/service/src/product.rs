@@ -1,7 +1,10 @@
+use std::convert::TryInto;
+
 use awto::macros::protobuf_service;
 use database::{
     product,
     sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel},
+    TryFromDatabaseError,
 };
 use schema::*;
 use tonic::Status;
@@ -32,7 +35,9 @@ impl ProductService {
             .map_err(|err| Status::internal(err.to_string()))?
             .ok_or_else(|| Status::not_found("product not found"))?;
 
-        Ok(product.into())
+        product
+            .try_into()
+            .map_err(|err: TryFromDatabaseError| Status::internal(err.to_string()))
     }
 
     pub async fn list_products(&self, _request: Empty) -> Result<ProductList, Status> {
@@ -41,8 +46,9 @@ impl ProductService {
             .await
             .map_err(|err| Status::internal(err.to_string()))?
             .into_iter()
-            .map(|product| product.into())
-            .collect();
+            .map(|product| product.try_into())
+            .collect::<Result<_, TryFromDatabaseError>>()
+            .map_err(|err| Status::internal(err.to_string()))?;
 
         Ok(ProductList { products })
     }